@@ -0,0 +1,24 @@
+use std::process::Command;
+
+//embed the git sha so events can be tied to the monitor build that wrote them
+fn main() {
+    println!("cargo:rerun-if-env-changed=BITAXE_BUILD_SHA");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    //an explicit BITAXE_BUILD_SHA wins (e.g. CI builds from a tarball without .git)
+    let sha = std::env::var("BITAXE_BUILD_SHA")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .or_else(|| {
+            Command::new("git")
+                .args(["rev-parse", "--short=12", "HEAD"])
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+                .and_then(|o| String::from_utf8(o.stdout).ok())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=BITAXE_BUILD_SHA={}", sha);
+}
@@ -1,35 +1,365 @@
 use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::ffi::OsString;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpConfig {
     pub endpoint_url: String,
     pub headers: Option<HashMap<String, String>>, //use for auth tokens if needed
+    // optional: environment variable holding a token sent as "Authorization: Bearer <token>", so the
+    // secret stays out of the config file
+    pub auth_token_env: Option<String>,
+    // optional: print only the scheme and a partial host hint of endpoint_url at startup (default true)
+    pub mask_endpoint: Option<bool>,
     pub timeout_secs: Option<u64>,
+    // optional cap on total time for one poll across all retries and backoff sleeps
+    pub poll_budget_secs: Option<u64>,
+    // optional retry tuning; defaults are 3 retries from 500ms when polling and 2 from 300ms at preflight
+    pub retry: Option<RetryConfig>,
+    // optional: resolve the endpoint host once at startup and reuse that address (re-resolved after a failed poll)
+    pub pin_resolved_ip: Option<bool>,
+    // optional connection pool tuning; unset values keep reqwest's defaults (unbounded idle
+    // connections, 90s idle timeout, no tcp keepalive). 0 idle connections disables reuse
+    pub pool_max_idle_per_host: Option<usize>,
+    pub pool_idle_timeout_secs: Option<u64>,
+    pub tcp_keepalive_secs: Option<u64>,
+    // optional: "GET" (default) or "POST" for endpoints that only answer posted requests
+    pub method: Option<HttpMethod>,
+    // optional: request body sent with POST as application/json (e.g. login fields for request-based auth)
+    pub body: Option<String>,
+    // optional: Accept header sent with every request, e.g. to ask the device for a compact variant
+    pub accept: Option<String>,
+    // optional: fail the attempt once the response body grows past this many bytes instead of reading
+    // it all into memory
+    pub max_response_bytes: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    Post,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryConfig {
+    pub max_retries: Option<usize>,
+    pub base_delay_ms: Option<u64>,
+    // upper bound for a single backoff sleep
+    pub max_delay_ms: Option<u64>,
+    // optional: scale each backoff sleep by a random factor in [0.5, 1.5] (default false)
+    pub jitter: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct JsonPointers {
-    pub json_pointer_all_time: String,
-    pub json_pointer_boot_best: String,
-    pub json_pointer_uptime_secs: Option<String>,
-    pub json_pointer_boot_id: Option<String>,
+    pub json_pointer_all_time: PointerSpec,
+    pub json_pointer_boot_best: PointerSpec,
+    pub json_pointer_uptime_secs: Option<PointerSpec>,
+    pub json_pointer_boot_id: Option<PointerSpec>,
+    // optional: pointer to the device's boot timestamp (epoch seconds); without an uptime pointer,
+    // uptime is computed as now - boot_time
+    pub json_pointer_boot_time: Option<PointerSpec>,
     // pointers for current hashrate (TH/s) and efficiency (J/TH); optional to avoid breaking older configs
-    pub json_pointer_hashrate_ths: Option<String>,
-    pub json_pointer_efficiency_j_per_th: Option<String>,
+    pub json_pointer_hashrate_ths: Option<PointerSpec>,
+    pub json_pointer_efficiency_j_per_th: Option<PointerSpec>,
     // optional: pointer to power in watts (W) for computing efficiency when efficiency pointer is absent
-    pub json_pointer_power_w: Option<String>,
+    pub json_pointer_power_w: Option<PointerSpec>,
     // optional: scale factor applied to hashrate pointer value to convert into TH/s (e.g., 0.001 for GH/s -> TH/s)
     pub hashrate_scale: Option<f64>,
+    // optional: scale factor applied to the power pointer value to convert into W (e.g., 0.001 for mW -> W)
+    pub power_scale: Option<f64>,
+    // optional: calibration for a sensor that reads off; temp_c = raw * temp_scale + temp_offset_c
+    pub temp_scale: Option<f64>,
+    pub temp_offset_c: Option<f64>,
+    // optional: pointer to the device's own sample timestamp (epoch seconds) for freshness checks
+    pub json_pointer_sample_time: Option<PointerSpec>,
+    // optional: pointer to a best-difficulty value; the original string (e.g. "1.23T") is kept for display
+    #[serde(alias = "json_pointer_best_diff")]
+    pub json_pointer_best_difficulty: Option<PointerSpec>,
+    // optional: pointer to the best difficulty since boot (e.g. "/bestSessionDiff"), tracked on its own
+    // and started over when a reboot is detected
+    #[serde(alias = "json_pointer_session_diff")]
+    pub json_pointer_session_difficulty: Option<PointerSpec>,
+    // optional: pointer to the chip temperature in °C
+    pub json_pointer_temp_c: Option<PointerSpec>,
+    // optional: pointers to the cumulative accepted/rejected share counters (e.g. "/sharesAccepted")
+    pub json_pointer_shares_accepted: Option<PointerSpec>,
+    pub json_pointer_shares_rejected: Option<PointerSpec>,
+    // optional: pointers to supply voltage (V) and current (A); when no power value is read, power is
+    // computed as voltage * current
+    pub json_pointer_voltage_v: Option<PointerSpec>,
+    pub json_pointer_current_a: Option<PointerSpec>,
+    // optional: pointers to the ASIC core voltage (mV) and frequency (MHz) settings, logged with
+    // heartbeats and new best hashrate events to correlate overclock changes with results
+    pub json_pointer_voltage_mv: Option<PointerSpec>,
+    pub json_pointer_frequency_mhz: Option<PointerSpec>,
+    // optional: decimal separator used by numeric strings ("dot" by default; "comma" for values like "16,09")
+    pub decimal_separator: Option<DecimalSeparator>,
+    // optional: expected json type per pointer field (e.g. "json_pointer_hashrate_ths": "number"), checked at preflight
+    pub expected_types: Option<HashMap<String, ValueKind>>,
+    // optional: how the pointer fields are written ("pointer" by default; "jsonpath" for RFC 9535 paths
+    // like "$.workers[?@.name=='main'].hashRate")
+    pub selector_kind: Option<SelectorKind>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+//a pointer field is either one pointer or an ordered list of fallbacks (e.g. for firmware that renamed
+//a field); extraction uses the first candidate present in the response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PointerSpec {
+    One(String),
+    Candidates(Vec<String>),
+}
+
+impl PointerSpec {
+    pub fn candidates(&self) -> &[String] {
+        match self {
+            PointerSpec::One(p) => std::slice::from_ref(p),
+            PointerSpec::Candidates(list) => list,
+        }
+    }
+}
+
+impl Default for PointerSpec {
+    fn default() -> Self {
+        PointerSpec::One(String::new())
+    }
+}
+
+impl From<&str> for PointerSpec {
+    fn from(p: &str) -> Self {
+        PointerSpec::One(p.to_string())
+    }
+}
+
+impl std::fmt::Display for PointerSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.candidates().join(", "))
+    }
+}
+
+//json value type a pointer is declared to resolve to; unset pointers keep the lenient coercion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueKind {
+    Number,
+    Integer,
+    String,
+}
+
+impl std::fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ValueKind::Number => "number",
+            ValueKind::Integer => "integer",
+            ValueKind::String => "string",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SelectorKind {
+    #[default]
+    Pointer,
+    JsonPath,
+}
+
+//explicit decimal separator so "1,234" is never guessed between grouping and decimal meaning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DecimalSeparator {
+    #[default]
+    Dot,
+    // comma is the decimal point; dots are treated as digit grouping
+    Comma,
+}
+
+impl JsonPointers {
+    //list configured pointers by field name so callers can report on each without repeating the field list
+    pub fn named_pointers(&self) -> Vec<(&'static str, &PointerSpec)> {
+        let mut out: Vec<(&'static str, &PointerSpec)> = vec![
+            ("json_pointer_all_time", &self.json_pointer_all_time),
+            ("json_pointer_boot_best", &self.json_pointer_boot_best),
+        ];
+        let optional = [
+            ("json_pointer_uptime_secs", &self.json_pointer_uptime_secs),
+            ("json_pointer_boot_id", &self.json_pointer_boot_id),
+            ("json_pointer_boot_time", &self.json_pointer_boot_time),
+            ("json_pointer_hashrate_ths", &self.json_pointer_hashrate_ths),
+            (
+                "json_pointer_efficiency_j_per_th",
+                &self.json_pointer_efficiency_j_per_th,
+            ),
+            ("json_pointer_power_w", &self.json_pointer_power_w),
+            ("json_pointer_sample_time", &self.json_pointer_sample_time),
+            (
+                "json_pointer_best_difficulty",
+                &self.json_pointer_best_difficulty,
+            ),
+            (
+                "json_pointer_session_difficulty",
+                &self.json_pointer_session_difficulty,
+            ),
+            ("json_pointer_temp_c", &self.json_pointer_temp_c),
+            (
+                "json_pointer_shares_accepted",
+                &self.json_pointer_shares_accepted,
+            ),
+            (
+                "json_pointer_shares_rejected",
+                &self.json_pointer_shares_rejected,
+            ),
+            ("json_pointer_voltage_v", &self.json_pointer_voltage_v),
+            ("json_pointer_current_a", &self.json_pointer_current_a),
+            ("json_pointer_voltage_mv", &self.json_pointer_voltage_mv),
+            (
+                "json_pointer_frequency_mhz",
+                &self.json_pointer_frequency_mhz,
+            ),
+        ];
+        for (name, ptr) in optional {
+            if let Some(p) = ptr {
+                out.push((name, p));
+            }
+        }
+        out
+    }
+
+    //the optional pointers with mutable access, so a poll can skip the ones its response lacks
+    pub fn optional_pointers_mut(&mut self) -> [(&'static str, &mut Option<PointerSpec>); 16] {
+        [
+            (
+                "json_pointer_uptime_secs",
+                &mut self.json_pointer_uptime_secs,
+            ),
+            ("json_pointer_boot_id", &mut self.json_pointer_boot_id),
+            ("json_pointer_boot_time", &mut self.json_pointer_boot_time),
+            (
+                "json_pointer_hashrate_ths",
+                &mut self.json_pointer_hashrate_ths,
+            ),
+            (
+                "json_pointer_efficiency_j_per_th",
+                &mut self.json_pointer_efficiency_j_per_th,
+            ),
+            ("json_pointer_power_w", &mut self.json_pointer_power_w),
+            (
+                "json_pointer_sample_time",
+                &mut self.json_pointer_sample_time,
+            ),
+            (
+                "json_pointer_best_difficulty",
+                &mut self.json_pointer_best_difficulty,
+            ),
+            (
+                "json_pointer_session_difficulty",
+                &mut self.json_pointer_session_difficulty,
+            ),
+            ("json_pointer_temp_c", &mut self.json_pointer_temp_c),
+            (
+                "json_pointer_shares_accepted",
+                &mut self.json_pointer_shares_accepted,
+            ),
+            (
+                "json_pointer_shares_rejected",
+                &mut self.json_pointer_shares_rejected,
+            ),
+            ("json_pointer_voltage_v", &mut self.json_pointer_voltage_v),
+            ("json_pointer_current_a", &mut self.json_pointer_current_a),
+            ("json_pointer_voltage_mv", &mut self.json_pointer_voltage_mv),
+            (
+                "json_pointer_frequency_mhz",
+                &mut self.json_pointer_frequency_mhz,
+            ),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    Jsonl,
+    // events and state go to tables in storage.db_path
+    Sqlite,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    // "2024-01-02T03:04:05.678Z"
+    #[default]
+    Rfc3339,
+    // integer milliseconds since the unix epoch
+    EpochMs,
+    // integer seconds since the unix epoch
+    EpochS,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EventSeverity {
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct StorageConfig {
     pub events_path: String,
     pub state_path: String,
+    // optional: "jsonl" (default) or "sqlite"
+    pub backend: Option<StorageBackend>,
+    // optional: sqlite database file (default "bitaxe_monitor.db")
+    pub db_path: Option<String>,
+    // optional: jsonl file that receives one wide record per poll when wide_records is enabled
+    pub records_path: Option<String>,
+    // optional: parquet file for per-poll samples, written in row groups of parquet_batch_size (default 100)
+    pub parquet_path: Option<String>,
+    pub parquet_batch_size: Option<usize>,
+    // optional: alternate state writes between two buffers with a checksummed pointer file so a bad
+    // write can be recovered from the previous copy
+    pub double_buffer: Option<bool>,
+    // optional: batch event writes, flushing every max_events events or max_delay_ms, whichever first
+    pub event_batch: Option<EventBatchConfig>,
+    // optional: write the process id here and hold a lock on it while running; startup fails while
+    // another instance holds the lock, and the file is removed on shutdown
+    pub pid_file: Option<String>,
+    // optional: how the "ts" field of events is written: "rfc3339" (default), "epoch_ms" or "epoch_s"
+    pub timestamp_format: Option<TimestampFormat>,
+    // optional: events below this severity ("info", "warn" or "error") are not written (default
+    // "info", i.e. everything)
+    pub min_event_severity: Option<EventSeverity>,
+    // optional: once events_path grows past this many bytes it is moved to a gzipped
+    // "<stem>-<timestamp>.jsonl.gz" archive and a fresh file is started
+    pub max_event_bytes: Option<u64>,
+    // optional: rotated archives to keep, oldest deleted first (default 10)
+    pub max_event_files: Option<usize>,
+    // optional: every snapshot_interval_secs, save the raw response body to
+    // snapshot_dir/<timestamp>.json for bug reports or replaying with `simulate`
+    pub snapshot_interval_secs: Option<u64>,
+    pub snapshot_dir: Option<String>,
+    // optional: snapshots to keep, oldest deleted first (default 100)
+    pub max_snapshots: Option<usize>,
+    // optional: save the state file at most once per this many seconds (default: every poll); polls
+    // that produce an event and shutdown always save
+    pub state_save_interval_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventBatchConfig {
+    // default 50
+    pub max_events: Option<usize>,
+    // default 1000
+    pub max_delay_ms: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,79 +370,877 @@ pub struct AppConfig {
     pub storage: StorageConfig,
     // optional tuning to reduce jitter when updating bests
     pub thresholds: Option<ThresholdsConfig>,
+    // optional friendly name included in records so files from several miners can be told apart
+    pub device_name: Option<String>,
+    // optional: append one record with all current metrics and bests to storage.records_path each poll
+    pub wide_records: Option<bool>,
+    // optional: only write a wide record when a metric moved past its epsilon, plus one every
+    // wide_record_keepalive_polls polls (default 60) so quiet periods still show up
+    pub wide_record_on_change_only: Option<bool>,
+    pub wide_record_keepalive_polls: Option<u32>,
+    // optional: add SI-formatted strings (e.g. "hashrate_human": "1.23 TH/s") next to raw event values
+    pub human_values: Option<bool>,
+    // optional: fail loading when the config contains unrecognized keys (default: warn and continue)
+    pub strict_config: Option<bool>,
+    // optional: lowest allowed poll interval (never below the built-in POLL_INTERVAL_FLOOR_SECS)
+    pub min_poll_interval_secs: Option<u64>,
+    // optional: lengthen the poll interval while nothing happens, snap back to poll_interval_secs on events
+    pub adaptive_interval: Option<AdaptiveIntervalConfig>,
+    // optional: widen the poll interval after consecutive failed polls and log a run of identical
+    // errors as one poll_error plus a poll_error_repeated count
+    pub failure_backoff: Option<FailureBackoffConfig>,
+    // optional: after failure_threshold failed polls in a row, write circuit_open and only poll every
+    // open_interval_secs until one succeeds, which writes circuit_closed
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    // optional: push each poll's metrics to a prometheus remote-write endpoint
+    pub remote_write: Option<RemoteWriteConfig>,
+    // optional: write each poll's metrics to influxdb as a line-protocol point
+    pub influx: Option<InfluxConfig>,
+    // optional: send each poll's metrics to a statsd collector as udp gauges
+    pub statsd: Option<StatsdConfig>,
+    // optional: serve the latest state as prometheus metrics on http://<listen_addr>/metrics
+    pub exporter: Option<ExporterConfig>,
+    // optional: serve a local html dashboard with current metrics and bests
+    pub dashboard: Option<DashboardConfig>,
+    // optional: forward events to an opentelemetry collector as otlp log records
+    pub otlp: Option<OtlpConfig>,
+    // optional: push selected events to chat services
+    pub notifications: Option<NotificationsConfig>,
+    // optional: when preflight fails, keep probing the device instead of exiting, and write nothing
+    // (events, state, notifications) until the first successful extraction (default false)
+    pub probe_until_ready: Option<bool>,
+    // optional: add the build's git sha as "build" to every event, not only service_start
+    pub tag_events_with_build: Option<bool>,
+    // optional: bucket edges for value distributions of polled metrics (exporter + histogram command)
+    pub histograms: Option<HistogramsConfig>,
+    // optional: number of recent hashrate samples in the rolling average (default 10)
+    pub rolling_window: Option<usize>,
+    // optional: write a heartbeat event (latest hashrate, efficiency, uptime) this often, independent
+    // of poll_interval_secs
+    pub heartbeat_interval_secs: Option<u64>,
+    // optional: add the monitor's own memory and cpu use to heartbeat events and the exporter
+    // (default false)
+    pub self_metrics: Option<bool>,
+    // optional: write metric_available / metric_unavailable when an optional pointer starts or stops
+    // resolving; a missing optional metric then skips that metric instead of failing the poll
+    pub metric_availability_events: Option<bool>,
+    // optional: after boot_detected, failed polls within this many seconds are reported once as
+    // rebooting (with a short retry policy) instead of as poll_error; recovery emits reboot_complete
+    pub post_reboot_grace_secs: Option<u64>,
+    // optional: report a restart loop as one boot_flapping event instead of a boot_detected per reboot
+    pub boot_flapping: Option<BootFlappingConfig>,
+    // optional (unix only): stream one json line per poll to every client connected to this socket
+    pub stream_socket_path: Option<String>,
+    // optional (unix only): accept "checkpoint [name]" / "restore [name]" commands on this socket
+    pub control_socket_path: Option<String>,
+    // optional: directory for checkpoint slot files (default "checkpoints")
+    pub checkpoint_dir: Option<String>,
+    // optional: switch off metrics the device does not report so they are neither read nor tracked
+    pub metrics: Option<MetricsConfig>,
+    // optional: more miners, each with its own endpoint and state file ([[devices]] in toml, a
+    // "devices" array in json); parsed and validated, only http.endpoint_url is polled for now
+    pub devices: Option<Vec<DeviceConfig>>,
+}
+
+//one extra miner; anything not set here comes from the top level of the config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceConfig {
+    // used as this miner's device_name
+    pub name: String,
+    pub endpoint_url: String,
+    // optional: default is storage.state_path with "-<name>" before the extension
+    pub state_path: Option<String>,
+    // optional: replace the top-level pointers / thresholds for this miner
+    pub pointers: Option<JsonPointers>,
+    pub thresholds: Option<ThresholdsConfig>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    // each defaults to true; false skips the metric's pointer (and, for efficiency, the power-based
+    // fallback) so no values or events are produced for it
+    pub track_hashrate: Option<bool>,
+    pub track_efficiency: Option<bool>,
+    pub track_temp: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistogramsConfig {
+    // ascending bucket upper edges, e.g. [1.0, 1.2, 1.4]; values above the last edge count as +Inf
+    pub hashrate_ths: Option<Vec<f64>>,
+    pub efficiency_j_per_th: Option<Vec<f64>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootFlappingConfig {
+    // sliding window the reboots are counted in
+    pub window_secs: u64,
+    // more reboots than this inside the window count as flapping
+    pub max_boots: u32,
+    // optional: mark boot_flapping as error severity rather than a warning (default false)
+    pub escalate: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationsConfig {
+    pub discord: Option<DiscordConfig>,
+    pub telegram: Option<TelegramConfig>,
+    // optional: any other receiver (slack, a custom service) posted a json body built from a template
+    pub webhook: Option<WebhookConfig>,
+    // optional: send from a background queue so polls never wait on a webhook; the queue is drained
+    // on shutdown (default false)
+    pub queued: Option<bool>,
+    // optional: how long shutdown waits for queued notifications to go out (default 10)
+    pub flush_timeout_secs: Option<u64>,
+    // optional: announce each record only once, even across restarts; the last value sent per record
+    // event is kept in the state file (default false)
+    pub dedupe: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscordConfig {
+    pub webhook_url: String,
+    // event names to forward, e.g. ["new_device_all_time_best", "boot_detected"]
+    pub events: Vec<String>,
+    // optional: "realtime" (default) or "digest" for one summary per UTC day instead
+    pub mode: Option<NotifyMode>,
+}
+
+//when a channel posts: every subscribed event as it is written, or one summary per UTC day (best
+//hashrate and efficiency, reboots, errors, alerts) from the first poll after midnight
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyMode {
+    #[default]
+    Realtime,
+    Digest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    // event names to forward, same as for discord
+    pub events: Vec<String>,
+    // optional: request body with {event}, {value}, {device} and {ts} placeholders; {value} is a json
+    // value (number or null) that can stand unquoted. default: notify::DEFAULT_PAYLOAD_TEMPLATE
+    pub payload_template: Option<String>,
+    // optional: "realtime" (default) or "digest", as for discord
+    pub mode: Option<NotifyMode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    // user, group or channel id as a string, e.g. "123456789" or "-1001234567890"
+    pub chat_id: String,
+    // event names to forward, same as for discord
+    pub events: Vec<String>,
+    // optional: bot api base url (default "https://api.telegram.org"), for a self-hosted bot api server
+    pub api_url: Option<String>,
+    // optional: "realtime" (default) or "digest", as for discord
+    pub mode: Option<NotifyMode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtlpConfig {
+    // otlp/http logs endpoint, e.g. "http://collector:4318/v1/logs"
+    pub endpoint: String,
+    pub headers: Option<HashMap<String, String>>,
+    // resource service.name (default "bitaxe_monitor")
+    pub service_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DashboardConfig {
+    // e.g. "127.0.0.1:8080"
+    pub listen_addr: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExporterConfig {
+    // e.g. "0.0.0.0:9184"
+    pub listen_addr: String,
+    // optional: per-series rename/help/scale keyed by the built-in metric name
+    pub series_overrides: Option<HashMap<String, SeriesOverride>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SeriesOverride {
+    // exposed metric name instead of the built-in one
+    pub name: Option<String>,
+    pub help: Option<String>,
+    // multiplied into the value, e.g. 0.001 to expose J/TH as W/GH
+    pub scale: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteWriteConfig {
+    // e.g. "http://prometheus:9090/api/v1/write"
+    pub url: String,
+    // optional auth: bearer token takes precedence over basic auth
+    pub bearer_token: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InfluxConfig {
+    // server base url, e.g. "http://influxdb:8086"
+    pub url: String,
+    pub org: String,
+    pub bucket: String,
+    // api token with write access to the bucket
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsdConfig {
+    // collector host:port, e.g. "127.0.0.1:8125"
+    pub addr: String,
+    // optional: prepended to every gauge name (default "bitaxe")
+    pub prefix: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdaptiveIntervalConfig {
+    // upper bound for the grown interval
+    pub max_interval_secs: u64,
+    // multiplier applied after each run of stable polls (default 2.0)
+    pub factor: Option<f64>,
+    // consecutive polls without events before the interval grows (default 5)
+    pub stable_polls: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailureBackoffConfig {
+    // upper bound for the widened interval, e.g. 300
+    pub max_interval_secs: u64,
+    // multiplier per consecutive failed poll (default 2.0)
+    pub factor: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    // consecutive failed polls that open the circuit
+    pub failure_threshold: u32,
+    // poll interval while open (default 300, never shorter than the normal schedule)
+    pub open_interval_secs: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThresholdsConfig {
     pub epsilon_hashrate_ths: Option<f64>,
     pub epsilon_efficiency_j_per_th: Option<f64>,
+    // seconds after boot to ignore for worst-value tracking so ramp-up readings do not count
+    pub warmup_secs: Option<u64>,
+    // emit high_jitter when the stddev of recent fetch durations exceeds this many milliseconds
+    pub jitter_threshold_ms: Option<f64>,
+    // number of recent fetch durations used for jitter (default 20)
+    pub jitter_window: Option<usize>,
+    // treat polls whose sample timestamp is older than this as stale (requires json_pointer_sample_time)
+    pub max_sample_age_secs: Option<u64>,
+    // without uptime/boot id, do not treat a boot_best drop as a reboot when all_time rose in the same poll
+    pub ignore_boot_best_reset_on_all_time_update: Option<bool>,
+    // uptimes above this (default 10 years) are treated as invalid for the poll and logged as implausible_uptime
+    pub max_plausible_uptime_secs: Option<u64>,
+    // hashrate (TH/s) and chip temperature (°C) readings above these are rejected for the poll as
+    // outliers and logged as outlier_rejected, so a garbage spike cannot set a best
+    pub max_plausible_hashrate_ths: Option<f64>,
+    pub max_plausible_temp_c: Option<f64>,
+    // number of recent efficiency samples used for the drift slope (default 30)
+    pub efficiency_trend_window: Option<usize>,
+    // emit efficiency_drift when J/TH rises faster than this many J/TH per hour over the window
+    pub efficiency_drift_per_hour: Option<f64>,
+    // emit temp_alert when the chip temperature (°C) rises above this (requires json_pointer_temp_c)
+    pub temp_alert_c: Option<f64>,
+    // emit high_reject_ratio when more than this fraction (0..=1) of the shares submitted since the
+    // last poll were rejected (requires both share pointers)
+    pub reject_ratio_alert: Option<f64>,
+    // preflight warns when reported efficiency and power/hashrate differ by more than this percent (default 5)
+    pub efficiency_consistency_tolerance_pct: Option<f64>,
+    // emit efficiency_degraded when J/TH is more than this percent worse than the median of recent samples
+    pub efficiency_degraded_pct: Option<f64>,
+    // number of recent efficiency samples in that median, reset on reboot (default 30)
+    pub efficiency_baseline_window: Option<usize>,
+    // emit hashrate_stalled when hashrate (TH/s) stays below this for stall_polls consecutive polls
+    pub min_hashrate_ths: Option<f64>,
+    // default 3
+    pub stall_polls: Option<u32>,
 }
 
-pub fn load_config<P: AsRef<Path>>(path: P) -> Result<AppConfig> {
-    let bytes = fs::read(path.as_ref()).with_context(|| "failed to read config file")?;
-    //support both json and toml by sniffing the first non-space char
-    let text = String::from_utf8(bytes).context("config is not utf-8")?;
-    let first = text.chars().find(|c| !c.is_whitespace());
-    let cfg: AppConfig = match first {
-        Some('{') => serde_json::from_str(&text).context("invalid json config")?,
-        _ => toml::from_str(&text).context("invalid toml config")?,
+//sha-256 of the settings that decide what a saved value means: the endpoint and the pointers,
+//including their scales. intervals, sinks and alert thresholds can change freely without the saved
+//bests becoming suspect. canonical json (sorted keys), so formatting and key order do not matter
+pub fn config_hash(cfg: &AppConfig) -> String {
+    use sha2::{Digest, Sha256};
+    let canonical = serde_json::json!({
+        "endpoint_url": cfg.http.endpoint_url,
+        "pointers": cfg.pointers,
+    })
+    .to_string();
+    Sha256::digest(canonical.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+//sections only read at startup: the http client, storage sinks and listeners are built once
+const STARTUP_ONLY_KEYS: [&str; 9] = [
+    "http",
+    "storage",
+    "exporter",
+    "dashboard",
+    "statsd",
+    "otlp",
+    "stream_socket_path",
+    "control_socket_path",
+    "tag_events_with_build",
+];
+
+//the config a [[devices]] entry stands for: the top level with the entry's endpoint, name, state
+//file, pointers and thresholds. outputs that only the main endpoint feeds (exporter, dashboard,
+//statsd, otlp, parquet, sockets, heartbeats, self metrics) are left out, and payload snapshots go to a subdirectory
+pub fn device_config(cfg: &AppConfig, device: &DeviceConfig) -> AppConfig {
+    let mut out = cfg.clone();
+    out.http.endpoint_url = device.endpoint_url.clone();
+    out.device_name = Some(device.name.clone());
+    out.storage.state_path = device
+        .state_path
+        .clone()
+        .unwrap_or_else(|| device_state_path(&cfg.storage.state_path, &device.name));
+    out.storage.parquet_path = None;
+    out.storage.snapshot_dir = cfg.storage.snapshot_dir.as_ref().map(|dir| {
+        Path::new(dir)
+            .join(&device.name)
+            .to_string_lossy()
+            .to_string()
+    });
+    if let Some(pointers) = &device.pointers {
+        out.pointers = pointers.clone();
+    }
+    if let Some(thresholds) = &device.thresholds {
+        out.thresholds = Some(thresholds.clone());
+    }
+    out.exporter = None;
+    out.dashboard = None;
+    out.statsd = None;
+    out.otlp = None;
+    out.stream_socket_path = None;
+    out.control_socket_path = None;
+    out.heartbeat_interval_secs = None;
+    out.self_metrics = None;
+    out.probe_until_ready = None;
+    out.devices = None;
+    out
+}
+
+//"state/myBitAxeInfo.json" -> "state/myBitAxeInfo-garage.json"
+fn device_state_path(template: &str, name: &str) -> String {
+    let file_start = template.rfind(['/', '\\']).map_or(0, |i| i + 1);
+    match template[file_start..].rfind('.') {
+        Some(dot) if dot > 0 => {
+            let at = file_start + dot;
+            format!("{}-{}{}", &template[..at], name, &template[at..])
+        }
+        _ => format!("{}-{}", template, name),
+    }
+}
+
+//a reloaded config keeps the running values of the startup-only sections; returns the sections the
+//new file changed, which only take effect after a restart
+pub fn keep_startup_settings(
+    running: &AppConfig,
+    next: &mut AppConfig,
+) -> Result<Vec<&'static str>> {
+    let running_value = serde_json::to_value(running)?;
+    let mut next_value = serde_json::to_value(&*next)?;
+    let mut changed = Vec::new();
+    for key in STARTUP_ONLY_KEYS {
+        if running_value.get(key) != next_value.get(key) {
+            changed.push(key);
+            next_value[key] = running_value[key].clone();
+        }
+    }
+    *next = serde_json::from_value(next_value)?;
+    Ok(changed)
+}
+
+//the config files to load: repeated --config flags, else BITAXE_MONITOR_CONFIG (a comma-separated
+//list), else config.json; a file whose name contains a comma can still be passed with --config
+pub fn config_paths(flags: &[PathBuf], env: Option<OsString>) -> Vec<PathBuf> {
+    if !flags.is_empty() {
+        return flags.to_vec();
+    }
+    let from_env: Vec<PathBuf> = env
+        .map(|v| {
+            v.to_string_lossy()
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    if from_env.is_empty() {
+        vec![PathBuf::from("config.json")]
+    } else {
+        from_env
+    }
+}
+
+//several files are merged left to right, later ones overriding earlier keys
+pub fn load_config<P: AsRef<Path>>(paths: &[P]) -> Result<AppConfig> {
+    let mut texts = Vec::with_capacity(paths.len());
+    for p in paths {
+        let p = p.as_ref();
+        let bytes = fs::read(p).with_context(|| format!("failed to read config file {:?}", p))?;
+        texts.push(
+            String::from_utf8(bytes).with_context(|| format!("config {:?} is not utf-8", p))?,
+        );
+    }
+    let (cfg, unknown) = match texts.as_slice() {
+        [text] => parse_config_str(text)?,
+        _ => parse_merged_config_strs(&texts)?,
     };
 
+    //typos in key names are otherwise silently ignored by serde; surface them
+    if !unknown.is_empty() {
+        if cfg.strict_config.unwrap_or(false) {
+            bail!("unknown config keys: {}", unknown.join(", "));
+        }
+        eprintln!(
+            "[bitaxe_monitor] WARN: ignoring unknown config keys: {}",
+            unknown.join(", ")
+        );
+    }
+
     validate_config(&cfg)?;
     Ok(cfg)
 }
 
+//json or toml (sniffed from the first non-space char) as a value tree
+fn parse_config_value(text: &str) -> Result<serde_json::Value> {
+    match text.chars().find(|c| !c.is_whitespace()) {
+        Some('{') => serde_json::from_str(text).context("invalid json config"),
+        _ => Ok(serde_json::to_value(
+            toml::from_str::<toml::Value>(text).context("invalid toml config")?,
+        )?),
+    }
+}
+
+//typed config from a value tree, collecting unknown keys like parse_config_str
+fn config_from_value(value: serde_json::Value) -> Result<(AppConfig, Vec<String>)> {
+    let mut unknown: Vec<String> = Vec::new();
+    let mut record = |path: serde_ignored::Path| unknown.push(path.to_string().replace(".?", ""));
+    let cfg = serde_ignored::deserialize(value, &mut record).context("invalid config")?;
+    Ok((cfg, unknown))
+}
+
+//later tables override earlier ones key by key; any other value (including arrays) replaces outright
+fn merge_config_values(base: &mut serde_json::Value, over: serde_json::Value) {
+    match (base, over) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(over)) => {
+            for (key, value) in over {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_config_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, over) => *base = over,
+    }
+}
+
+//merge several files in order, then expand ${VAR} and deserialize once, so only the combined
+//result has to be complete
+fn parse_merged_config_strs(texts: &[String]) -> Result<(AppConfig, Vec<String>)> {
+    let mut merged = serde_json::Value::Object(Default::default());
+    for text in texts {
+        merge_config_values(&mut merged, parse_config_value(text)?);
+    }
+    expand_env_vars(&mut merged, "")?;
+    config_from_value(merged)
+}
+
+//parse json or toml and collect the dotted paths of any keys the schema does not recognize
+fn parse_config_str(text: &str) -> Result<(AppConfig, Vec<String>)> {
+    //with ${VAR} placeholders, parse to a value tree first so they can be expanded field by field
+    if text.contains("${") {
+        let mut value = parse_config_value(text)?;
+        expand_env_vars(&mut value, "")?;
+        return config_from_value(value);
+    }
+    let mut unknown: Vec<String> = Vec::new();
+    //serde_ignored marks Option layers as "?"; drop them so paths read like config keys
+    let mut record = |path: serde_ignored::Path| unknown.push(path.to_string().replace(".?", ""));
+    let first = text.chars().find(|c| !c.is_whitespace());
+    let cfg: AppConfig = match first {
+        Some('{') => {
+            let mut de = serde_json::Deserializer::from_str(text);
+            let cfg =
+                serde_ignored::deserialize(&mut de, &mut record).context("invalid json config")?;
+            de.end().context("invalid json config")?;
+            cfg
+        }
+        _ => serde_ignored::deserialize(toml::Deserializer::new(text), &mut record)
+            .context("invalid toml config")?,
+    };
+    Ok((cfg, unknown))
+}
+
+//replace ${VAR} in every string value with the variable's value; path is the dotted key used in errors
+fn expand_env_vars(value: &mut serde_json::Value, path: &str) -> Result<()> {
+    match value {
+        serde_json::Value::String(s) => *s = expand_env_str(s, path)?,
+        serde_json::Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                expand_env_vars(item, &format!("{}[{}]", path, i))?;
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                expand_env_vars(item, &child)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn expand_env_str(s: &str, path: &str) -> Result<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start + 2..].find('}') else {
+            bail!("{}: unterminated ${{ in {:?}", path, s);
+        };
+        let name = &rest[start + 2..start + 2 + len];
+        let value = std::env::var(name)
+            .map_err(|_| anyhow::anyhow!("{}: environment variable {} is not set", path, name))?;
+        out.push_str(&value);
+        rest = &rest[start + 3 + len..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+//no configuration may poll the device more often than this, so a typo cannot hammer it
+pub const POLL_INTERVAL_FLOOR_SECS: u64 = 2;
+
+//the built-in floor, raised by min_poll_interval_secs when set
+pub fn min_poll_interval_secs(cfg: &AppConfig) -> u64 {
+    cfg.min_poll_interval_secs
+        .unwrap_or(POLL_INTERVAL_FLOOR_SECS)
+        .max(POLL_INTERVAL_FLOOR_SECS)
+}
+
 fn validate_config(cfg: &AppConfig) -> Result<()> {
-    if cfg.poll_interval_secs == 0 {
-        bail!("poll_interval_secs must be > 0");
+    if let Some(min) = cfg.min_poll_interval_secs {
+        if min < POLL_INTERVAL_FLOOR_SECS {
+            bail!(
+                "min_poll_interval_secs ({}) cannot be below the built-in floor of {}s",
+                min,
+                POLL_INTERVAL_FLOOR_SECS
+            );
+        }
+    }
+    let floor = min_poll_interval_secs(cfg);
+    if cfg.poll_interval_secs < floor {
+        bail!(
+            "poll_interval_secs ({}) is below the minimum of {}s; polling faster can overload the device",
+            cfg.poll_interval_secs,
+            floor
+        );
     }
     if !cfg.http.endpoint_url.starts_with("http://")
         && !cfg.http.endpoint_url.starts_with("https://")
     {
         bail!("endpoint_url must start with http:// or https://");
     }
+    //sinks compiled out of this build are rejected rather than silently ignored
+    let missing_feature = [
+        (
+            "exporter",
+            cfg!(feature = "exporter"),
+            cfg.exporter.is_some(),
+        ),
+        (
+            "dashboard",
+            cfg!(feature = "dashboard"),
+            cfg.dashboard.is_some(),
+        ),
+        ("otlp", cfg!(feature = "otlp"), cfg.otlp.is_some()),
+        (
+            "parquet",
+            cfg!(feature = "parquet"),
+            cfg.storage.parquet_path.is_some(),
+        ),
+        (
+            "sqlite",
+            cfg!(feature = "sqlite"),
+            cfg.storage.backend == Some(StorageBackend::Sqlite),
+        ),
+    ];
+    for (feature, built, used) in missing_feature {
+        if used && !built {
+            bail!(
+                "the config uses {} but this build does not include it; rebuild with `--features {}`",
+                feature,
+                feature
+            );
+        }
+    }
+    #[cfg(feature = "exporter")]
+    if let Some(exp) = &cfg.exporter {
+        if exp.listen_addr.parse::<std::net::SocketAddr>().is_err() {
+            bail!("exporter.listen_addr must be an ip:port address, e.g. 0.0.0.0:9184");
+        }
+        for (key, o) in exp.series_overrides.iter().flatten() {
+            if !crate::exporter::SERIES_NAMES.contains(&key.as_str()) {
+                bail!(
+                    "exporter.series_overrides: unknown series '{}' (known: {})",
+                    key,
+                    crate::exporter::SERIES_NAMES.join(", ")
+                );
+            }
+            if let Some(name) = &o.name {
+                let valid = name.chars().enumerate().all(|(i, c)| {
+                    c.is_ascii_alphabetic() || c == '_' || c == ':' || (i > 0 && c.is_ascii_digit())
+                });
+                if name.is_empty() || !valid {
+                    bail!(
+                        "exporter.series_overrides.{}.name '{}' is not a valid prometheus metric name",
+                        key,
+                        name
+                    );
+                }
+            }
+            if o.scale.is_some_and(|s| !(s.is_finite() && s != 0.0)) {
+                bail!(
+                    "exporter.series_overrides.{}.scale must be finite and non-zero",
+                    key
+                );
+            }
+        }
+    }
+    if let Some(d) = &cfg.dashboard {
+        if d.listen_addr.parse::<std::net::SocketAddr>().is_err() {
+            bail!("dashboard.listen_addr must be an ip:port address, e.g. 127.0.0.1:8080");
+        }
+    }
+    if let Some(otlp) = &cfg.otlp {
+        if !otlp.endpoint.starts_with("http://") && !otlp.endpoint.starts_with("https://") {
+            bail!("otlp.endpoint must start with http:// or https://");
+        }
+    }
+    if let Some(rw) = &cfg.remote_write {
+        if !rw.url.starts_with("http://") && !rw.url.starts_with("https://") {
+            bail!("remote_write.url must start with http:// or https://");
+        }
+    }
+    if let Some(h) = &cfg.histograms {
+        for (name, edges) in [
+            ("hashrate_ths", &h.hashrate_ths),
+            ("efficiency_j_per_th", &h.efficiency_j_per_th),
+        ] {
+            let Some(edges) = edges else { continue };
+            if edges.is_empty() || edges.iter().any(|e| !e.is_finite()) {
+                bail!(
+                    "histograms.{} must be a non-empty list of finite numbers",
+                    name
+                );
+            }
+            if edges.windows(2).any(|w| w[0] >= w[1]) {
+                bail!("histograms.{} edges must be strictly increasing", name);
+            }
+        }
+    }
+    if let Some(flap) = &cfg.boot_flapping {
+        if flap.window_secs == 0 || flap.max_boots == 0 {
+            bail!("boot_flapping.window_secs and boot_flapping.max_boots must be > 0");
+        }
+    }
+    if let Some(influx) = &cfg.influx {
+        if !influx.url.starts_with("http://") && !influx.url.starts_with("https://") {
+            bail!("influx.url must start with http:// or https://");
+        }
+        if influx.org.is_empty() || influx.bucket.is_empty() {
+            bail!("influx.org and influx.bucket must not be empty");
+        }
+    }
 
-    //require json pointers to begin with '/' so pointer semantics match serde_json::Value::pointer
-    let ptrs = &cfg.pointers;
-    let mut bad: Vec<(&str, &str)> = Vec::new();
-    if !ptrs.json_pointer_all_time.starts_with('/') {
-        bad.push(("json_pointer_all_time", &ptrs.json_pointer_all_time));
+    if let Some(statsd) = &cfg.statsd {
+        if statsd
+            .addr
+            .rsplit_once(':')
+            .is_none_or(|(host, port)| host.is_empty() || port.parse::<u16>().is_err())
+        {
+            bail!("statsd.addr must be a host:port address, e.g. 127.0.0.1:8125");
+        }
+        if let Some(prefix) = &statsd.prefix {
+            if prefix.contains([':', '|', '\n']) {
+                bail!("statsd.prefix must not contain ':', '|' or newlines");
+            }
+        }
+    }
+
+    if cfg.storage.parquet_batch_size == Some(0) {
+        bail!("storage.parquet_batch_size must be > 0");
+    }
+    if cfg.http.body.is_some() && cfg.http.method.unwrap_or_default() != HttpMethod::Post {
+        bail!("http.body is only sent with POST; set http.method = \"POST\"");
+    }
+    if cfg.http.poll_budget_secs == Some(0) {
+        bail!("http.poll_budget_secs must be > 0");
+    }
+    if cfg.http.pool_idle_timeout_secs == Some(0) {
+        bail!("http.pool_idle_timeout_secs must be > 0 (use pool_max_idle_per_host = 0 to disable reuse)");
+    }
+    if cfg.http.tcp_keepalive_secs == Some(0) {
+        bail!("http.tcp_keepalive_secs must be > 0");
+    }
+    if cfg.http.max_response_bytes == Some(0) {
+        bail!("http.max_response_bytes must be > 0");
+    }
+    if let Some(r) = &cfg.http.retry {
+        if r.max_retries.unwrap_or(0) > 0 && r.base_delay_ms == Some(0) {
+            bail!("http.retry.base_delay_ms must be > 0 when retries are enabled");
+        }
+        if r.max_delay_ms == Some(0) {
+            bail!("http.retry.max_delay_ms must be > 0");
+        }
     }
-    if !ptrs.json_pointer_boot_best.starts_with('/') {
-        bad.push(("json_pointer_boot_best", &ptrs.json_pointer_boot_best));
+
+    if let Some(d) = cfg.notifications.as_ref().and_then(|n| n.discord.as_ref()) {
+        if !(d.webhook_url.starts_with("http://") || d.webhook_url.starts_with("https://")) {
+            bail!("notifications.discord.webhook_url must start with http:// or https://");
+        }
     }
-    if let Some(p) = &ptrs.json_pointer_uptime_secs {
-        if !p.starts_with('/') {
-            bad.push(("json_pointer_uptime_secs", p));
+    if let Some(t) = cfg.notifications.as_ref().and_then(|n| n.telegram.as_ref()) {
+        if t.bot_token.trim().is_empty() || t.chat_id.trim().is_empty() {
+            bail!("notifications.telegram needs both bot_token and chat_id");
+        }
+        if let Some(url) = &t.api_url {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                bail!("notifications.telegram.api_url must start with http:// or https://");
+            }
         }
     }
-    if let Some(p) = &ptrs.json_pointer_boot_id {
-        if !p.starts_with('/') {
-            bad.push(("json_pointer_boot_id", p));
+    if let Some(w) = cfg.notifications.as_ref().and_then(|n| n.webhook.as_ref()) {
+        if !(w.url.starts_with("http://") || w.url.starts_with("https://")) {
+            bail!("notifications.webhook.url must start with http:// or https://");
+        }
+        //the body has to be json for every event, with a value and without one
+        if let Some(template) = &w.payload_template {
+            for value in [serde_json::json!(1.5), serde_json::Value::Null] {
+                let sample = serde_json::json!({
+                    "ts": "2024-01-01T00:00:00Z", "event": "boot_detected", "value": value
+                });
+                let body = crate::notify::render_payload(template, Some("bitaxe"), &sample);
+                serde_json::from_str::<serde_json::Value>(&body).with_context(|| {
+                    format!(
+                        "notifications.webhook.payload_template is not valid json once filled in: {}",
+                        body
+                    )
+                })?;
+            }
         }
     }
-    if let Some(p) = &ptrs.json_pointer_hashrate_ths {
-        if !p.starts_with('/') {
-            bad.push(("json_pointer_hashrate_ths", p));
+    if cfg.storage.backend == Some(StorageBackend::Sqlite) {
+        if cfg.storage.event_batch.is_some() {
+            bail!("storage.event_batch only applies to the jsonl backend");
+        }
+        if cfg.storage.double_buffer.unwrap_or(false) {
+            bail!("storage.double_buffer only applies to the jsonl backend");
+        }
+        //there is no file to rotate; the events table is never rotated
+        if cfg.storage.max_event_bytes.is_some() || cfg.storage.max_event_files.is_some() {
+            bail!("storage.max_event_bytes and max_event_files only apply to the jsonl backend");
         }
     }
-    if let Some(p) = &ptrs.json_pointer_efficiency_j_per_th {
-        if !p.starts_with('/') {
-            bad.push(("json_pointer_efficiency_j_per_th", p));
+    if cfg.rolling_window == Some(0) {
+        bail!("rolling_window must be > 0");
+    }
+    if cfg.heartbeat_interval_secs == Some(0) {
+        bail!("heartbeat_interval_secs must be > 0");
+    }
+    if cfg.storage.max_event_bytes == Some(0) {
+        bail!("storage.max_event_bytes must be > 0");
+    }
+    if cfg.storage.max_event_files == Some(0) {
+        bail!("storage.max_event_files must be > 0");
+    }
+    if cfg.storage.snapshot_interval_secs.is_some() != cfg.storage.snapshot_dir.is_some() {
+        bail!("storage.snapshot_interval_secs and storage.snapshot_dir must be set together");
+    }
+    if cfg.storage.snapshot_interval_secs == Some(0) {
+        bail!("storage.snapshot_interval_secs must be > 0");
+    }
+    if cfg.storage.state_save_interval_secs == Some(0) {
+        bail!("storage.state_save_interval_secs must be > 0");
+    }
+    if cfg.storage.max_snapshots == Some(0) {
+        bail!("storage.max_snapshots must be > 0");
+    }
+    if let Some(b) = &cfg.storage.event_batch {
+        if b.max_events == Some(0) {
+            bail!("storage.event_batch.max_events must be > 0");
+        }
+        if b.max_delay_ms == Some(0) {
+            bail!("storage.event_batch.max_delay_ms must be > 0");
         }
     }
-    if let Some(p) = &ptrs.json_pointer_power_w {
-        if !p.starts_with('/') {
-            bad.push(("json_pointer_power_w", p));
+
+    //require json pointers to begin with '/' so pointer semantics match serde_json::Value::pointer
+    //every fallback candidate is checked, and a list must name at least one pointer
+    let named = cfg.pointers.named_pointers();
+    if let Some((name, _)) = named.iter().find(|(_, spec)| spec.candidates().is_empty()) {
+        bail!("{} must list at least one json pointer", name);
+    }
+    let candidates = named
+        .iter()
+        .flat_map(|(name, spec)| spec.candidates().iter().map(move |p| (*name, p.as_str())));
+    //jsonpath selectors are checked by parsing them instead
+    if cfg.pointers.selector_kind == Some(SelectorKind::JsonPath) {
+        for (name, p) in candidates {
+            if let Err(err) = serde_json_path::JsonPath::parse(p) {
+                bail!("invalid jsonpath {}='{}': {}", name, p, err);
+            }
+        }
+    } else {
+        let bad: Vec<(&str, &str)> = candidates.filter(|(_, p)| !p.starts_with('/')).collect();
+        if !bad.is_empty() {
+            let joined = bad
+                .into_iter()
+                .map(|(k, v)| format!("{}='{}'", k, v))
+                .collect::<Vec<_>>()
+                .join(", ");
+            bail!("json pointers must start with '/': {}", joined);
         }
     }
-    if !bad.is_empty() {
-        let joined = bad
-            .into_iter()
-            .map(|(k, v)| format!("{}='{}'", k, v))
-            .collect::<Vec<_>>()
-            .join(", ");
-        bail!("json pointers must start with '/': {}", joined);
+
+    //type declarations must name a configured pointer so a typo cannot silently disable the check
+    if let Some(types) = &cfg.pointers.expected_types {
+        let mut unknown: Vec<&str> = types
+            .keys()
+            .map(|k| k.as_str())
+            .filter(|k| !named.iter().any(|(name, _)| name == k))
+            .collect();
+        if !unknown.is_empty() {
+            unknown.sort_unstable();
+            bail!(
+                "pointers.expected_types names pointers that are not configured: {}",
+                unknown.join(", ")
+            );
+        }
     }
 
     //validate thresholds when provided so negative or non-finite values are rejected early
@@ -127,6 +1255,110 @@ fn validate_config(cfg: &AppConfig) -> Result<()> {
                 bail!("epsilon_efficiency_j_per_th must be >= 0 and finite");
             }
         }
+        if let Some(w) = t.efficiency_trend_window {
+            if w < 2 {
+                bail!("efficiency_trend_window must be >= 2");
+            }
+        }
+        if let Some(v) = t.efficiency_drift_per_hour {
+            if !(v.is_finite() && v > 0.0) {
+                bail!("efficiency_drift_per_hour must be > 0 and finite");
+            }
+        }
+        if let Some(v) = t.efficiency_consistency_tolerance_pct {
+            if !(v.is_finite() && v >= 0.0) {
+                bail!("efficiency_consistency_tolerance_pct must be >= 0 and finite");
+            }
+        }
+        if let Some(v) = t.efficiency_degraded_pct {
+            if !(v.is_finite() && v > 0.0) {
+                bail!("efficiency_degraded_pct must be > 0 and finite");
+            }
+        }
+        if t.min_hashrate_ths
+            .is_some_and(|v| !(v.is_finite() && v >= 0.0))
+        {
+            bail!("min_hashrate_ths must be >= 0 and finite");
+        }
+        if t.stall_polls == Some(0) {
+            bail!("stall_polls must be > 0");
+        }
+        if t.efficiency_baseline_window == Some(0) {
+            bail!("efficiency_baseline_window must be > 0");
+        }
+        if t.temp_alert_c.is_some_and(|v| !v.is_finite()) {
+            bail!("temp_alert_c must be finite");
+        }
+        if t.reject_ratio_alert
+            .is_some_and(|v| !(0.0..=1.0).contains(&v))
+        {
+            bail!("reject_ratio_alert must be between 0 and 1");
+        }
+        if t.max_plausible_uptime_secs == Some(0) {
+            bail!("max_plausible_uptime_secs must be > 0");
+        }
+        for (name, bound) in [
+            ("max_plausible_hashrate_ths", t.max_plausible_hashrate_ths),
+            ("max_plausible_temp_c", t.max_plausible_temp_c),
+        ] {
+            if bound.is_some_and(|v| !(v.is_finite() && v > 0.0)) {
+                bail!("{} must be > 0 and finite", name);
+            }
+        }
+        if let Some(v) = t.jitter_threshold_ms {
+            if !(v.is_finite() && v > 0.0) {
+                bail!("jitter_threshold_ms must be > 0 and finite");
+            }
+        }
+        if t.jitter_window.is_some_and(|w| w < 2) {
+            bail!("jitter_window must be >= 2");
+        }
+    }
+
+    if let Some(a) = &cfg.adaptive_interval {
+        if a.max_interval_secs < cfg.poll_interval_secs {
+            bail!("adaptive_interval.max_interval_secs must be >= poll_interval_secs");
+        }
+        if let Some(f) = a.factor {
+            if !(f.is_finite() && f > 1.0) {
+                bail!("adaptive_interval.factor must be > 1 and finite");
+            }
+        }
+        if a.stable_polls == Some(0) {
+            bail!("adaptive_interval.stable_polls must be > 0");
+        }
+    }
+    if let Some(b) = &cfg.failure_backoff {
+        if b.max_interval_secs < cfg.poll_interval_secs {
+            bail!("failure_backoff.max_interval_secs must be >= poll_interval_secs");
+        }
+        if let Some(f) = b.factor {
+            if !(f.is_finite() && f > 1.0) {
+                bail!("failure_backoff.factor must be > 1 and finite");
+            }
+        }
+    }
+    if let Some(c) = &cfg.circuit_breaker {
+        if c.failure_threshold == 0 {
+            bail!("circuit_breaker.failure_threshold must be > 0");
+        }
+        if c.open_interval_secs == Some(0) {
+            bail!("circuit_breaker.open_interval_secs must be > 0");
+        }
+    }
+
+    //wide records need somewhere to go; catch the missing path at load instead of on first poll
+    if cfg.wide_records.unwrap_or(false) && cfg.storage.records_path.is_none() {
+        bail!("wide_records requires storage.records_path");
+    }
+    if cfg.wide_record_keepalive_polls == Some(0) {
+        bail!("wide_record_keepalive_polls must be > 0");
+    }
+
+    //every device must be a valid config on its own; errors name the entry
+    for (i, device) in cfg.devices.iter().flatten().enumerate() {
+        validate_config(&device_config(cfg, device))
+            .with_context(|| format!("devices[{}] ({})", i, device.name))?;
     }
 
     // validate hashrate scale when provided so we avoid NaNs or negative factors
@@ -135,5 +1367,590 @@ fn validate_config(cfg: &AppConfig) -> Result<()> {
             bail!("hashrate_scale must be > 0 and finite");
         }
     }
+    if let Some(s) = cfg.pointers.power_scale {
+        if !(s.is_finite() && s > 0.0) {
+            bail!("power_scale must be > 0 and finite");
+        }
+    }
+    if let Some(s) = cfg.pointers.temp_scale {
+        if !(s.is_finite() && s > 0.0) {
+            bail!("temp_scale must be > 0 and finite");
+        }
+    }
+    if let Some(o) = cfg.pointers.temp_offset_c {
+        if !o.is_finite() {
+            bail!("temp_offset_c must be finite");
+        }
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_JSON: &str = r#"{
+        "http": { "endpoint_url": "http://127.0.0.1/api/system/info", "headers": null, "timeout_secs": 5 },
+        "pointers": { "json_pointer_all_time": "/bestDiff", "json_pointer_boot_best": "/bestSessionDiff" },
+        "poll_interval_secs": 10,
+        "storage": { "events_path": "events.jsonl", "state_path": "state.json" },
+        "thresholds": { "epsilon_hashrate_ths": 0.01, "epsilon_hashrate_th": 0.5 }
+        STRICT
+    }"#;
+
+    fn write_config(dir: &tempfile::TempDir, strict: bool) -> std::path::PathBuf {
+        let strict_line = if strict {
+            r#", "strict_config": true"#
+        } else {
+            ""
+        };
+        let path = dir.path().join("config.json");
+        fs::write(&path, BASE_JSON.replace("STRICT", strict_line)).expect("write config");
+        path
+    }
+
+    #[test]
+    fn test_unknown_keys_reported_with_path() {
+        // misspelled threshold key should be reported with its full dotted path
+        let (_, unknown) = parse_config_str(&BASE_JSON.replace("STRICT", "")).unwrap();
+        assert_eq!(unknown, vec!["thresholds.epsilon_hashrate_th".to_string()]);
+    }
+
+    #[test]
+    fn test_unknown_keys_lenient_loads() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let cfg = load_config(&[write_config(&dir, false)]).expect("lenient load");
+        assert_eq!(cfg.poll_interval_secs, 10);
+    }
+
+    #[test]
+    fn test_unknown_keys_strict_fails() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let err = load_config(&[write_config(&dir, true)]).unwrap_err();
+        let msg = format!("{}", err);
+        assert!(msg.contains("unknown config keys"));
+        assert!(msg.contains("thresholds.epsilon_hashrate_th"));
+    }
+
+    #[test]
+    fn test_unknown_keys_toml() {
+        let text = r#"
+poll_interval_secs = 10
+pol_interval = 5
+
+[http]
+endpoint_url = "http://127.0.0.1/api/system/info"
+
+[pointers]
+json_pointer_all_time = "/bestDiff"
+json_pointer_boot_best = "/bestSessionDiff"
+
+[storage]
+events_path = "events.jsonl"
+state_path = "state.json"
+"#;
+        let (_, unknown) = parse_config_str(text).unwrap();
+        assert_eq!(unknown, vec!["pol_interval".to_string()]);
+    }
+
+    //one logical config with optional pointers, fallback lists and nested tables, in both formats
+    const FULL_JSON: &str = r#"{
+        "http": {
+            "endpoint_url": "http://10.0.0.5/api/system/info",
+            "timeout_secs": 5,
+            "headers": { "X-Api-Key": "abc" },
+            "retry": { "max_retries": 4, "base_delay_ms": 250, "jitter": true }
+        },
+        "pointers": {
+            "json_pointer_all_time": "/bestDiff",
+            "json_pointer_boot_best": "/bestSessionDiff",
+            "json_pointer_uptime_secs": "/uptimeSeconds",
+            "json_pointer_hashrate_ths": ["/hashRate", "/hashrate"],
+            "json_pointer_temp_c": "/temp",
+            "hashrate_scale": 0.001,
+            "decimal_separator": "comma",
+            "expected_types": { "json_pointer_uptime_secs": "integer" }
+        },
+        "poll_interval_secs": 10,
+        "storage": { "events_path": "events.jsonl", "state_path": "state.json" },
+        "thresholds": {
+            "epsilon_hashrate_ths": 0.02,
+            "warmup_secs": 120,
+            "temp_alert_c": 72.5,
+            "ignore_boot_best_reset_on_all_time_update": true
+        },
+        "adaptive_interval": { "max_interval_secs": 120, "factor": 1.5 },
+        "histograms": { "hashrate_ths": [1.0, 1.2, 1.4] },
+        "notifications": {
+            "queued": true,
+            "discord": { "webhook_url": "https://discord.example/hook", "events": ["boot_detected"] }
+        }
+    }"#;
+
+    const FULL_TOML: &str = r#"
+poll_interval_secs = 10
+
+[http]
+endpoint_url = "http://10.0.0.5/api/system/info"
+timeout_secs = 5
+
+[http.headers]
+X-Api-Key = "abc"
+
+[http.retry]
+max_retries = 4
+base_delay_ms = 250
+jitter = true
+
+[pointers]
+json_pointer_all_time = "/bestDiff"
+json_pointer_boot_best = "/bestSessionDiff"
+json_pointer_uptime_secs = "/uptimeSeconds"
+json_pointer_hashrate_ths = ["/hashRate", "/hashrate"]
+json_pointer_temp_c = "/temp"
+hashrate_scale = 0.001
+decimal_separator = "comma"
+expected_types = { json_pointer_uptime_secs = "integer" }
+
+[storage]
+events_path = "events.jsonl"
+state_path = "state.json"
+
+[thresholds]
+epsilon_hashrate_ths = 0.02
+warmup_secs = 120
+temp_alert_c = 72.5
+ignore_boot_best_reset_on_all_time_update = true
+
+[adaptive_interval]
+max_interval_secs = 120
+factor = 1.5
+
+[histograms]
+hashrate_ths = [1.0, 1.2, 1.4]
+
+[notifications]
+queued = true
+
+[notifications.discord]
+webhook_url = "https://discord.example/hook"
+events = ["boot_detected"]
+"#;
+
+    #[test]
+    fn test_json_and_toml_load_identically() {
+        let (from_json, unknown_json) = parse_config_str(FULL_JSON).unwrap();
+        let (from_toml, unknown_toml) = parse_config_str(FULL_TOML).unwrap();
+        assert!(unknown_json.is_empty() && unknown_toml.is_empty());
+        assert_eq!(
+            serde_json::to_value(&from_json).unwrap(),
+            serde_json::to_value(&from_toml).unwrap()
+        );
+        assert_eq!(config_hash(&from_json), config_hash(&from_toml));
+
+        // optional pointers that are left out stay unset rather than defaulting to ""
+        assert_eq!(from_toml.pointers.json_pointer_boot_id, None);
+        assert_eq!(
+            from_toml.pointers.json_pointer_hashrate_ths,
+            Some(PointerSpec::Candidates(vec![
+                "/hashRate".to_string(),
+                "/hashrate".to_string()
+            ]))
+        );
+        let t = from_toml.thresholds.as_ref().unwrap();
+        assert_eq!(t.temp_alert_c, Some(72.5));
+        assert_eq!(t.ignore_boot_best_reset_on_all_time_update, Some(true));
+        assert_eq!(t.epsilon_efficiency_j_per_th, None);
+
+        // both go through the file loader and validation the same way
+        let dir = tempfile::tempdir().expect("tempdir");
+        let json_path = dir.path().join("config.json");
+        let toml_path = dir.path().join("config.toml");
+        fs::write(&json_path, FULL_JSON).unwrap();
+        fs::write(&toml_path, FULL_TOML).unwrap();
+        assert_eq!(
+            serde_json::to_value(load_config(&[&json_path]).unwrap()).unwrap(),
+            serde_json::to_value(load_config(&[&toml_path]).unwrap()).unwrap()
+        );
+    }
+
+    const DEVICES_JSON: &str = r#"{
+        "http": { "endpoint_url": "http://10.0.0.5/api/system/info" },
+        "pointers": { "json_pointer_all_time": "/bestDiff", "json_pointer_boot_best": "/bestSessionDiff" },
+        "poll_interval_secs": 10,
+        "storage": { "events_path": "events.jsonl", "state_path": "state/bitaxe.json", "snapshot_dir": "snaps", "snapshot_interval_secs": 3600 },
+        "device_name": "desk",
+        "devices": [
+            { "name": "garage", "endpoint_url": "http://10.0.0.6/api/system/info" },
+            {
+                "name": "shed_2",
+                "endpoint_url": "http://10.0.0.7/api/system/info",
+                "state_path": "shed.json",
+                "pointers": {
+                    "json_pointer_all_time": "/best",
+                    "json_pointer_boot_best": "/sessionBest",
+                    "json_pointer_temp_c": "/asicTemp"
+                },
+                "thresholds": { "warmup_secs": 300, "temp_alert_c": 68.0 }
+            }
+        ]
+    }"#;
+
+    const DEVICES_TOML: &str = r#"
+poll_interval_secs = 10
+device_name = "desk"
+
+[http]
+endpoint_url = "http://10.0.0.5/api/system/info"
+
+[pointers]
+json_pointer_all_time = "/bestDiff"
+json_pointer_boot_best = "/bestSessionDiff"
+
+[storage]
+events_path = "events.jsonl"
+state_path = "state/bitaxe.json"
+snapshot_dir = "snaps"
+snapshot_interval_secs = 3600
+
+[[devices]]
+name = "garage"
+endpoint_url = "http://10.0.0.6/api/system/info"
+
+[[devices]]
+name = "shed_2"
+endpoint_url = "http://10.0.0.7/api/system/info"
+state_path = "shed.json"
+
+[devices.pointers]
+json_pointer_all_time = "/best"
+json_pointer_boot_best = "/sessionBest"
+json_pointer_temp_c = "/asicTemp"
+
+[devices.thresholds]
+warmup_secs = 300
+temp_alert_c = 68.0
+"#;
+
+    #[test]
+    fn test_devices_load_identically_from_json_and_toml() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let json_path = dir.path().join("config.json");
+        let toml_path = dir.path().join("config.toml");
+        fs::write(&json_path, DEVICES_JSON).unwrap();
+        fs::write(&toml_path, DEVICES_TOML).unwrap();
+        let from_json = load_config(&[&json_path]).unwrap();
+        let from_toml = load_config(&[&toml_path]).unwrap();
+        assert_eq!(
+            serde_json::to_value(&from_json).unwrap(),
+            serde_json::to_value(&from_toml).unwrap()
+        );
+
+        let devices = from_toml.devices.as_deref().unwrap();
+        assert_eq!(devices.len(), 2);
+        // a device without overrides gets the top-level pointers and its own state file
+        let garage = device_config(&from_toml, &devices[0]);
+        assert_eq!(garage.http.endpoint_url, "http://10.0.0.6/api/system/info");
+        assert_eq!(garage.device_name.as_deref(), Some("garage"));
+        assert_eq!(garage.storage.state_path, "state/bitaxe-garage.json");
+        assert_eq!(
+            garage.storage.snapshot_dir.as_deref().map(Path::new),
+            Some(Path::new("snaps").join("garage").as_path())
+        );
+        assert_eq!(
+            garage.pointers.json_pointer_all_time.to_string(),
+            "/bestDiff"
+        );
+        assert!(garage.thresholds.is_none() && garage.devices.is_none());
+
+        // [devices.pointers] and [devices.thresholds] belong to the entry above them
+        let shed = device_config(&from_toml, &devices[1]);
+        assert_eq!(shed.storage.state_path, "shed.json");
+        assert_eq!(shed.pointers.json_pointer_all_time.to_string(), "/best");
+        assert_eq!(
+            shed.pointers.json_pointer_temp_c.map(|p| p.to_string()),
+            Some("/asicTemp".to_string())
+        );
+        assert_eq!(shed.pointers.json_pointer_uptime_secs, None);
+        let t = shed.thresholds.as_ref().unwrap();
+        assert_eq!((t.warmup_secs, t.temp_alert_c), (Some(300), Some(68.0)));
+        assert_eq!(t.epsilon_hashrate_ths, None);
+    }
+
+    #[test]
+    fn test_devices_validated_like_the_top_level() {
+        let (cfg, _) = parse_config_str(DEVICES_JSON).unwrap();
+        assert!(validate_config(&cfg).is_ok());
+        let text = DEVICES_JSON.replace("http://10.0.0.7", "10.0.0.7");
+        let err = validate_config(&parse_config_str(&text).unwrap().0).unwrap_err();
+        assert!(format!("{:#}", err).contains("devices[1] (shed_2)"));
+    }
+
+    #[test]
+    fn test_pointer_fallback_list_parses_and_validates() {
+        let text = BASE_JSON.replace("STRICT", "").replace(
+            r#""json_pointer_boot_best": "/bestSessionDiff""#,
+            r#""json_pointer_boot_best": "/bestSessionDiff", "json_pointer_hashrate_ths": ["/hashrate", "hashRate"]"#,
+        );
+        let (cfg, unknown) = parse_config_str(&text).unwrap();
+        assert_eq!(unknown, vec!["thresholds.epsilon_hashrate_th".to_string()]);
+        assert_eq!(
+            cfg.pointers.json_pointer_hashrate_ths,
+            Some(PointerSpec::Candidates(vec![
+                "/hashrate".to_string(),
+                "hashRate".to_string()
+            ]))
+        );
+        // every candidate must be a valid pointer, not just the first
+        let msg = format!("{}", validate_config(&cfg).unwrap_err());
+        assert!(msg.contains("json_pointer_hashrate_ths='hashRate'"));
+    }
+
+    #[test]
+    fn test_config_hash_tracks_content_not_format() {
+        let (a, _) = parse_config_str(&BASE_JSON.replace("STRICT", "")).unwrap();
+        let (b, _) =
+            parse_config_str(&BASE_JSON.replace("STRICT", "").replace(",", ",\n  ")).unwrap();
+        assert_eq!(config_hash(&a), config_hash(&b));
+
+        // settings that do not change what a value means leave the hash alone
+        let mut c = a.clone();
+        c.poll_interval_secs = 30;
+        c.heartbeat_interval_secs = Some(600);
+        assert_eq!(config_hash(&a), config_hash(&c));
+        let saved = crate::metrics::MonitorState {
+            config_hash: Some(config_hash(&a)),
+            ..Default::default()
+        };
+        assert!(crate::persist::config_hash_mismatch(&saved, &config_hash(&c)).is_none());
+
+        // a different scale, pointer or device does not
+        let mut d = a.clone();
+        d.pointers.hashrate_scale = Some(1.0);
+        assert_ne!(config_hash(&a), config_hash(&d));
+        let mut e = a.clone();
+        e.pointers.json_pointer_temp_c = Some("/vrTemp".into());
+        assert_ne!(config_hash(&a), config_hash(&e));
+        let mut f = a.clone();
+        f.http.endpoint_url = "http://10.0.0.6/api/system/info".to_string();
+        assert_ne!(config_hash(&a), config_hash(&f));
+    }
+
+    #[test]
+    fn test_poll_interval_floor_enforced() {
+        let (mut cfg, _) = parse_config_str(&BASE_JSON.replace("STRICT", "")).unwrap();
+        cfg.poll_interval_secs = 1;
+        let msg = format!("{}", validate_config(&cfg).unwrap_err());
+        assert!(msg.contains("below the minimum of 2s"), "{}", msg);
+
+        // a configured minimum can raise the floor but not lower it
+        cfg.poll_interval_secs = 10;
+        cfg.min_poll_interval_secs = Some(30);
+        assert!(validate_config(&cfg).is_err());
+        cfg.poll_interval_secs = 30;
+        assert!(validate_config(&cfg).is_ok());
+        cfg.min_poll_interval_secs = Some(1);
+        let msg = format!("{}", validate_config(&cfg).unwrap_err());
+        assert!(msg.contains("built-in floor"), "{}", msg);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_event_rotation_needs_jsonl_backend() {
+        let (mut cfg, _) = parse_config_str(&BASE_JSON.replace("STRICT", "")).unwrap();
+        cfg.storage.max_event_bytes = Some(1_000_000);
+        assert!(validate_config(&cfg).is_ok());
+        cfg.storage.backend = Some(StorageBackend::Sqlite);
+        let msg = format!("{}", validate_config(&cfg).unwrap_err());
+        assert!(msg.contains("only apply to the jsonl backend"), "{}", msg);
+    }
+
+    #[test]
+    fn test_http_body_requires_post() {
+        let json = BASE_JSON.replace("STRICT", "").replace(
+            r#""endpoint_url""#,
+            r#""method": "POST", "body": "{\"key\":1}", "endpoint_url""#,
+        );
+        let (mut cfg, _) = parse_config_str(&json).unwrap();
+        assert_eq!(cfg.http.method, Some(HttpMethod::Post));
+        assert!(validate_config(&cfg).is_ok());
+        cfg.http.method = Some(HttpMethod::Get);
+        let msg = format!("{}", validate_config(&cfg).unwrap_err());
+        assert!(msg.contains("only sent with POST"), "{}", msg);
+    }
+
+    #[test]
+    fn test_webhook_template_checked_at_load() {
+        let (mut cfg, _) = parse_config_str(&BASE_JSON.replace("STRICT", "")).unwrap();
+        let mut webhook = WebhookConfig {
+            url: "https://hooks.example.com/x".to_string(),
+            events: vec!["boot_detected".to_string()],
+            payload_template: Some(r#"{"text": "{device} {event}", "value": {value}}"#.to_string()),
+            mode: None,
+        };
+        cfg.notifications = Some(NotificationsConfig {
+            discord: None,
+            telegram: None,
+            webhook: Some(webhook.clone()),
+            queued: None,
+            flush_timeout_secs: None,
+            dedupe: None,
+        });
+        assert!(validate_config(&cfg).is_ok());
+
+        // a quoted {value} is fine, a missing brace is not
+        webhook.payload_template = Some(r#"{"value": "{value}"}"#.to_string());
+        cfg.notifications.as_mut().unwrap().webhook = Some(webhook.clone());
+        assert!(validate_config(&cfg).is_ok());
+        webhook.payload_template = Some(r#"{"text": "{event}""#.to_string());
+        cfg.notifications.as_mut().unwrap().webhook = Some(webhook);
+        let msg = format!("{:#}", validate_config(&cfg).unwrap_err());
+        assert!(
+            msg.contains("payload_template is not valid json"),
+            "{}",
+            msg
+        );
+    }
+
+    #[test]
+    fn test_env_vars_expanded_in_string_fields() {
+        std::env::set_var("BITAXE_MONITOR_TEST_HOST", "10.0.0.7");
+        std::env::set_var("BITAXE_MONITOR_TEST_DIR", "/var/lib/bitaxe");
+        let json = BASE_JSON
+            .replace("STRICT", "")
+            .replace("127.0.0.1", "${BITAXE_MONITOR_TEST_HOST}")
+            .replace(
+                r#""events.jsonl""#,
+                r#""${BITAXE_MONITOR_TEST_DIR}/events.jsonl""#,
+            );
+        let (cfg, unknown) = parse_config_str(&json).unwrap();
+        assert_eq!(cfg.http.endpoint_url, "http://10.0.0.7/api/system/info");
+        assert_eq!(cfg.storage.events_path, "/var/lib/bitaxe/events.jsonl");
+        // unknown keys are still reported on this path
+        assert_eq!(unknown, vec!["thresholds.epsilon_hashrate_th".to_string()]);
+
+        let toml = "poll_interval_secs = 10\n\
+            [http]\nendpoint_url = \"http://${BITAXE_MONITOR_TEST_HOST}/\"\n\
+            [pointers]\njson_pointer_all_time = \"/a\"\njson_pointer_boot_best = \"/b\"\n\
+            [storage]\nevents_path = \"e.jsonl\"\nstate_path = \"s.json\"\n";
+        let (cfg, _) = parse_config_str(toml).unwrap();
+        assert_eq!(cfg.http.endpoint_url, "http://10.0.0.7/");
+
+        let missing = BASE_JSON
+            .replace("STRICT", "")
+            .replace("state.json", "${BITAXE_MONITOR_TEST_UNSET}/state.json");
+        let msg = format!("{:#}", parse_config_str(&missing).unwrap_err());
+        assert!(
+            msg.contains("storage.state_path") && msg.contains("BITAXE_MONITOR_TEST_UNSET"),
+            "{}",
+            msg
+        );
+    }
+
+    #[test]
+    fn test_merged_configs_override_left_to_right() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let base = dir.path().join("base.json");
+        fs::write(&base, BASE_JSON.replace("STRICT", "")).unwrap();
+        let local = dir.path().join("local.toml");
+        fs::write(
+            &local,
+            "poll_interval_secs = 30\n\
+             [http]\nendpoint_url = \"http://10.0.0.9/api/system/info\"\n\
+             [pointers]\njson_pointer_hashrate_ths = \"/hashRate\"\n",
+        )
+        .unwrap();
+        let cfg = load_config(&[&base, &local]).unwrap();
+        // scalars are replaced
+        assert_eq!(cfg.poll_interval_secs, 30);
+        assert_eq!(cfg.http.endpoint_url, "http://10.0.0.9/api/system/info");
+        // nested tables merge key by key, keeping the base's other fields
+        assert_eq!(cfg.http.timeout_secs, Some(5));
+        assert_eq!(cfg.pointers.json_pointer_all_time.to_string(), "/bestDiff");
+        assert_eq!(
+            cfg.pointers
+                .json_pointer_hashrate_ths
+                .map(|p| p.to_string()),
+            Some("/hashRate".to_string())
+        );
+
+        // the override alone is missing required fields
+        let err = load_config(&[&local]).unwrap_err();
+        assert!(format!("{:#}", err).contains("missing field"), "{:#}", err);
+    }
+
+    #[test]
+    fn test_config_paths_split_env_on_commas() {
+        let env = OsString::from("config.base.toml, config.local.json,,");
+        assert_eq!(
+            config_paths(&[], Some(env)),
+            [
+                PathBuf::from("config.base.toml"),
+                PathBuf::from("config.local.json")
+            ]
+        );
+
+        // --config flags win over the environment and are taken whole, commas included
+        let flags = [PathBuf::from("a.toml"), PathBuf::from("rig 1, garage.json")];
+        assert_eq!(config_paths(&flags, Some(OsString::from("x.json"))), flags);
+        assert_eq!(config_paths(&[], None), [PathBuf::from("config.json")]);
+        assert_eq!(
+            config_paths(&[], Some(OsString::from(" , "))),
+            [PathBuf::from("config.json")]
+        );
+    }
+
+    #[test]
+    fn test_reload_keeps_startup_only_settings() {
+        let (running, _) = parse_config_str(&BASE_JSON.replace("STRICT", "")).unwrap();
+        let mut next = running.clone();
+        next.poll_interval_secs = 30;
+        next.pointers.json_pointer_temp_c = Some("/temp".into());
+        assert!(keep_startup_settings(&running, &mut next)
+            .unwrap()
+            .is_empty());
+        assert_eq!(next.poll_interval_secs, 30);
+
+        // a new events path would split the log between sinks, so it waits for a restart
+        next.storage.events_path = "other.jsonl".to_string();
+        next.http.timeout_secs = Some(99);
+        assert_eq!(
+            keep_startup_settings(&running, &mut next).unwrap(),
+            vec!["http", "storage"]
+        );
+        assert_eq!(next.storage.events_path, running.storage.events_path);
+        assert_eq!(next.http.timeout_secs, running.http.timeout_secs);
+        assert_eq!(next.poll_interval_secs, 30);
+        assert!(next.pointers.json_pointer_temp_c.is_some());
+    }
+
+    #[test]
+    fn test_leading_slash_rule_only_for_pointer_kind() {
+        let json = r#"{
+            "http": { "endpoint_url": "http://127.0.0.1/api/system/info" },
+            "pointers": {
+                "json_pointer_all_time": "$.bestDiff",
+                "json_pointer_boot_best": "$.workers[?@.name=='main'].bestSessionDiff"
+                KIND
+            },
+            "poll_interval_secs": 10,
+            "storage": { "events_path": "events.jsonl", "state_path": "state.json" }
+        }"#;
+        let (cfg, _) = parse_config_str(&json.replace("KIND", "")).unwrap();
+        let msg = format!("{}", validate_config(&cfg).unwrap_err());
+        assert!(msg.contains("must start with '/'"), "{}", msg);
+
+        let jsonpath = json.replace("KIND", r#", "selector_kind": "jsonpath""#);
+        let (cfg, _) = parse_config_str(&jsonpath).unwrap();
+        assert!(validate_config(&cfg).is_ok());
+
+        let broken = jsonpath.replace("$.bestDiff", "$.bestDiff[");
+        let (cfg, _) = parse_config_str(&broken).unwrap();
+        let msg = format!("{}", validate_config(&cfg).unwrap_err());
+        assert!(
+            msg.contains("invalid jsonpath json_pointer_all_time"),
+            "{}",
+            msg
+        );
+    }
+}
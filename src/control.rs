@@ -0,0 +1,196 @@
+use crate::metrics::MonitorState;
+use crate::persist::{load_state, save_state};
+use anyhow::{bail, Result};
+use std::path::Path;
+use tokio::sync::{mpsc, oneshot};
+
+pub const DEFAULT_CHECKPOINT_DIR: &str = "checkpoints";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlCommand {
+    Checkpoint(String),
+    Restore(String),
+}
+
+//a command read from the socket plus the channel its one-line reply goes back on
+pub type ControlRequest = (ControlCommand, oneshot::Sender<String>);
+
+//one command per line: "checkpoint [name]" or "restore [name]"; the name defaults to "default"
+pub fn parse_command(line: &str) -> Result<ControlCommand> {
+    let mut parts = line.split_whitespace();
+    let verb = parts.next().unwrap_or("");
+    let name = parts.next().unwrap_or("default").to_string();
+    if parts.next().is_some() {
+        bail!("expected one slot name");
+    }
+    //slot names become file names, so keep them to a safe character set
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!("slot names may only contain letters, digits, '-' and '_'");
+    }
+    match verb {
+        "checkpoint" => Ok(ControlCommand::Checkpoint(name)),
+        "restore" => Ok(ControlCommand::Restore(name)),
+        "" => bail!("empty command"),
+        other => bail!(
+            "unknown command {:?} (expected checkpoint or restore)",
+            other
+        ),
+    }
+}
+
+pub fn slot_path(dir: &str, name: &str) -> String {
+    Path::new(dir)
+        .join(format!("{}.json", name))
+        .to_string_lossy()
+        .to_string()
+}
+
+//write the full state to the named slot; returns the slot file path
+pub fn checkpoint(state: &MonitorState, dir: &str, name: &str) -> Result<String> {
+    let path = slot_path(dir, name);
+    save_state(&path, state)?;
+    Ok(path)
+}
+
+//bring back the bests saved in the slot; device-tracking fields (last uptime, boot marker, share
+//baselines) and in-memory windows stay current so the next poll is not mistaken for a reboot
+pub fn restore(state: &mut MonitorState, dir: &str, name: &str) -> Result<()> {
+    let path = slot_path(dir, name);
+    if !Path::new(&path).exists() {
+        bail!("no checkpoint named {:?} in {}", name, dir);
+    }
+    let saved = load_state(&path)?;
+    state.tool_global_all_time_best = saved.tool_global_all_time_best;
+    state.tool_best_hashrate_ths = saved.tool_best_hashrate_ths;
+    state.tool_best_efficiency_j_per_th = saved.tool_best_efficiency_j_per_th;
+    state.tool_worst_hashrate_ths = saved.tool_worst_hashrate_ths;
+    state.tool_worst_efficiency_j_per_th = saved.tool_worst_efficiency_j_per_th;
+    state.tool_best_difficulty = saved.tool_best_difficulty;
+    state.tool_best_difficulty_display = saved.tool_best_difficulty_display;
+    state.tool_max_temp_c = saved.tool_max_temp_c;
+    state.tool_min_power_w = saved.tool_min_power_w;
+    state.tool_max_power_w = saved.tool_max_power_w;
+    state.tool_global_all_time_best_at = saved.tool_global_all_time_best_at;
+    state.tool_best_hashrate_ths_at = saved.tool_best_hashrate_ths_at;
+    state.tool_best_efficiency_j_per_th_at = saved.tool_best_efficiency_j_per_th_at;
+    state.tool_worst_hashrate_ths_at = saved.tool_worst_hashrate_ths_at;
+    state.tool_worst_efficiency_j_per_th_at = saved.tool_worst_efficiency_j_per_th_at;
+    state.tool_best_difficulty_at = saved.tool_best_difficulty_at;
+    state.tool_max_temp_c_at = saved.tool_max_temp_c_at;
+    state.tool_min_power_w_at = saved.tool_min_power_w_at;
+    state.tool_max_power_w_at = saved.tool_max_power_w_at;
+    state.recent_hashrate_ths = saved.recent_hashrate_ths;
+    state.efficiency_baseline = saved.efficiency_baseline;
+    Ok(())
+}
+
+//bind before returning so a bad socket path fails startup; commands are handed to the poll loop,
+//which owns the state, and its reply is written back as one line
+#[cfg(unix)]
+pub async fn spawn(socket_path: &str, tx: mpsc::Sender<ControlRequest>) -> Result<()> {
+    use anyhow::Context;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixListener;
+
+    if std::fs::symlink_metadata(socket_path).is_ok() {
+        std::fs::remove_file(socket_path)
+            .with_context(|| format!("failed to remove stale socket {}", socket_path))?;
+    }
+    let listener = UnixListener::bind(socket_path)
+        .with_context(|| format!("failed to bind control socket {}", socket_path))?;
+    tokio::spawn(async move {
+        loop {
+            let (sock, _) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    eprintln!("[bitaxe_monitor] WARN: control socket accept failed: {err}");
+                    continue;
+                }
+            };
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let (read, mut write) = sock.into_split();
+                let mut lines = BufReader::new(read).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let reply = match parse_command(&line) {
+                        Ok(cmd) => {
+                            let (reply_tx, reply_rx) = oneshot::channel();
+                            if tx.send((cmd, reply_tx)).await.is_err() {
+                                break;
+                            }
+                            reply_rx
+                                .await
+                                .unwrap_or_else(|_| "error: monitor is shutting down".to_string())
+                        }
+                        Err(err) => format!("error: {}", err),
+                    };
+                    if write.write_all(reply.as_bytes()).await.is_err()
+                        || write.write_all(b"\n").await.is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn spawn(_socket_path: &str, _tx: mpsc::Sender<ControlRequest>) -> Result<()> {
+    anyhow::bail!("control_socket_path requires unix domain sockets, which this platform lacks")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{detect_changes, Displayed, Metrics, Thresholds};
+
+    #[test]
+    fn test_checkpoint_then_restore_reverts_bests() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let dir = tmp.path().to_string_lossy().to_string();
+        let mut state = MonitorState::new();
+        let poll = |state: &mut MonitorState, uptime: u64, best: f64, hashrate: f64| {
+            detect_changes(
+                state,
+                Displayed {
+                    all_time: best,
+                    boot_best: best,
+                },
+                Metrics {
+                    uptime_secs: Some(uptime),
+                    hashrate_ths: Some(hashrate),
+                    efficiency_j_per_th: Some(16.0),
+                    ..Default::default()
+                },
+                Thresholds::default(),
+                chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            )
+        };
+        poll(&mut state, 600, 100.0, 1.2);
+        assert_eq!(
+            parse_command("checkpoint before-oc").unwrap(),
+            ControlCommand::Checkpoint("before-oc".to_string())
+        );
+        checkpoint(&state, &dir, "before-oc").unwrap();
+
+        // risky settings push the bests up
+        poll(&mut state, 1200, 250.0, 1.9);
+        assert_eq!(state.tool_best_hashrate_ths, Some(1.9));
+
+        restore(&mut state, &dir, "before-oc").unwrap();
+        assert_eq!(state.tool_best_hashrate_ths, Some(1.2));
+        assert_eq!(state.tool_global_all_time_best, 100.0);
+        // device tracking is left alone, so the next poll is not read as a reboot
+        assert_eq!(state.last_uptime_secs, Some(1200));
+        assert!(!poll(&mut state, 1800, 250.0, 1.5).boot_detected);
+
+        assert!(restore(&mut state, &dir, "missing").is_err());
+        assert!(parse_command("restore ../etc").is_err());
+        assert!(parse_command("rollback x").is_err());
+    }
+}
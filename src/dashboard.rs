@@ -0,0 +1,148 @@
+use crate::config::DashboardConfig;
+use crate::exporter::{SharedSnapshot, Snapshot};
+use anyhow::{Context, Result};
+use axum::{extract::State, response::Html, routing::get, Json, Router};
+use serde_json::Value;
+
+//single page with inline css/js; it polls /api/state, so nothing is fetched from outside
+const PAGE: &str = include_str!("dashboard.html");
+
+//current values from the latest poll plus the tracked bests; values not seen yet are null
+pub fn state_json(snap: &Snapshot) -> Value {
+    let s = &snap.state;
+    //the device's own formatting when it gave one, e.g. "4.5G"
+    let best_difficulty = match &s.tool_best_difficulty_display {
+        Some(display) => Value::from(display.clone()),
+        None => serde_json::json!(s.tool_best_difficulty),
+    };
+    serde_json::json!({
+        "last_poll": snap.last_poll,
+        "current": {
+            "hashrate_ths": s.last_hashrate_ths,
+            "rolling_avg_hashrate_ths": s.rolling_avg_hashrate_ths(),
+            "efficiency_j_per_th": s.last_efficiency_j_per_th,
+            "voltage_mv": s.last_voltage_mv,
+            "frequency_mhz": s.last_frequency_mhz,
+            "uptime_secs": s.last_uptime_secs,
+            "displayed_all_time": s.last_displayed_all_time,
+            "displayed_boot_best": s.last_displayed_boot_best,
+        },
+        "bests": {
+            "tool_global_all_time_best": {
+                "value": s.tool_global_all_time_best,
+                "at": s.tool_global_all_time_best_at,
+            },
+            "best_hashrate_ths": {
+                "value": s.tool_best_hashrate_ths,
+                "at": s.tool_best_hashrate_ths_at,
+            },
+            "best_efficiency_j_per_th": {
+                "value": s.tool_best_efficiency_j_per_th,
+                "at": s.tool_best_efficiency_j_per_th_at,
+            },
+            "best_difficulty": {
+                "value": best_difficulty,
+                "at": s.tool_best_difficulty_at,
+            },
+            "max_temp_c": {
+                "value": s.tool_max_temp_c,
+                "at": s.tool_max_temp_c_at,
+            },
+            "max_power_w": {
+                "value": s.tool_max_power_w,
+                "at": s.tool_max_power_w_at,
+            },
+        },
+    })
+}
+
+async fn page_handler() -> Html<&'static str> {
+    Html(PAGE)
+}
+
+async fn state_handler(State(shared): State<SharedSnapshot>) -> Json<Value> {
+    let body = {
+        let snap = shared.lock().unwrap_or_else(|e| e.into_inner());
+        state_json(&snap)
+    };
+    Json(body)
+}
+
+//bind before returning so a bad or busy listen_addr fails startup, like the exporter
+pub async fn spawn(cfg: &DashboardConfig, shared: SharedSnapshot) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(&cfg.listen_addr)
+        .await
+        .with_context(|| format!("failed to bind dashboard on {}", cfg.listen_addr))?;
+    let app = Router::new()
+        .route("/", get(page_handler))
+        .route("/api/state", get(state_handler))
+        .with_state(shared);
+    tokio::spawn(async move {
+        if let Err(err) = axum::serve(listener, app).await {
+            eprintln!("[bitaxe_monitor] WARN: dashboard stopped: {err}");
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exporter::publish;
+    use crate::metrics::MonitorState;
+    use chrono::Utc;
+
+    #[test]
+    fn test_state_json_reports_current_values_and_bests() {
+        let mut state = MonitorState::new();
+        state.last_hashrate_ths = Some(1.2);
+        state.tool_best_hashrate_ths = Some(1.6);
+        state.tool_best_difficulty = Some(4.5e9);
+        state.tool_best_difficulty_display = Some("4.5G".to_string());
+        let snap = Snapshot {
+            state,
+            last_poll: None,
+        };
+        let v = state_json(&snap);
+        assert_eq!(v["current"]["hashrate_ths"], 1.2);
+        assert_eq!(v["bests"]["best_hashrate_ths"]["value"], 1.6);
+        assert_eq!(v["bests"]["best_difficulty"]["value"], "4.5G");
+        // nothing seen yet is null rather than 0
+        assert!(v["current"]["efficiency_j_per_th"].is_null());
+        assert!(v["last_poll"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_dashboard_serves_page_and_state() {
+        let shared = SharedSnapshot::default();
+        let mut state = MonitorState::new();
+        state.tool_global_all_time_best = 3.0;
+        publish(&shared, &state, Utc::now());
+
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let cfg = DashboardConfig {
+            listen_addr: addr.clone(),
+        };
+        spawn(&cfg, shared).await.unwrap();
+
+        let page = reqwest::get(format!("http://{}/", addr))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(page.contains("/api/state"));
+        let v: Value = reqwest::get(format!("http://{}/api/state", addr))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(v["bests"]["tool_global_all_time_best"]["value"], 3.0);
+        assert!(v["last_poll"].is_string());
+    }
+}
@@ -0,0 +1,313 @@
+#[cfg(feature = "sqlite")]
+use crate::config::StorageBackend;
+use crate::config::{self, AppConfig, JsonPointers, StorageConfig};
+use crate::fetch::{
+    build_client, device_headers, fetch_text_with_retries, mask_endpoint, RetryPolicy,
+};
+use crate::metrics;
+use crate::persist::{load_state_for, resolve_state_path, state_exists};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+//one line of the doctor checklist; the hint is only printed for failures
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            passed: true,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn fail(name: impl Into<String>, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            passed: false,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+//run every check, print the checklist and fail when any check failed; later checks that depend
+//on an earlier one (pointers need a response) are left out rather than reported as failures
+pub async fn run(config_paths: &[PathBuf]) -> Result<()> {
+    let mut checks = Vec::new();
+    let config = match config::load_config(config_paths) {
+        Ok(config) => {
+            checks.push(Check::pass(
+                "config",
+                format!("{:?} parses and validates", config_paths),
+            ));
+            Some(config)
+        }
+        Err(err) => {
+            checks.push(Check::fail(
+                "config",
+                format!("{:?}: {:#}", config_paths, err),
+                "fix the file, or pass the right one with --config (or BITAXE_MONITOR_CONFIG)",
+            ));
+            None
+        }
+    };
+
+    if let Some(config) = &config {
+        let (endpoint, json) = check_endpoint(config).await;
+        checks.push(endpoint);
+        if let Some(json) = &json {
+            checks.extend(check_pointers(json, &config.pointers));
+        }
+        checks.extend(check_storage(&config.storage));
+        checks.extend(check_state(&config.storage, Utc::now()));
+    }
+
+    print!("{}", render(&checks));
+    let failed = checks.iter().filter(|c| !c.passed).count();
+    if failed > 0 {
+        bail!("{} of {} checks failed", failed, checks.len());
+    }
+    println!("[bitaxe_monitor] all {} checks passed", checks.len());
+    Ok(())
+}
+
+//a single short attempt: the goal is to learn whether the device answers, not to ride out a blip
+async fn check_endpoint(config: &AppConfig) -> (Check, Option<Value>) {
+    let endpoint = mask_endpoint(&config.http.endpoint_url);
+    let client = match device_headers(&config.http).and_then(|_| build_client(&config.http, None)) {
+        Ok(client) => client,
+        Err(err) => {
+            return (
+                Check::fail(
+                    "endpoint",
+                    format!("{:#}", err),
+                    "check the http section (headers, auth_token_env)",
+                ),
+                None,
+            )
+        }
+    };
+    let policy = RetryPolicy::from_config(None, 0, Duration::from_millis(300));
+    let text = match fetch_text_with_retries(&client, &config.http, policy).await {
+        Ok(text) => text,
+        Err(err) => {
+            return (
+                Check::fail(
+                    "endpoint",
+                    format!("{}: {:#}", endpoint, err),
+                    "check http.endpoint_url and that the device is powered and on this network (try it with curl)",
+                ),
+                None,
+            )
+        }
+    };
+    match serde_json::from_str::<Value>(&text) {
+        Ok(json) => (
+            Check::pass("endpoint", format!("{} answered with json", endpoint)),
+            Some(json),
+        ),
+        Err(err) => (
+            Check::fail(
+                "endpoint",
+                format!("{} answered, but not with json: {}", endpoint, err),
+                "http.endpoint_url should be the device's json api (e.g. http://<ip>/api/system/info)",
+            ),
+            None,
+        ),
+    }
+}
+
+//one check per configured pointer; a missing or null value fails
+pub fn check_pointers(json: &Value, ptrs: &JsonPointers) -> Vec<Check> {
+    metrics::resolve_selectors(json, ptrs)
+        .named_pointers()
+        .into_iter()
+        .map(|(name, spec)| {
+            if metrics::pointer_available(json, spec) {
+                Check::pass(name, format!("{} resolves", spec))
+            } else {
+                Check::fail(
+                    name,
+                    format!("{} is missing or null in the response", spec),
+                    "compare the path with the device's json (save it and run validate-pointers --sample), or remove the pointer",
+                )
+            }
+        })
+        .collect()
+}
+
+//every configured file's directory, each probed once; directories that do not exist yet are
+//created on first write, so the nearest existing ancestor is what has to be writable
+pub fn check_storage(storage: &StorageConfig) -> Vec<Check> {
+    let now = Utc::now();
+    let mut files: Vec<(&str, String)> = vec![
+        ("storage.events_path", storage.events_path.clone()),
+        (
+            "storage.state_path",
+            resolve_state_path(&storage.state_path, now),
+        ),
+    ];
+    let optional = [
+        ("storage.records_path", &storage.records_path),
+        ("storage.parquet_path", &storage.parquet_path),
+        ("storage.pid_file", &storage.pid_file),
+    ];
+    for (name, path) in optional {
+        if let Some(path) = path {
+            files.push((name, path.clone()));
+        }
+    }
+    #[cfg(feature = "sqlite")]
+    if storage.backend == Some(StorageBackend::Sqlite) {
+        let db = storage
+            .db_path
+            .as_deref()
+            .unwrap_or(crate::sqlite::DEFAULT_DB_PATH);
+        files.push(("storage.db_path", db.to_string()));
+    }
+
+    let mut dirs: Vec<(PathBuf, Vec<&str>)> = Vec::new();
+    for (name, path) in files {
+        let dir = match Path::new(&path).parent() {
+            Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        match dirs.iter_mut().find(|(d, _)| *d == dir) {
+            Some((_, names)) => names.push(name),
+            None => dirs.push((dir, vec![name])),
+        }
+    }
+    if let Some(dir) = &storage.snapshot_dir {
+        dirs.push((PathBuf::from(dir), vec!["storage.snapshot_dir"]));
+    }
+
+    dirs.into_iter()
+        .map(|(dir, names)| {
+            let name = names.join(", ");
+            match probe_writable(&dir) {
+                Ok(()) => Check::pass(name, format!("{:?} is writable", dir)),
+                Err(err) => Check::fail(
+                    name,
+                    format!("{:?}: {:#}", dir, err),
+                    "create the directory or fix its permissions, or move the path somewhere the monitor's user can write",
+                ),
+            }
+        })
+        .collect()
+}
+
+fn probe_writable(dir: &Path) -> Result<()> {
+    let existing = dir
+        .ancestors()
+        .find(|p| p.exists())
+        .unwrap_or(Path::new("."));
+    if !existing.is_dir() {
+        bail!("{:?} exists but is not a directory", existing);
+    }
+    let probe = existing.join(format!(".bitaxe_monitor_doctor.{}", std::process::id()));
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .map_err(|err| anyhow::anyhow!("cannot create files in {:?}: {}", existing, err))?;
+    fs::remove_file(&probe)?;
+    Ok(())
+}
+
+//only a state that is there but unreadable fails; no state yet is what a first run looks like
+pub fn check_state(storage: &StorageConfig, now: DateTime<Utc>) -> Option<Check> {
+    let path = resolve_state_path(&storage.state_path, now);
+    if !state_exists(&path, storage) {
+        return None;
+    }
+    Some(match load_state_for(&storage.state_path, now, storage) {
+        Ok(_) => Check::pass("state", format!("{} loads", path)),
+        Err(err) => Check::fail(
+            "state",
+            format!("{}: {:#}", path, err),
+            "the file is corrupt or from an incompatible version; move it aside to start fresh (bests are also in the events log)",
+        ),
+    })
+}
+
+pub fn render(checks: &[Check]) -> String {
+    let mut out = String::new();
+    for check in checks {
+        let mark = if check.passed { "PASS" } else { "FAIL" };
+        out.push_str(&format!("[{}] {}: {}\n", mark, check.name, check.detail));
+        if let (false, Some(hint)) = (check.passed, &check.hint) {
+            out.push_str(&format!("       hint: {}\n", hint));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage(dir: &Path) -> StorageConfig {
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+        StorageConfig {
+            events_path: path("events.jsonl"),
+            state_path: path("state.json"),
+            records_path: Some(path("records/wide.jsonl")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check_pointers_flags_missing_and_null() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/bestDiff".into(),
+            json_pointer_boot_best: "/bestSessionDiff".into(),
+            json_pointer_hashrate_ths: Some("/hashRate".into()),
+            json_pointer_temp_c: Some("/temp".into()),
+            ..Default::default()
+        };
+        let json = serde_json::json!({ "bestDiff": "1.2M", "hashRate": 1.2, "temp": null });
+        let failed: Vec<String> = check_pointers(&json, &ptrs)
+            .into_iter()
+            .filter(|c| !c.passed)
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(failed, ["json_pointer_boot_best", "json_pointer_temp_c"]);
+    }
+
+    #[test]
+    fn test_storage_and_state_checks() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let storage = storage(dir.path());
+        // the records directory does not exist yet, which is fine while its parent is writable
+        let checks = check_storage(&storage);
+        assert_eq!(checks.len(), 2);
+        assert!(checks.iter().all(|c| c.passed), "{:?}", checks);
+        assert_eq!(checks[0].name, "storage.events_path, storage.state_path");
+
+        // no state yet is not a failure; an unreadable one is
+        assert_eq!(check_state(&storage, Utc::now()), None);
+        fs::write(&storage.state_path, "{ not json").unwrap();
+        let check = check_state(&storage, Utc::now()).unwrap();
+        assert!(!check.passed);
+        assert!(render(&[check]).contains("hint: "));
+
+        // a file where a directory is expected cannot be written into
+        let blocked = StorageConfig {
+            records_path: Some(storage.state_path.clone() + "/wide.jsonl"),
+            ..storage.clone()
+        };
+        assert!(!check_storage(&blocked)[1].passed);
+    }
+}
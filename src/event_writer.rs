@@ -0,0 +1,227 @@
+use crate::clock::Clock;
+use crate::persist::{create_parent_dir, rotate_events_if_needed, EventRotation, EventSink};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+//events that must reach disk right away rather than waiting for the batch to fill
+pub fn is_critical(event: &Value) -> bool {
+    let name = event.get("event").and_then(Value::as_str).unwrap_or("");
+    matches!(
+        name,
+        "service_start"
+            | "service_stop"
+            | "boot_detected"
+            | "boot_flapping"
+            | "temp_alert"
+            | "hashrate_stalled"
+    ) || name.ends_with("_error")
+}
+
+//dedicated task that appends events in batches: one write + fsync per max_events events or
+//max_delay, whichever comes first; critical events flush the batch immediately
+pub struct EventWriter {
+    path: String,
+    // taken on shutdown; appends after that fail instead of being lost silently
+    tx: Mutex<Option<UnboundedSender<(String, bool)>>>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl EventWriter {
+    pub fn spawn(
+        path: &str,
+        rotation: Option<EventRotation>,
+        clock: Arc<dyn Clock>,
+        max_events: usize,
+        max_delay: Duration,
+    ) -> Self {
+        let (tx, rx) = unbounded_channel();
+        let handle = tokio::spawn(run(
+            path.to_string(),
+            rotation,
+            clock,
+            rx,
+            max_events.max(1),
+            max_delay,
+        ));
+        Self {
+            path: path.to_string(),
+            tx: Mutex::new(Some(tx)),
+            handle: Mutex::new(Some(handle)),
+        }
+    }
+
+    //close the channel and wait until everything queued is on disk
+    pub async fn shutdown(&self) {
+        drop(self.tx.lock().unwrap_or_else(|e| e.into_inner()).take());
+        let handle = self.handle.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(handle) = handle {
+            let _ = handle.await;
+        }
+    }
+}
+
+impl EventSink for EventWriter {
+    fn append(&self, event: &Value) -> Result<()> {
+        let line = serde_json::to_string(event)?;
+        let tx = self.tx.lock().unwrap_or_else(|e| e.into_inner());
+        tx.as_ref()
+            .and_then(|tx| tx.send((line, is_critical(event))).ok())
+            .ok_or_else(|| anyhow!("event writer for {} has stopped", self.path))
+    }
+}
+
+async fn run(
+    path: String,
+    rotation: Option<EventRotation>,
+    clock: Arc<dyn Clock>,
+    mut rx: UnboundedReceiver<(String, bool)>,
+    max_events: usize,
+    max_delay: Duration,
+) {
+    let mut buf: Vec<String> = Vec::new();
+    let mut deadline: Option<Instant> = None;
+    loop {
+        let timer = async {
+            match deadline {
+                Some(d) => tokio::time::sleep_until(d).await,
+                None => std::future::pending().await,
+            }
+        };
+        let flush_now = tokio::select! {
+            msg = rx.recv() => match msg {
+                Some((line, critical)) => {
+                    buf.push(line);
+                    deadline.get_or_insert_with(|| Instant::now() + max_delay);
+                    critical || buf.len() >= max_events
+                }
+                // every sender is gone: shutting down
+                None => break,
+            },
+            _ = timer => true,
+        };
+        if flush_now {
+            flush(&path, rotation, clock.as_ref(), &mut buf);
+            deadline = None;
+        }
+    }
+    flush(&path, rotation, clock.as_ref(), &mut buf);
+}
+
+//on failure the batch is kept and retried with the next flush rather than dropped
+fn flush(path: &str, rotation: Option<EventRotation>, clock: &dyn Clock, buf: &mut Vec<String>) {
+    if buf.is_empty() {
+        return;
+    }
+    match write_batch(path, rotation, clock.now(), buf) {
+        Ok(()) => buf.clear(),
+        Err(err) => eprintln!(
+            "[bitaxe_monitor] WARN: failed to write {} events to {}: {err}",
+            buf.len(),
+            path
+        ),
+    }
+}
+
+//the file is rotated here rather than in append_event_jsonl, which hands events to this task
+fn write_batch(
+    path: &str,
+    rotation: Option<EventRotation>,
+    now: DateTime<Utc>,
+    lines: &[String],
+) -> Result<()> {
+    create_parent_dir(path)?;
+    if let Some(rotation) = rotation {
+        rotate_events_if_needed(path, rotation, now)?;
+    }
+    let mut out = String::new();
+    for line in lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+    let mut f = OpenOptions::new().create(true).append(true).open(path)?;
+    f.write_all(out.as_bytes())?;
+    f.sync_data()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+    use std::fs;
+    use std::path::Path;
+
+    fn line_count(path: &Path) -> usize {
+        fs::read_to_string(path)
+            .map(|s| s.lines().count())
+            .unwrap_or(0)
+    }
+
+    async fn wait_for_lines(path: &Path, n: usize, within: Duration) -> bool {
+        let until = Instant::now() + within;
+        while Instant::now() < until {
+            if line_count(path) >= n {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        line_count(path) >= n
+    }
+
+    #[tokio::test]
+    async fn test_batched_events_durable_after_interval_and_on_critical() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("events.jsonl");
+        let writer = EventWriter::spawn(
+            &path.to_string_lossy(),
+            None,
+            Arc::new(SystemClock),
+            100,
+            Duration::from_millis(200),
+        );
+
+        // ordinary events wait for the batch interval
+        writer
+            .append(&serde_json::json!({"event": "new_device_boot_best", "value": 1}))
+            .unwrap();
+        writer
+            .append(&serde_json::json!({"event": "new_device_boot_best", "value": 2}))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(line_count(&path), 0);
+        assert!(wait_for_lines(&path, 2, Duration::from_secs(2)).await);
+
+        // a critical event is written without waiting, together with anything queued before it
+        let slow = EventWriter::spawn(
+            &path.to_string_lossy(),
+            None,
+            Arc::new(SystemClock),
+            100,
+            Duration::from_secs(60),
+        );
+        slow.append(&serde_json::json!({"event": "new_tool_best_hashrate_ths", "value": 1.5}))
+            .unwrap();
+        slow.append(&serde_json::json!({"event": "poll_error", "error": "timeout"}))
+            .unwrap();
+        assert!(wait_for_lines(&path, 4, Duration::from_secs(2)).await);
+        let text = fs::read_to_string(&path).unwrap();
+        let last: Vec<&str> = text.lines().skip(2).collect();
+        assert!(last[0].contains("new_tool_best_hashrate_ths"));
+        assert!(last[1].contains("poll_error"));
+
+        // shutdown flushes the remainder
+        slow.append(&serde_json::json!({"event": "new_device_boot_best", "value": 3}))
+            .unwrap();
+        slow.shutdown().await;
+        assert_eq!(line_count(&path), 5);
+        writer.shutdown().await;
+    }
+}
@@ -0,0 +1,84 @@
+use crate::history::{event_lines, EventLine};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+//quote a field only when it needs it (comma, quote or line break), doubling embedded quotes
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn cell(v: Option<&Value>) -> String {
+    match v {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+//flatten events.jsonl into ts,event,value rows; events without a value keep an empty cell and
+//lines that are not json objects are skipped. Returns (rows written, lines skipped)
+pub fn events_to_csv(reader: impl BufRead, mut out: impl Write) -> Result<(usize, usize)> {
+    writeln!(out, "ts,event,value")?;
+    let (mut rows, mut skipped) = (0, 0);
+    for line in event_lines(reader) {
+        let EventLine::Event(event) = line? else {
+            skipped += 1;
+            continue;
+        };
+        writeln!(
+            out,
+            "{},{},{}",
+            csv_field(&cell(event.get("ts"))),
+            csv_field(&cell(event.get("event"))),
+            csv_field(&cell(event.get("value")))
+        )?;
+        rows += 1;
+    }
+    out.flush()?;
+    Ok((rows, skipped))
+}
+
+pub fn export_csv_file(events_path: &str, out_path: &Path) -> Result<(usize, usize)> {
+    let reader = BufReader::new(
+        File::open(events_path).with_context(|| format!("failed to open {}", events_path))?,
+    );
+    let writer = BufWriter::new(
+        File::create(out_path).with_context(|| format!("failed to create {:?}", out_path))?,
+    );
+    events_to_csv(reader, writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_flatten_to_csv_rows() {
+        let input = concat!(
+            "{\"ts\":\"2024-01-01T00:00:00Z\",\"event\":\"service_start\"}\n",
+            "{\"ts\":\"2024-01-01T00:00:10Z\",\"event\":\"new_tool_best_hashrate_ths\",\"value\":1.25}\n",
+            "not json\n",
+            "\n",
+            "{\"ts\":\"2024-01-01T00:00:20Z\",\"event\":\"new_best_difficulty\",\"value\":\"1,5T\"}\n",
+        );
+        let mut out = Vec::new();
+        let (rows, skipped) = events_to_csv(input.as_bytes(), &mut out).unwrap();
+        assert_eq!((rows, skipped), (3, 1));
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                "ts,event,value\n",
+                "2024-01-01T00:00:00Z,service_start,\n",
+                "2024-01-01T00:00:10Z,new_tool_best_hashrate_ths,1.25\n",
+                "2024-01-01T00:00:20Z,new_best_difficulty,\"1,5T\"\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use crate::config::{ExporterConfig, SeriesOverride};
+use crate::metrics::MonitorState;
+use anyhow::{Context, Result};
+use axum::{extract::State, http::header, response::IntoResponse, routing::get, Router};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+//latest state copied in after every poll; the server only ever reads it
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub state: MonitorState,
+    pub last_poll: Option<DateTime<Utc>>,
+}
+
+pub type SharedSnapshot = Arc<Mutex<Snapshot>>;
+
+pub fn publish(shared: &SharedSnapshot, state: &MonitorState, polled_at: DateTime<Utc>) {
+    //a poisoned lock only means a scrape panicked mid-read; the data is still usable
+    let mut snap = shared.lock().unwrap_or_else(|e| e.into_inner());
+    snap.state = state.clone();
+    snap.last_poll = Some(polled_at);
+}
+
+//after a failed poll: the state (e.g. an open circuit) changed but last_poll stays at the last success
+pub fn publish_state(shared: &SharedSnapshot, state: &MonitorState) {
+    shared.lock().unwrap_or_else(|e| e.into_inner()).state = state.clone();
+}
+
+//built-in series names in render order, the keys accepted by exporter.series_overrides
+pub const SERIES_NAMES: [&str; 12] = [
+    "bitaxe_best_hashrate_ths",
+    "bitaxe_best_efficiency_j_per_th",
+    "bitaxe_rolling_avg_hashrate_ths",
+    "bitaxe_tool_global_all_time_best",
+    "bitaxe_displayed_all_time",
+    "bitaxe_displayed_boot_best",
+    "bitaxe_uptime_seconds",
+    "bitaxe_best_difficulty",
+    "bitaxe_last_poll_timestamp_seconds",
+    "bitaxe_circuit_open",
+    "bitaxe_monitor_process_rss_bytes",
+    "bitaxe_monitor_process_cpu_percent",
+];
+
+//prometheus text exposition format; gauges without a value yet are left out rather than reported as 0;
+//overrides rename a series, replace its help text and scale its value
+pub fn render_metrics(snap: &Snapshot, overrides: &HashMap<String, SeriesOverride>) -> String {
+    let s = &snap.state;
+    let gauges: [(&str, &str, Option<f64>); 12] = [
+        (
+            "bitaxe_best_hashrate_ths",
+            "Highest hashrate observed by the monitor (TH/s)",
+            s.tool_best_hashrate_ths,
+        ),
+        (
+            "bitaxe_best_efficiency_j_per_th",
+            "Lowest efficiency observed by the monitor (J/TH)",
+            s.tool_best_efficiency_j_per_th,
+        ),
+        (
+            "bitaxe_rolling_avg_hashrate_ths",
+            "Mean hashrate over the last rolling_window polls (TH/s)",
+            s.rolling_avg_hashrate_ths(),
+        ),
+        (
+            "bitaxe_tool_global_all_time_best",
+            "Highest device best the monitor has ever observed",
+            Some(s.tool_global_all_time_best),
+        ),
+        (
+            "bitaxe_displayed_all_time",
+            "Device-reported all-time best from the last poll",
+            s.last_displayed_all_time,
+        ),
+        (
+            "bitaxe_displayed_boot_best",
+            "Device-reported best since boot from the last poll",
+            s.last_displayed_boot_best,
+        ),
+        (
+            "bitaxe_uptime_seconds",
+            "Device uptime from the last poll",
+            s.last_uptime_secs.map(|v| v as f64),
+        ),
+        (
+            "bitaxe_best_difficulty",
+            "Highest best difficulty observed by the monitor",
+            s.tool_best_difficulty,
+        ),
+        (
+            "bitaxe_last_poll_timestamp_seconds",
+            "Unix time of the last completed poll",
+            snap.last_poll.map(|t| t.timestamp_millis() as f64 / 1000.0),
+        ),
+        (
+            "bitaxe_circuit_open",
+            "1 while the circuit breaker is open after consecutive failed polls, else 0",
+            Some(if s.circuit.is_open() { 1.0 } else { 0.0 }),
+        ),
+        (
+            "bitaxe_monitor_process_rss_bytes",
+            "Resident memory of the monitor process, with self_metrics (bytes)",
+            s.process_rss_bytes.map(|v| v as f64),
+        ),
+        (
+            "bitaxe_monitor_process_cpu_percent",
+            "CPU use of the monitor process since the previous poll, with self_metrics (% of one core)",
+            s.process_cpu_percent,
+        ),
+    ];
+
+    let mut out = String::new();
+    for (name, help, value) in gauges {
+        let Some(mut v) = value.filter(|v| v.is_finite()) else {
+            continue;
+        };
+        let (mut name, mut help) = (name, help);
+        if let Some(o) = overrides.get(name) {
+            name = o.name.as_deref().unwrap_or(name);
+            help = o.help.as_deref().unwrap_or(help);
+            v *= o.scale.unwrap_or(1.0);
+        }
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        let _ = writeln!(out, "{} {}", name, v);
+    }
+    for (metric, hist) in &s.histograms {
+        let name = format!("bitaxe_{}", metric);
+        let _ = writeln!(
+            out,
+            "# HELP {} Distribution of polled {} values",
+            name, metric
+        );
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        let cumulative = hist.cumulative();
+        for (edge, count) in hist.edges.iter().zip(&cumulative) {
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, edge, count);
+        }
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, hist.count());
+        let _ = writeln!(out, "{}_sum {}", name, hist.sum);
+        let _ = writeln!(out, "{}_count {}", name, hist.count());
+    }
+    out
+}
+
+#[derive(Clone)]
+struct ExporterState {
+    shared: SharedSnapshot,
+    overrides: Arc<HashMap<String, SeriesOverride>>,
+}
+
+async fn metrics_handler(State(st): State<ExporterState>) -> impl IntoResponse {
+    let body = {
+        let snap = st.shared.lock().unwrap_or_else(|e| e.into_inner());
+        render_metrics(&snap, &st.overrides)
+    };
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+//bind before returning so a bad or busy listen_addr fails startup instead of being skipped silently
+pub async fn spawn(cfg: &ExporterConfig, shared: SharedSnapshot) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(&cfg.listen_addr)
+        .await
+        .with_context(|| format!("failed to bind exporter on {}", cfg.listen_addr))?;
+    let app = Router::new()
+        .route("/metrics", get(metrics_handler))
+        .with_state(ExporterState {
+            shared,
+            overrides: Arc::new(cfg.series_overrides.clone().unwrap_or_default()),
+        });
+    tokio::spawn(async move {
+        if let Err(err) = axum::serve(listener, app).await {
+            eprintln!("[bitaxe_monitor] WARN: exporter stopped: {err}");
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_metrics_gauges() {
+        let mut state = MonitorState::new();
+        state.tool_global_all_time_best = 12.5;
+        state.tool_best_hashrate_ths = Some(1.6);
+        let snap = Snapshot {
+            state,
+            last_poll: DateTime::from_timestamp(1_700_000_000, 0),
+        };
+        let text = render_metrics(&snap, &HashMap::new());
+        assert!(
+            text.contains("# TYPE bitaxe_best_hashrate_ths gauge\nbitaxe_best_hashrate_ths 1.6\n")
+        );
+        assert!(text.contains("bitaxe_tool_global_all_time_best 12.5\n"));
+        assert!(text.contains("bitaxe_last_poll_timestamp_seconds 1700000000\n"));
+        assert!(text.contains("bitaxe_circuit_open 0\n"));
+        assert!(!text.contains("histogram"));
+
+        let mut snap = snap;
+        for v in [1.1, 1.3, 1.6] {
+            snap.state.observe_histogram("hashrate_ths", &[1.2, 1.5], v);
+        }
+        let text = render_metrics(&snap, &HashMap::new());
+        assert!(text.contains("# TYPE bitaxe_hashrate_ths histogram\n"));
+        assert!(text.contains("bitaxe_hashrate_ths_bucket{le=\"1.5\"} 2\n"));
+        assert!(text.contains("bitaxe_hashrate_ths_bucket{le=\"+Inf\"} 3\n"));
+        assert!(text.contains("bitaxe_hashrate_ths_count 3\n"));
+        // no efficiency seen yet, so no series rather than a misleading 0
+        assert!(!text.contains("bitaxe_best_efficiency_j_per_th"));
+        assert!(!text.contains("bitaxe_monitor_process"));
+
+        // with self_metrics on
+        snap.state.process_rss_bytes = Some(25_000_000);
+        snap.state.process_cpu_percent = Some(0.5);
+        let text = render_metrics(&snap, &HashMap::new());
+        assert!(text.contains(
+            "bitaxe_monitor_process_rss_bytes 25000000
+"
+        ));
+        assert!(text.contains(
+            "bitaxe_monitor_process_cpu_percent 0.5
+"
+        ));
+    }
+
+    #[test]
+    fn test_render_metrics_applies_series_overrides() {
+        let mut state = MonitorState::new();
+        state.tool_best_efficiency_j_per_th = Some(16.0);
+        state.tool_best_hashrate_ths = Some(1.5);
+        let snap = Snapshot {
+            state,
+            last_poll: None,
+        };
+        let overrides = HashMap::from([(
+            "bitaxe_best_efficiency_j_per_th".to_string(),
+            SeriesOverride {
+                name: Some("bitaxe_best_efficiency_w_per_gh".to_string()),
+                help: Some("Lowest efficiency observed (W/GH)".to_string()),
+                scale: Some(0.001),
+            },
+        )]);
+        let text = render_metrics(&snap, &overrides);
+        assert!(text.contains(
+            "# HELP bitaxe_best_efficiency_w_per_gh Lowest efficiency observed (W/GH)\n"
+        ));
+        assert!(text.contains(
+            "# TYPE bitaxe_best_efficiency_w_per_gh gauge\nbitaxe_best_efficiency_w_per_gh 0.016\n"
+        ));
+        assert!(!text.contains("bitaxe_best_efficiency_j_per_th"));
+        // series without an override keep their built-in name and value
+        assert!(text.contains("bitaxe_best_hashrate_ths 1.5\n"));
+    }
+
+    #[tokio::test]
+    async fn test_exporter_serves_metrics_and_fails_on_busy_port() {
+        let shared = SharedSnapshot::default();
+        let mut state = MonitorState::new();
+        state.tool_global_all_time_best = 3.0;
+        publish(&shared, &state, Utc::now());
+
+        // reserve a free port, release it, then let the exporter take it
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let cfg = ExporterConfig {
+            listen_addr: addr.clone(),
+            series_overrides: None,
+        };
+        spawn(&cfg, shared.clone()).await.unwrap();
+
+        let body = reqwest::get(format!("http://{}/metrics", addr))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(body.contains("bitaxe_tool_global_all_time_best 3\n"));
+
+        // the port is now taken, so a second exporter must fail loudly
+        let err = spawn(&cfg, shared).await.unwrap_err();
+        assert!(format!("{}", err).contains("failed to bind exporter"));
+    }
+}
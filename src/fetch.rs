@@ -0,0 +1,683 @@
+use crate::config::{HttpConfig, HttpMethod, RetryConfig};
+use anyhow::{bail, Context, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::{Client, ClientBuilder, RequestBuilder};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+//endpoint host pinned to one resolved address (http.pin_resolved_ip)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinnedAddr {
+    pub host: String,
+    pub addr: SocketAddr,
+}
+
+//resolve the endpoint's host once; endpoints that already use an ip address need no pin
+pub async fn resolve_pin(endpoint_url: &str) -> Result<Option<PinnedAddr>> {
+    let url = reqwest::Url::parse(endpoint_url).context("invalid endpoint_url")?;
+    let Some(host) = url.domain().map(str::to_string) else {
+        return Ok(None);
+    };
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addr = tokio::net::lookup_host((host.as_str(), port))
+        .await
+        .with_context(|| format!("failed to resolve {}", host))?
+        .next()
+        .with_context(|| format!("no addresses found for {}", host))?;
+    Ok(Some(PinnedAddr { host, addr }))
+}
+
+//scheme plus a hint of the host for log lines: "http://192.168.x.x", "http://b***.local"; the port,
+//path, query and any credentials are dropped since they may carry secrets
+pub fn mask_endpoint(url: &str) -> String {
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return "[HOST-HIDDEN]".to_string();
+    };
+    let initial = |s: &str| s.chars().next().map(String::from).unwrap_or_default();
+    let hint = match parsed.host_str() {
+        Some(host) if host.starts_with('[') => "[ipv6]".to_string(),
+        Some(host) => match host.parse::<std::net::Ipv4Addr>() {
+            Ok(ip) => {
+                let [a, b, _, _] = ip.octets();
+                format!("{}.{}.x.x", a, b)
+            }
+            Err(_) => match host.split_once('.') {
+                Some((first, rest)) => format!("{}***.{}", initial(first), rest),
+                None => format!("{}***", initial(host)),
+            },
+        },
+        None => "[HOST-HIDDEN]".to_string(),
+    };
+    format!("{}://{}", parsed.scheme(), hint)
+}
+
+//http client with timeouts for the device endpoint; a pinned address bypasses dns for the endpoint host
+//device headers are not set here but on each device request, so the client never carries credentials
+pub fn build_client(http: &HttpConfig, pinned: Option<&PinnedAddr>) -> Result<Client> {
+    let mut client_builder = Client::builder()
+        .timeout(Duration::from_secs(http.timeout_secs.unwrap_or(10)))
+        .user_agent("bitaxe-monitor/0.1");
+
+    if let Some(pin) = pinned {
+        client_builder = client_builder.resolve(&pin.host, pin.addr);
+    }
+
+    PoolTuning::from_config(http)
+        .apply(client_builder)
+        .build()
+        .context("failed to build http client")
+}
+
+//plain client for webhooks, remote write and influx: same timeout, none of the device headers or pin
+pub fn build_sink_client(http: &HttpConfig) -> Result<Client> {
+    Client::builder()
+        .timeout(Duration::from_secs(http.timeout_secs.unwrap_or(10)))
+        .user_agent("bitaxe-monitor/0.1")
+        .build()
+        .context("failed to build http client")
+}
+
+//headers sent with every device request: http.headers, accept and the bearer token
+//the token is read from the environment here rather than stored in the config
+pub fn device_headers(http: &HttpConfig) -> Result<HeaderMap> {
+    let token = match &http.auth_token_env {
+        Some(var) => Some(
+            std::env::var(var)
+                .with_context(|| format!("http.auth_token_env names {}, which is not set", var))?,
+        ),
+        None => None,
+    };
+    device_headers_with_token(http, token.as_deref())
+}
+
+fn device_headers_with_token(http: &HttpConfig, token: Option<&str>) -> Result<HeaderMap> {
+    let mut hdrs = http.headers.clone().unwrap_or_default();
+    if let Some(token) = token {
+        hdrs.insert("Authorization".to_string(), format!("Bearer {}", token));
+    }
+    if let Some(accept) = &http.accept {
+        hdrs.insert("Accept".to_string(), accept.clone());
+    }
+
+    let mut map = HeaderMap::new();
+    for (k, v) in hdrs.iter() {
+        let name = HeaderName::from_bytes(k.as_bytes())
+            .with_context(|| format!("invalid header name: {}", k))?;
+        let value = HeaderValue::from_str(v)
+            .with_context(|| format!("invalid header value for {}: {}", k, v))?;
+        map.append(name, value);
+    }
+    Ok(map)
+}
+
+//connection reuse settings handed to the client builder; unset values keep reqwest's defaults
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PoolTuning {
+    pub max_idle_per_host: Option<usize>,
+    pub idle_timeout: Option<Duration>,
+    pub tcp_keepalive: Option<Duration>,
+}
+
+impl PoolTuning {
+    pub fn from_config(http: &HttpConfig) -> Self {
+        Self {
+            max_idle_per_host: http.pool_max_idle_per_host,
+            idle_timeout: http.pool_idle_timeout_secs.map(Duration::from_secs),
+            tcp_keepalive: http.tcp_keepalive_secs.map(Duration::from_secs),
+        }
+    }
+
+    fn apply(self, mut builder: ClientBuilder) -> ClientBuilder {
+        if let Some(n) = self.max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(n);
+        }
+        if let Some(t) = self.idle_timeout {
+            builder = builder.pool_idle_timeout(t);
+        }
+        if let Some(t) = self.tcp_keepalive {
+            builder = builder.tcp_keepalive(t);
+        }
+        builder
+    }
+}
+
+//how many times to retry and how long to back off between attempts
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: usize,
+    pub base_delay: Duration,
+    // cap for a single backoff sleep so exponential growth stays bounded
+    pub max_delay: Option<Duration>,
+    // spread sleeps randomly so devices that failed together do not retry in lockstep
+    pub jitter: bool,
+}
+
+impl RetryPolicy {
+    //values from http.retry override the caller's defaults (polling and preflight use different ones)
+    pub fn from_config(
+        cfg: Option<&RetryConfig>,
+        default_retries: usize,
+        default_base_delay: Duration,
+    ) -> Self {
+        Self {
+            max_retries: cfg.and_then(|c| c.max_retries).unwrap_or(default_retries),
+            base_delay: cfg
+                .and_then(|c| c.base_delay_ms)
+                .map(Duration::from_millis)
+                .unwrap_or(default_base_delay),
+            max_delay: cfg.and_then(|c| c.max_delay_ms).map(Duration::from_millis),
+            jitter: cfg.and_then(|c| c.jitter).unwrap_or(false),
+        }
+    }
+
+    //base * 2^attempt (times a random 0.5..=1.5 with jitter), clamped to max_delay
+    pub fn delay_for(&self, attempt: usize) -> Duration {
+        let factor = 1u64.checked_shl(attempt as u32).unwrap_or(u64::MAX);
+        let mut delay_ms = (self.base_delay.as_millis() as u64).saturating_mul(factor);
+        if self.jitter {
+            delay_ms = (delay_ms as f64 * rand::random_range(0.5..=1.5)) as u64;
+        }
+        let delay = Duration::from_millis(delay_ms);
+        match self.max_delay {
+            Some(max) => delay.min(max),
+            None => delay,
+        }
+    }
+}
+
+//make a few attempts with exponential backoff to get a response so short network glitches do not surface as errors
+//when http.poll_budget_secs is set, total time across attempts and backoff sleeps stays within it
+pub async fn fetch_text_with_retries(
+    client: &Client,
+    http: &HttpConfig,
+    policy: RetryPolicy,
+) -> Result<String> {
+    let headers = device_headers(http)?;
+    fetch_with_headers(client, http, &headers, policy).await
+}
+
+async fn fetch_with_headers(
+    client: &Client,
+    http: &HttpConfig,
+    headers: &HeaderMap,
+    policy: RetryPolicy,
+) -> Result<String> {
+    let budget = http.poll_budget_secs.map(Duration::from_secs);
+    let per_request_timeout = Duration::from_secs(http.timeout_secs.unwrap_or(10));
+    let started = Instant::now();
+    let mut attempt: usize = 0;
+    loop {
+        //rebuild request each attempt because RequestBuilder is single-use
+        let mut req = match http.method.unwrap_or_default() {
+            HttpMethod::Get => client.get(&http.endpoint_url),
+            HttpMethod::Post => client.post(&http.endpoint_url),
+        }
+        .headers(headers.clone());
+        if let Some(body) = &http.body {
+            req = req
+                .header(CONTENT_TYPE, "application/json")
+                .body(body.clone());
+        }
+
+        //never let a single attempt run past what is left of the budget
+        if let Some(budget) = budget {
+            let remaining = budget.saturating_sub(started.elapsed());
+            req = req.timeout(remaining.min(per_request_timeout));
+        }
+
+        let err = match send_once(req, http.max_response_bytes).await {
+            Ok(body) => return Ok(body),
+            Err(err) => err,
+        };
+        if attempt >= policy.max_retries {
+            return Err(err);
+        }
+
+        let delay = policy.delay_for(attempt);
+
+        //stop early when the next backoff would overrun the budget so the poll cadence stays predictable
+        if let Some(budget) = budget {
+            if started.elapsed() + delay >= budget {
+                return Err(err.context(format!(
+                    "poll budget of {}s exhausted after {} attempt(s)",
+                    budget.as_secs(),
+                    attempt + 1
+                )));
+            }
+        }
+
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+//one request/response round trip; non-2xx statuses count as failures so they are retried too
+//with max_bytes the body is read chunk by chunk and abandoned as soon as it passes the limit
+async fn send_once(req: RequestBuilder, max_bytes: Option<usize>) -> Result<String> {
+    let mut resp = req.send().await?.error_for_status()?;
+    let Some(max) = max_bytes else {
+        return Ok(resp.text().await?);
+    };
+    if let Some(len) = resp.content_length().filter(|len| *len > max as u64) {
+        bail!(
+            "response too large: content-length {} exceeds http.max_response_bytes ({})",
+            len,
+            max
+        );
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        if body.len() + chunk.len() > max {
+            bail!(
+                "response too large: body exceeds http.max_response_bytes ({})",
+                max
+            );
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    fn http_config(url: String, timeout_secs: u64, budget_secs: Option<u64>) -> HttpConfig {
+        HttpConfig {
+            endpoint_url: url,
+            headers: None,
+            auth_token_env: None,
+            mask_endpoint: None,
+            timeout_secs: Some(timeout_secs),
+            poll_budget_secs: budget_secs,
+            retry: None,
+            pin_resolved_ip: None,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout_secs: None,
+            tcp_keepalive_secs: None,
+            method: None,
+            body: None,
+            accept: None,
+            max_response_bytes: None,
+        }
+    }
+
+    fn policy(max_retries: usize, base_ms: u64) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(base_ms),
+            max_delay: None,
+            jitter: false,
+        }
+    }
+
+    //accepts connections but never answers so every attempt runs into its timeout
+    async fn spawn_silent_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((sock, _)) = listener.accept().await {
+                held.push(sock);
+            }
+        });
+        format!("http://{}/api/system/info", addr)
+    }
+
+    #[tokio::test]
+    async fn test_budget_bounds_total_poll_time() {
+        let url = spawn_silent_server().await;
+        let http = http_config(url, 1, Some(2));
+        let client = Client::new();
+
+        // without a budget this would take ~6 x 1s timeouts plus backoff
+        let started = Instant::now();
+        let err = fetch_text_with_retries(&client, &http, policy(5, 50))
+            .await
+            .unwrap_err();
+        let elapsed = started.elapsed();
+
+        assert!(elapsed < Duration::from_millis(2800), "took {:?}", elapsed);
+        assert!(format!("{:#}", err).contains("poll budget"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_returns_body() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let (mut sock, _) = listener.accept().await.expect("accept");
+            let mut buf = [0u8; 1024];
+            let _ = sock.read(&mut buf).await;
+            let body = r#"{"ok":true}"#;
+            let resp = format!(
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\ncontent-type: application/json\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = sock.write_all(resp.as_bytes()).await;
+        });
+
+        let http = http_config(format!("http://{}/", addr), 2, Some(5));
+        let body = fetch_text_with_retries(&Client::new(), &http, policy(0, 10))
+            .await
+            .expect("fetch");
+        assert_eq!(body, r#"{"ok":true}"#);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_posts_json_body() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let (mut sock, _) = listener.accept().await.expect("accept");
+            // headers and body may arrive in separate reads
+            let mut request = String::new();
+            let mut buf = [0u8; 2048];
+            while !request.ends_with('}') {
+                match sock.read(&mut buf).await {
+                    Ok(n) if n > 0 => request.push_str(&String::from_utf8_lossy(&buf[..n])),
+                    _ => break,
+                }
+            }
+            let _ = tx.send(request);
+            let body = r#"{"ok":true}"#;
+            let resp = format!(
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = sock.write_all(resp.as_bytes()).await;
+        });
+
+        let http = HttpConfig {
+            method: Some(HttpMethod::Post),
+            body: Some(r#"{"user":"admin"}"#.into()),
+            ..http_config(format!("http://{}/stats", addr), 2, Some(5))
+        };
+        let body = fetch_text_with_retries(&Client::new(), &http, policy(0, 10))
+            .await
+            .expect("fetch");
+        assert_eq!(body, r#"{"ok":true}"#);
+        let request = rx.await.expect("request");
+        assert!(request.starts_with("POST /stats"), "{}", request);
+        assert!(request.contains("content-type: application/json"));
+        assert!(request.ends_with(r#"{"user":"admin"}"#));
+    }
+
+    #[test]
+    fn test_retry_policy_from_config_and_cap() {
+        // unset values keep the caller's defaults
+        let p = RetryPolicy::from_config(None, 3, Duration::from_millis(500));
+        assert_eq!(
+            (p.max_retries, p.base_delay),
+            (3, Duration::from_millis(500))
+        );
+        assert_eq!(p.delay_for(2), Duration::from_millis(2000));
+
+        let cfg = RetryConfig {
+            max_retries: Some(6),
+            base_delay_ms: Some(1000),
+            max_delay_ms: Some(30_000),
+            jitter: None,
+        };
+        let p = RetryPolicy::from_config(Some(&cfg), 3, Duration::from_millis(500));
+        assert_eq!(p.max_retries, 6);
+        assert_eq!(p.delay_for(0), Duration::from_secs(1));
+        assert_eq!(p.delay_for(4), Duration::from_secs(16));
+        // 1s * 2^5 = 32s is capped, and very large attempts do not overflow
+        assert_eq!(p.delay_for(5), Duration::from_secs(30));
+        assert_eq!(p.delay_for(200), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_jittered_delay_within_bounds() {
+        let p = RetryPolicy {
+            jitter: true,
+            ..policy(3, 500)
+        };
+        // 500ms * 2^2 = 2s, scaled into [1s, 3s]
+        let delays: Vec<Duration> = (0..500).map(|_| p.delay_for(2)).collect();
+        assert!(delays
+            .iter()
+            .all(|d| *d >= Duration::from_millis(1000) && *d <= Duration::from_millis(3000)));
+        assert!(delays.iter().any(|d| *d != delays[0]));
+
+        // the cap still applies after jitter
+        let capped = RetryPolicy {
+            max_delay: Some(Duration::from_millis(1200)),
+            ..p
+        };
+        assert!((0..100).all(|_| capped.delay_for(2) <= Duration::from_millis(1200)));
+    }
+
+    #[tokio::test]
+    async fn test_pinned_address_used_for_endpoint_host() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let (mut sock, _) = listener.accept().await.expect("accept");
+            let mut buf = [0u8; 1024];
+            let _ = sock.read(&mut buf).await;
+            let resp = "HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok";
+            let _ = sock.write_all(resp.as_bytes()).await;
+        });
+
+        // ".invalid" never resolves through dns, so success proves the pinned address was used
+        let http = http_config(format!("http://bitaxe.invalid:{}/", addr.port()), 2, None);
+        let pin = PinnedAddr {
+            host: "bitaxe.invalid".to_string(),
+            addr,
+        };
+        let client = build_client(&http, Some(&pin)).unwrap();
+        let body = fetch_text_with_retries(&client, &http, policy(0, 10))
+            .await
+            .expect("fetch via pinned address");
+        assert_eq!(body, "ok");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_pin_skips_ip_endpoints() {
+        assert_eq!(resolve_pin("http://192.168.1.50/api").await.unwrap(), None);
+        let pin = resolve_pin("http://localhost:8080/api")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pin.host, "localhost");
+        assert_eq!(pin.addr.port(), 8080);
+    }
+
+    //answers every request on a connection (keep-alive) and counts the connections it accepted
+    async fn spawn_counting_server() -> (String, Arc<AtomicUsize>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            while let Ok((mut sock, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    while matches!(sock.read(&mut buf).await, Ok(n) if n > 0) {
+                        let resp = "HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok";
+                        if sock.write_all(resp.as_bytes()).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        (format!("http://{}/", addr), accepted)
+    }
+
+    #[tokio::test]
+    async fn test_pool_settings_applied_to_client() {
+        let mut http = http_config(String::new(), 2, None);
+        http.pool_max_idle_per_host = Some(0);
+        http.pool_idle_timeout_secs = Some(30);
+        http.tcp_keepalive_secs = Some(60);
+        assert_eq!(
+            PoolTuning::from_config(&http),
+            PoolTuning {
+                max_idle_per_host: Some(0),
+                idle_timeout: Some(Duration::from_secs(30)),
+                tcp_keepalive: Some(Duration::from_secs(60)),
+            }
+        );
+
+        // with no idle connections kept, every poll opens a new connection
+        let (url, accepted) = spawn_counting_server().await;
+        http.endpoint_url = url;
+        let client = build_client(&http, None).unwrap();
+        for _ in 0..2 {
+            fetch_text_with_retries(&client, &http, policy(0, 10))
+                .await
+                .expect("fetch");
+        }
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+
+        // the default pool reuses one connection
+        let (url, accepted) = spawn_counting_server().await;
+        let http = http_config(url, 2, None);
+        let client = build_client(&http, None).unwrap();
+        for _ in 0..2 {
+            fetch_text_with_retries(&client, &http, policy(0, 10))
+                .await
+                .expect("fetch");
+        }
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_auth_token_env_sent_as_bearer() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let (mut sock, _) = listener.accept().await.expect("accept");
+            let mut request = String::new();
+            let mut buf = [0u8; 2048];
+            while !request.ends_with("\r\n\r\n") {
+                match sock.read(&mut buf).await {
+                    Ok(n) if n > 0 => request.push_str(&String::from_utf8_lossy(&buf[..n])),
+                    _ => break,
+                }
+            }
+            let _ = tx.send(request);
+            let _ = sock
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\n{}")
+                .await;
+        });
+
+        let mut http = http_config(format!("http://{}/", addr), 2, None);
+        http.auth_token_env = Some("BITAXE_MONITOR_TEST_TOKEN_MISSING".into());
+        let err = device_headers(&http).unwrap_err();
+        assert!(format!("{:#}", err).contains("BITAXE_MONITOR_TEST_TOKEN_MISSING"));
+
+        let headers = device_headers_with_token(&http, Some("s3cret")).unwrap();
+        let client = build_client(&http, None).unwrap();
+        fetch_with_headers(&client, &http, &headers, policy(0, 10))
+            .await
+            .expect("fetch");
+        let request = rx.await.expect("request").to_lowercase();
+        assert!(
+            request.contains("authorization: bearer s3cret"),
+            "{}",
+            request
+        );
+    }
+
+    //answers every connection with the given raw response and records the request headers
+    async fn spawn_fixed_server(
+        response: &'static str,
+    ) -> (String, tokio::sync::mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let (tx, rx) = tokio::sync::mpsc::channel(8);
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            while let Ok((mut sock, _)) = listener.accept().await {
+                let mut request = String::new();
+                let mut buf = [0u8; 2048];
+                while !request.ends_with("\r\n\r\n") {
+                    match sock.read(&mut buf).await {
+                        Ok(n) if n > 0 => request.push_str(&String::from_utf8_lossy(&buf[..n])),
+                        _ => break,
+                    }
+                }
+                let _ = tx.send(request).await;
+                let _ = sock.write_all(response.as_bytes()).await;
+            }
+        });
+        (format!("http://{}/", addr), rx)
+    }
+
+    #[tokio::test]
+    async fn test_response_over_limit_is_rejected() {
+        // no content-length, so the limit has to be enforced while streaming
+        let (url, mut rx) = spawn_fixed_server(
+            "HTTP/1.1 200 OK\r\nconnection: close\r\n\r\n{\"blob\": \"0123456789012345678901234567890123456789\"}",
+        )
+        .await;
+        let mut http = http_config(url, 2, None);
+        http.accept = Some("application/json".into());
+        http.max_response_bytes = Some(16);
+        let client = build_client(&http, None).unwrap();
+        let err = fetch_text_with_retries(&client, &http, policy(0, 10))
+            .await
+            .unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("response too large"),
+            "{:#}",
+            err
+        );
+        let request = rx.recv().await.expect("request").to_lowercase();
+        assert!(request.contains("accept: application/json"), "{}", request);
+
+        // an announced length over the limit fails before the body is read
+        let (url, _rx) = spawn_fixed_server("HTTP/1.1 200 OK\r\ncontent-length: 40\r\n\r\n").await;
+        let mut http = http_config(url, 2, None);
+        http.max_response_bytes = Some(16);
+        let err = fetch_text_with_retries(&Client::new(), &http, policy(0, 10))
+            .await
+            .unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("content-length 40"),
+            "{:#}",
+            err
+        );
+
+        // within the limit the body comes back unchanged
+        let (url, _rx) = spawn_fixed_server("HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\n{}").await;
+        let mut http = http_config(url, 2, None);
+        http.max_response_bytes = Some(16);
+        let body = fetch_text_with_retries(&Client::new(), &http, policy(0, 10))
+            .await
+            .expect("fetch");
+        assert_eq!(body, "{}");
+    }
+
+    #[test]
+    fn test_mask_endpoint_keeps_scheme_and_host_hint() {
+        assert_eq!(
+            mask_endpoint("http://192.168.1.42/api/system/info"),
+            "http://192.168.x.x"
+        );
+        assert_eq!(
+            mask_endpoint("https://user:pw@bitaxe.local:8443/api?token=abc"),
+            "https://b***.local"
+        );
+        assert_eq!(mask_endpoint("http://miner/api"), "http://m***");
+        assert_eq!(mask_endpoint("http://[fe80::1]:80/"), "http://[ipv6]");
+        assert_eq!(mask_endpoint("not a url"), "[HOST-HIDDEN]");
+    }
+}
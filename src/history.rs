@@ -0,0 +1,220 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{Map, Value};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines};
+
+//one non-blank line of events.jsonl (or the wide records file)
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventLine {
+    Event(Map<String, Value>),
+    // not a json object; callers count these rather than failing on a single bad line
+    Unreadable,
+}
+
+//events in file order; blank lines are passed over
+pub struct EventLines<R> {
+    lines: Lines<R>,
+}
+
+pub fn event_lines<R: BufRead>(reader: R) -> EventLines<R> {
+    EventLines {
+        lines: reader.lines(),
+    }
+}
+
+impl<R: BufRead> Iterator for EventLines<R> {
+    type Item = std::io::Result<EventLine>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err)),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(Ok(match serde_json::from_str::<Value>(&line) {
+                Ok(Value::Object(event)) => EventLine::Event(event),
+                _ => EventLine::Unreadable,
+            }));
+        }
+    }
+}
+
+//"ts" as written with any storage.timestamp_format: rfc3339 strings or epoch numbers, where values
+//past 1e11 cannot be seconds (that is the year 5138) and are read as milliseconds
+pub fn event_time(ts: &Value) -> Option<DateTime<Utc>> {
+    if let Some(s) = ts.as_str() {
+        return DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|t| t.with_timezone(&Utc));
+    }
+    let n = ts.as_u64()?;
+    let ms = if n >= 100_000_000_000 { n } else { n * 1000 };
+    DateTime::from_timestamp_millis(i64::try_from(ms).ok()?)
+}
+
+//"30m", "6h", "2d" (also "45s"); a bare number is rejected so the unit is never guessed
+pub fn parse_window(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let Some(unit) = text.chars().last() else {
+        bail!("empty window; use e.g. 30m, 6h or 2d");
+    };
+    let amount: i64 = text[..text.len() - unit.len_utf8()]
+        .parse()
+        .with_context(|| format!("invalid window {:?}; use e.g. 30m, 6h or 2d", text))?;
+    if amount <= 0 {
+        bail!("window {:?} must be longer than zero", text);
+    }
+    let secs = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86_400,
+        _ => bail!("invalid window unit in {:?}; use s, m, h or d", text),
+    };
+    amount
+        .checked_mul(secs)
+        .and_then(Duration::try_seconds)
+        .with_context(|| format!("window {:?} is too long", text))
+}
+
+//highest readings seen in events (and wide records) inside a time window
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WindowBests {
+    pub best_hashrate_ths: Option<f64>,
+    pub best_difficulty: Option<f64>,
+    pub best_difficulty_display: Option<String>,
+    pub peak_temp_c: Option<f64>,
+    // lines inside the window, and lines that were not json objects
+    pub events: usize,
+    pub skipped: usize,
+}
+
+impl WindowBests {
+    //per-poll fields (heartbeats, wide records) and the value of record events both count, so a
+    //window that set no all-time record still reports what it reached
+    fn observe(&mut self, event: &Map<String, Value>) {
+        let name = event.get("event").and_then(Value::as_str).unwrap_or("");
+        let value = event.get("value").and_then(Value::as_f64);
+        let field = |key: &str| event.get(key).and_then(Value::as_f64);
+
+        for h in [
+            field("hashrate_ths"),
+            value.filter(|_| name == "new_tool_best_hashrate_ths"),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            raise(&mut self.best_hashrate_ths, h);
+        }
+
+        let difficulties = [
+            (field("best_difficulty"), "best_difficulty_display"),
+            (value.filter(|_| name == "new_best_difficulty"), "display"),
+        ];
+        for (d, display_key) in difficulties {
+            let Some(d) = d.filter(|v| v.is_finite()) else {
+                continue;
+            };
+            if self.best_difficulty.is_none_or(|prev| d > prev) {
+                self.best_difficulty = Some(d);
+                self.best_difficulty_display = event
+                    .get(display_key)
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+            }
+        }
+
+        for t in [
+            field("temp_c"),
+            value.filter(|_| matches!(name, "new_tool_max_temp_c" | "temp_alert")),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            raise(&mut self.peak_temp_c, t);
+        }
+    }
+
+    //fold in every line of reader stamped at or after since; lines without a readable ts are left out
+    pub fn scan(&mut self, reader: impl BufRead, since: DateTime<Utc>) -> Result<()> {
+        for line in event_lines(reader) {
+            let event = match line? {
+                EventLine::Event(event) => event,
+                EventLine::Unreadable => {
+                    self.skipped += 1;
+                    continue;
+                }
+            };
+            if event
+                .get("ts")
+                .and_then(event_time)
+                .is_some_and(|ts| ts >= since)
+            {
+                self.events += 1;
+                self.observe(&event);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn scan_file(&mut self, path: &str, since: DateTime<Utc>) -> Result<()> {
+        let file = File::open(path).with_context(|| format!("failed to open {}", path))?;
+        self.scan(BufReader::new(file), since)
+    }
+}
+
+fn raise(slot: &mut Option<f64>, v: f64) {
+    if v.is_finite() && slot.is_none_or(|prev| v > prev) {
+        *slot = Some(v);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_window() {
+        assert_eq!(parse_window("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_window("6h").unwrap(), Duration::hours(6));
+        assert_eq!(parse_window(" 2d ").unwrap(), Duration::days(2));
+        assert_eq!(parse_window("45s").unwrap(), Duration::seconds(45));
+        for bad in ["", "24", "h", "0h", "-1d", "3w", "1.5h"] {
+            assert!(parse_window(bad).is_err(), "{:?} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn test_window_bests_only_count_recent_lines() {
+        let input = concat!(
+            // older than the window: the all-time records do not count
+            "{\"ts\":\"2024-01-01T00:00:00Z\",\"event\":\"new_tool_best_hashrate_ths\",\"value\":2.0}\n",
+            "{\"ts\":\"2024-01-01T00:00:00Z\",\"event\":\"new_tool_max_temp_c\",\"value\":80.0}\n",
+            "{\"ts\":\"2024-01-02T10:00:00Z\",\"event\":\"heartbeat\",\"hashrate_ths\":1.4}\n",
+            "not json\n",
+            "{\"ts\":\"2024-01-02T11:00:00Z\",\"event\":\"new_best_difficulty\",\"value\":4.5e9,\"display\":\"4.5G\"}\n",
+            "{\"ts\":1704196800000,\"hashrate_ths\":1.55,\"temp_c\":63.5,\"best_difficulty\":1.2e9}\n",
+            "{\"ts\":\"2024-01-02T12:30:00Z\",\"event\":\"temp_alert\",\"value\":71.0,\"threshold\":70.0}\n",
+        );
+        let since = DateTime::parse_from_rfc3339("2024-01-02T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut bests = WindowBests::default();
+        bests.scan(input.as_bytes(), since).unwrap();
+        assert_eq!(
+            bests,
+            WindowBests {
+                best_hashrate_ths: Some(1.55),
+                best_difficulty: Some(4.5e9),
+                best_difficulty_display: Some("4.5G".to_string()),
+                peak_temp_c: Some(71.0),
+                events: 4,
+                skipped: 1,
+            }
+        );
+    }
+}
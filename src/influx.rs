@@ -0,0 +1,119 @@
+use crate::config::InfluxConfig;
+use crate::metrics::ExtractedMetrics;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+
+//tag keys and values may not contain unescaped commas, spaces or equals signs
+fn escape_tag(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, ',' | ' ' | '=') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+//one "bitaxe" point with millisecond precision; fields without a value this poll are left out, and
+//None is returned when there is nothing to write since a point needs at least one field
+pub fn to_line_protocol(
+    ts: DateTime<Utc>,
+    device: Option<&str>,
+    m: &ExtractedMetrics,
+) -> Option<String> {
+    let fields: Vec<String> = [
+        ("hashrate_ths", m.hashrate_ths),
+        ("efficiency_j_per_th", m.efficiency_j_per_th),
+        ("temp_c", m.temp_c),
+        ("power_w", m.power_w),
+    ]
+    .into_iter()
+    .filter_map(|(name, v)| {
+        v.filter(|v| v.is_finite())
+            .map(|v| format!("{}={}", name, v))
+    })
+    .collect();
+    if fields.is_empty() {
+        return None;
+    }
+    let mut line = "bitaxe".to_string();
+    if let Some(d) = device {
+        line.push_str(",device=");
+        line.push_str(&escape_tag(d));
+    }
+    Some(format!(
+        "{} {} {}",
+        line,
+        fields.join(","),
+        ts.timestamp_millis()
+    ))
+}
+
+//influxdb v2 write api
+pub async fn write(client: &Client, cfg: &InfluxConfig, line: String) -> Result<()> {
+    let url = format!("{}/api/v2/write", cfg.url.trim_end_matches('/'));
+    client
+        .post(url)
+        .query(&[
+            ("org", cfg.org.as_str()),
+            ("bucket", cfg.bucket.as_str()),
+            ("precision", "ms"),
+        ])
+        .header("Authorization", format!("Token {}", cfg.token))
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .body(line)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_line_protocol() {
+        let m = ExtractedMetrics {
+            displayed_all_time: 12.0,
+            displayed_boot_best: 9.0,
+            uptime_secs: Some(300),
+            boot_id: None,
+            boot_time: None,
+            voltage_mv: None,
+            frequency_mhz: None,
+            hashrate_ths: Some(1.6),
+            efficiency_j_per_th: Some(16.5),
+            sample_time: None,
+            best_difficulty: None,
+            best_difficulty_display: None,
+            session_difficulty: None,
+            session_difficulty_display: None,
+            temp_c: Some(58.0),
+            temp_c_raw: None,
+            power_w: None,
+            shares_accepted: None,
+            shares_rejected: None,
+        };
+        let ts = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        assert_eq!(
+            to_line_protocol(ts, Some("garage rig,1"), &m).unwrap(),
+            "bitaxe,device=garage\\ rig\\,1 hashrate_ths=1.6,efficiency_j_per_th=16.5,temp_c=58 1700000000000"
+        );
+        assert_eq!(
+            to_line_protocol(ts, None, &m).unwrap(),
+            "bitaxe hashrate_ths=1.6,efficiency_j_per_th=16.5,temp_c=58 1700000000000"
+        );
+
+        // no fields at all is not a valid point
+        let empty = ExtractedMetrics {
+            hashrate_ths: None,
+            efficiency_j_per_th: None,
+            temp_c: None,
+            ..m
+        };
+        assert_eq!(to_line_protocol(ts, None, &empty), None);
+    }
+}
@@ -1,3 +1,31 @@
+pub mod clock;
 pub mod config;
+pub mod control;
+#[cfg(feature = "dashboard")]
+pub mod dashboard;
+pub mod doctor;
+pub mod event_writer;
+pub mod export;
+#[cfg(feature = "exporter")]
+pub mod exporter;
+pub mod fetch;
+pub mod history;
+pub mod influx;
 pub mod metrics;
+pub mod notify;
+#[cfg(feature = "otlp")]
+pub mod otlp;
+pub mod outcome;
+#[cfg(feature = "parquet")]
+pub mod parquet_sink;
 pub mod persist;
+pub mod pidfile;
+pub mod remote_write;
+pub mod schedule;
+pub mod self_metrics;
+pub mod simulate;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod statsd;
+pub mod stream;
+pub mod tail;
@@ -1,21 +1,62 @@
+mod clock;
 mod config;
+mod control;
+#[cfg(feature = "dashboard")]
+mod dashboard;
+mod doctor;
+mod event_writer;
+mod export;
+#[cfg(feature = "exporter")]
+mod exporter;
+mod fetch;
+mod history;
+mod influx;
 mod metrics;
+mod notify;
+#[cfg(feature = "otlp")]
+mod otlp;
+mod outcome;
+#[cfg(feature = "parquet")]
+mod parquet_sink;
 mod persist;
+mod pidfile;
+mod remote_write;
+mod schedule;
+mod self_metrics;
+mod simulate;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod statsd;
+mod stream;
+mod tail;
 
-use crate::config::AppConfig;
+use crate::clock::{Clock, SystemClock};
+use crate::config::{AppConfig, NotifyMode};
+use crate::control::{ControlCommand, ControlRequest};
+use crate::fetch::{
+    build_client, build_sink_client, device_headers, fetch_text_with_retries, mask_endpoint,
+    resolve_pin, PinnedAddr, RetryPolicy,
+};
 use crate::metrics::{
-    extract_metrics_from_json, DetectionOutcome, Displayed, ExtractedMetrics, Metrics,
-    MonitorState, Thresholds,
+    describe_resolved_values, set_at_suffix, ExtractedMetrics, MonitorState, Thresholds,
+};
+use crate::outcome::{handle_detection_outcome, with_severity};
+#[cfg(feature = "parquet")]
+use crate::parquet_sink::{ParquetSample, ParquetSink};
+use crate::persist::{
+    append_event_jsonl, append_jsonl, config_hash_mismatch, heartbeat_event, load_state_for,
+    resolve_state_path, save_state_to, service_start_event, state_save_due, write_payload_snapshot,
+    EventLog,
 };
-use crate::persist::{append_event_jsonl, load_state, save_state};
-use anyhow::{Context, Result};
+use crate::schedule::{AdaptiveInterval, FailureBackoff, Heartbeat};
+use anyhow::{bail, Context, Result};
 use chrono::Utc;
-use clap::Parser;
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use clap::{Parser, Subcommand};
 use reqwest::Client;
 use serde_json::Value;
-use std::path::PathBuf;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::signal;
 
 //simple CLI for toggling summary mode
@@ -26,18 +67,85 @@ use tokio::signal;
     about = "Polls device metrics and tracks bests"
 )]
 struct Cli {
+    /// Config file (JSON or TOML); repeat to merge several, later files overriding earlier keys.
+    /// Defaults to BITAXE_MONITOR_CONFIG (comma-separated), then config.json
+    #[arg(long = "config", value_name = "PATH", global = true)]
+    config: Vec<PathBuf>,
+
     /// Print saved best metrics and exit
     #[arg(long)]
     summary: bool,
+
+    /// With --summary, report the bests reached within this window (e.g. 30m, 6h, 2d) from the events log
+    #[arg(long, value_name = "DURATION")]
+    window: Option<String>,
+
+    /// Print the values each configured pointer resolved to during preflight
+    #[arg(long)]
+    show_preflight: bool,
+
+    /// Validate the config and run one preflight, then exit without writing events or state
+    #[arg(long)]
+    check_config: bool,
+
+    /// Send a "test" notification through every configured channel, report each and exit
+    #[arg(long)]
+    test_notify: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write the events log as CSV (ts,event,value) and exit
+    ExportCsv {
+        /// Output CSV path
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Clear saved bests (e.g. after swapping hardware) and exit
+    Reset {
+        /// Clear best and worst hashrate
+        #[arg(long)]
+        hashrate: bool,
+        /// Clear best and worst efficiency
+        #[arg(long)]
+        efficiency: bool,
+        /// Clear everything, including the all-time best and device-reported values
+        #[arg(long)]
+        all: bool,
+    },
+    /// Follow events.jsonl and print new events as they are written
+    Tail {
+        /// How often to check the file for new lines, in milliseconds
+        #[arg(long, default_value_t = 500)]
+        interval_ms: u64,
+    },
+    /// Print the saved value distributions as ASCII bars and exit
+    Histogram {
+        /// Only this metric (hashrate_ths or efficiency_j_per_th)
+        #[arg(long)]
+        metric: Option<String>,
+    },
+    /// Check the configured pointers against a saved device response instead of the live device
+    ValidatePointers {
+        /// Captured response body (JSON)
+        #[arg(long)]
+        sample: PathBuf,
+    },
+    /// Replay recorded device responses through extraction and detection, printing each outcome
+    Simulate {
+        /// JSONL file with one full device response per line
+        #[arg(long)]
+        responses: PathBuf,
+    },
+    /// Check the config, endpoint, pointers, storage paths and state file, and print what to fix
+    Doctor,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    //choose config path from env or default
-    let config_path = std::env::var("BITAXE_MONITOR_CONFIG")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| PathBuf::from("config.json"));
-
     //initialize logging so runtime logs can be controlled via RUST_LOG
     tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
@@ -46,144 +154,633 @@ async fn main() -> Result<()> {
     //parse CLI flags (e.g., --summary)
     let cli = Cli::parse();
 
+    //choose config files from --config, the env or the default
+    let config_paths = config::config_paths(&cli.config, std::env::var_os("BITAXE_MONITOR_CONFIG"));
+
+    //doctor loads the config itself so a broken config is reported like any other failed check
+    if let Some(Command::Doctor) = &cli.command {
+        return doctor::run(&config_paths).await;
+    }
+
     //load config file for user-defined endpoint and json pointers
-    let config: AppConfig = config::load_config(&config_path)
-        .with_context(|| format!("failed to load config at {:?}", config_path))?;
+    let mut config: AppConfig = config::load_config(&config_paths)
+        .with_context(|| format!("failed to load config at {:?}", config_paths))?;
+
+    //offline subcommands work from the files on disk and never contact the device
+    if let Some(Command::Reset {
+        hashrate,
+        efficiency,
+        all,
+    }) = &cli.command
+    {
+        return reset_state(&config, *hashrate, *efficiency, *all);
+    }
+    if let Some(Command::Tail { interval_ms }) = &cli.command {
+        return tail::follow(
+            &config.storage.events_path,
+            Duration::from_millis(*interval_ms),
+        )
+        .await;
+    }
+    if let Some(Command::ValidatePointers { sample }) = &cli.command {
+        let text = std::fs::read_to_string(sample)
+            .with_context(|| format!("failed to read sample {:?}", sample))?;
+        let json: Value = serde_json::from_str(&text)
+            .with_context(|| format!("sample {:?} is not valid json", sample))?;
+        let tracked = metrics::TrackedMetrics::from_config(config.metrics.as_ref());
+        let lines = metrics::validate_pointers_sample(&json, &config.pointers, tracked)
+            .with_context(|| format!("pointers do not resolve against {:?}", sample))?;
+        println!("[bitaxe_monitor] pointers resolve against {:?}:", sample);
+        for line in lines {
+            println!("  {}", line);
+        }
+        return Ok(());
+    }
+    if let Some(Command::Simulate { responses }) = &cli.command {
+        let text = std::fs::read_to_string(responses)
+            .with_context(|| format!("failed to read responses {:?}", responses))?;
+        let steps = simulate::replay(
+            &text,
+            &config.pointers,
+            metrics::TrackedMetrics::from_config(config.metrics.as_ref()),
+            Thresholds::from_config(config.thresholds.as_ref()),
+            &SystemClock,
+        )
+        .with_context(|| format!("failed to replay {:?}", responses))?;
+        for (line, outcome) in &steps {
+            println!("line {}: {:?}", line, outcome);
+        }
+        println!("[bitaxe_monitor] replayed {} responses", steps.len());
+        return Ok(());
+    }
+    if let Some(Command::Histogram { metric }) = &cli.command {
+        return print_histograms(&config, metric.as_deref());
+    }
+    if let Some(Command::ExportCsv { out }) = &cli.command {
+        let (rows, skipped) = export::export_csv_file(&config.storage.events_path, out)?;
+        println!(
+            "[bitaxe_monitor] wrote {} events from {} to {:?}",
+            rows, config.storage.events_path, out
+        );
+        if skipped > 0 {
+            eprintln!(
+                "[bitaxe_monitor] WARN: skipped {} unreadable lines in {}",
+                skipped, config.storage.events_path
+            );
+        }
+        return Ok(());
+    }
+
+    if cli.test_notify {
+        return test_notify(&config).await;
+    }
 
     //support a quick summary mode via --summary or bare "summary" arg
     let wants_summary = cli.summary
         || std::env::args()
             .skip(1)
             .any(|a| a == "summary" || a == "--summary");
-    if wants_summary && maybe_print_summary_and_exit(&config)? {
+    if wants_summary && maybe_print_summary_and_exit(&config, cli.window.as_deref())? {
+        return Ok(());
+    }
+
+    //resolve the endpoint host once when pinning so flaky dns/mdns is not hit on every poll
+    let pinned = if config.http.pin_resolved_ip.unwrap_or(false) {
+        resolve_pin(&config.http.endpoint_url).await?
+    } else {
+        None
+    };
+
+    //prepare http client with sensible timeouts; bad headers or a missing auth token fail fast here
+    device_headers(&config.http)?;
+    let client = build_client(&config.http, pinned.as_ref())?;
+
+    //preflight: validate pointers against a live response so failures surface fast
+    //with probe_until_ready a failure is not fatal: the monitor starts, silently, and keeps probing
+    let preflight = match preflight_check(&client, &config).await {
+        Ok(resolved) => Some(resolved),
+        Err(err) if config.probe_until_ready.unwrap_or(false) && !cli.check_config => {
+            eprintln!(
+                "[bitaxe_monitor] WARN: preflight failed, holding all output until a poll succeeds: {err:#}"
+            );
+            None
+        }
+        Err(err) => {
+            return Err(err.context("preflight failed: endpoint/pointers invalid or unreachable"))
+        }
+    };
+
+    //validate-only run: nothing above has written a file, and nothing below runs
+    if let (true, Some((preflight_json, preflight_metrics))) = (cli.check_config, &preflight) {
+        println!(
+            "[bitaxe_monitor] config {:?} is valid and preflight passed; resolved values:",
+            config_paths
+        );
+        for line in describe_resolved_values(preflight_json, &config.pointers, preflight_metrics) {
+            println!("  {}", line);
+        }
         return Ok(());
     }
 
-    //prepare http client with sensible timeouts
-    let mut client_builder = Client::builder()
-        .timeout(Duration::from_secs(config.http.timeout_secs.unwrap_or(10)))
-        .user_agent("bitaxe-monitor/0.1");
+    //one instance per pid file; held until main returns, which removes it
+    let _pid_file = match &config.storage.pid_file {
+        Some(path) => Some(pidfile::PidFile::acquire(path)?),
+        None => None,
+    };
 
-    //parse headers once at startup so invalid names/values fail fast
-    if let Some(hdrs) = &config.http.headers {
-        let mut map = HeaderMap::new();
-        for (k, v) in hdrs.iter() {
-            let name = HeaderName::from_bytes(k.as_bytes())
-                .with_context(|| format!("invalid header name: {}", k))?;
-            let value = HeaderValue::from_str(v)
-                .with_context(|| format!("invalid header value for {}: {}", k, v))?;
-            map.append(name, value);
+    //print resolved values once so a new config can be confirmed before polling starts
+    if let (true, Some((preflight_json, preflight_metrics))) = (cli.show_preflight, &preflight) {
+        println!("[bitaxe_monitor] preflight ok; resolved values:");
+        for line in describe_resolved_values(preflight_json, &config.pointers, preflight_metrics) {
+            println!("  {}", line);
         }
-        client_builder = client_builder.default_headers(map);
     }
 
-    let client = client_builder
-        .build()
-        .context("failed to build http client")?;
+    //one clock for the whole run, handed to everything that stamps or schedules by time
+    let shared_clock: Arc<dyn Clock> = Arc::new(SystemClock);
+    let clock = shared_clock.as_ref();
 
-    //preflight: validate pointers against a live response so failures surface fast
-    preflight_check(&client, &config)
-        .await
-        .context("preflight failed: endpoint/pointers invalid or unreachable")?;
+    //every event goes through this log; its settings come from the storage section, read once
+    let events = Arc::new(EventLog::new(
+        &config.storage,
+        config.tag_events_with_build.unwrap_or(false),
+        shared_clock.clone(),
+    ));
 
-    //load prior state so we can keep all-time best across reboots
-    let mut state = load_state(&config.storage.state_path).unwrap_or_else(|_| MonitorState::new());
+    //with the sqlite backend, events go to the events table instead of events_path
+    #[cfg(feature = "sqlite")]
+    if config.storage.backend == Some(config::StorageBackend::Sqlite) {
+        let db_path = config
+            .storage
+            .db_path
+            .as_deref()
+            .unwrap_or(sqlite::DEFAULT_DB_PATH);
+        events.install_sink(Arc::new(sqlite::SqliteStore::open(db_path)?));
+    }
+
+    //batch event writes through a dedicated task so busy setups do not fsync every line
+    let event_writer = config.storage.event_batch.as_ref().map(|batch| {
+        let writer = Arc::new(event_writer::EventWriter::spawn(
+            events.path(),
+            events.rotation(),
+            shared_clock.clone(),
+            batch.max_events.unwrap_or(50),
+            Duration::from_millis(batch.max_delay_ms.unwrap_or(1000)),
+        ));
+        events.install_sink(writer.clone());
+        writer
+    });
+
+    //still probing: events are dropped until a preflight against the device succeeds
+    let mut probe = preflight
+        .is_none()
+        .then(|| persist::ReadinessGate::hold(&events, clock.now()));
+
+    //load prior state so we can keep all-time best across reboots; the file on disk matches what
+    //was just loaded, so it counts as the last save for state_save_interval_secs
+    let mut state = match load_state_for(&config.storage.state_path, clock.now(), &config.storage) {
+        Ok(state) => MonitorState {
+            last_saved: Some(clock.now()),
+            ..state
+        },
+        Err(_) => MonitorState::new(),
+    };
+
+    //outputs fed after every poll; each stays None unless configured
+    let mut sinks = PollSinks {
+        client: build_sink_client(&config.http)?,
+        ..PollSinks::default()
+    };
+
+    //start forwarding before service_start so the whole run reaches the collector
+    #[cfg(feature = "otlp")]
+    if let Some(c) = &config.otlp {
+        let fwd = Arc::new(otlp::OtlpForwarder::new(c)?);
+        events.forward_to(fwd.clone());
+        sinks.otlp = Some(fwd);
+    }
 
     //write a startup event to help debugging timelines
     append_event_jsonl(
-        &config.storage.events_path,
-        serde_json::json!({
-            "ts": Utc::now(),
-            "event": "service_start",
-            "config": {
-                "endpoint_url": config.http.endpoint_url,
-                "poll_interval_secs": config.poll_interval_secs
-            }
-        }),
+        &events,
+        service_start_event(
+            clock.now(),
+            &config.http.endpoint_url,
+            config.poll_interval_secs,
+        ),
     )?;
 
-    //mask the endpoint url for security
-    fn mask_endpoint(url: &str) -> String {
-        if let Some(x) = url.find("://") {
-            let (scheme, _rest) = url.split_at(x + 3); // keep "http://"
-            let (_host, _tail) = _rest.split_once('/').unwrap_or((_rest, "")); // HIDE HOST/PATH so we dont need host or tail
-            format!("{}[HIDDEN_ENDPOINT EVEN IF RUNNING LOCALLY]", scheme)
-        } else {
-            "[HOST-HIDDEN]".to_string()
+    //flag a state file produced under a different config, then stamp the current hash on future saves
+    let current_hash = config::config_hash(&config);
+    if let Some(msg) = config_hash_mismatch(&state, &current_hash) {
+        eprintln!("[bitaxe_monitor] WARN: {}", msg);
+        append_event_jsonl(
+            &events,
+            with_severity(serde_json::json!({
+                "ts": clock.now(),
+                "event": "config_changed_since_state",
+                "state_config_hash": state.config_hash,
+                "config_hash": current_hash
+            })),
+        )?;
+    }
+    state.config_hash = Some(current_hash);
+
+    //open the parquet sink once per run; samples are buffered and written in row groups
+    #[cfg(feature = "parquet")]
+    if let Some(path) = &config.storage.parquet_path {
+        let sink = ParquetSink::create(
+            path,
+            config.storage.parquet_batch_size.unwrap_or(100),
+            clock.now(),
+        )?;
+        println!(
+            "[bitaxe_monitor] writing parquet samples to {}",
+            sink.path()
+        );
+        sinks.parquet = Some(sink);
+    }
+
+    //live feed: each poll's wide record is pushed to every connected socket client
+    if let Some(path) = &config.stream_socket_path {
+        let hub = stream::StreamHub::default();
+        stream::spawn(path, hub.clone()).await?;
+        println!("[bitaxe_monitor] streaming poll records on {}", path);
+        sinks.stream = Some(hub);
+    }
+
+    //statsd gauges go out over udp after every successful poll
+    if let Some(cfg) = &config.statsd {
+        let sink = statsd::StatsdSink::connect(cfg).await?;
+        println!("[bitaxe_monitor] sending statsd gauges to {}", cfg.addr);
+        sinks.statsd = Some(sink);
+    }
+
+    //control commands are queued to the poll loop below, which owns the state
+    let mut control_rx = match &config.control_socket_path {
+        Some(path) => {
+            let (tx, rx) = tokio::sync::mpsc::channel(8);
+            control::spawn(path, tx).await?;
+            println!("[bitaxe_monitor] accepting control commands on {}", path);
+            Some(rx)
+        }
+        None => None,
+    };
+
+    //serve /metrics and the dashboard from a snapshot refreshed after every successful poll
+    #[cfg(feature = "exporter")]
+    {
+        sinks.exporter = (config.exporter.is_some() || config.dashboard.is_some())
+            .then(exporter::SharedSnapshot::default);
+        if let (Some(exp), Some(shared)) = (&config.exporter, &sinks.exporter) {
+            exporter::spawn(exp, shared.clone()).await?;
+            println!(
+                "[bitaxe_monitor] serving prometheus metrics on http://{}/metrics",
+                exp.listen_addr
+            );
+        }
+        #[cfg(feature = "dashboard")]
+        if let (Some(dash), Some(shared)) = (&config.dashboard, &sinks.exporter) {
+            dashboard::spawn(dash, shared.clone()).await?;
+            println!(
+                "[bitaxe_monitor] serving dashboard on http://{}/",
+                dash.listen_addr
+            );
         }
     }
 
-    //print service start message WITH MASKED ENDPOINT URL FOR SECURITY
+    //the client, adaptive schedule and failure backoff carried from one poll to the next
+    let mut poller = Poller::new(&config, client, pinned);
+
+    //with notifications.queued, webhooks are posted from a background task that shutdown drains;
+    //it posts through the plain sink client, never the device one
+    sinks.notify_queue = config
+        .notifications
+        .as_ref()
+        .filter(|n| n.queued.unwrap_or(false))
+        .map(|_| notify::NotifyQueue::spawn(sinks.client.clone(), events.clone()));
+
+    //print service start message, masking the endpoint url for security unless http.mask_endpoint is false
+    let shown_endpoint = if config.http.mask_endpoint.unwrap_or(true) {
+        mask_endpoint(&config.http.endpoint_url)
+    } else {
+        config.http.endpoint_url.clone()
+    };
     println!(
         "Starting [bitaxe_monitor] service: polling {} every {}s -> to exit, press Ctrl+C",
-        mask_endpoint(&config.http.endpoint_url),
-        config.poll_interval_secs
+        shown_endpoint, config.poll_interval_secs
     );
+
     //do one poll immediately so first data shows up without waiting a full interval
-    if let Err(err) = poll_once(&client, &config, &mut state).await {
-        //log errors to events file so failures are visible later
-        let _ = append_event_jsonl(
-            &config.storage.events_path,
-            serde_json::json!({
-                "ts": Utc::now(),
-                "event": "poll_error",
-                "error": err.to_string()
-            }),
-        );
+    if probe_ready(&poller.client, &config, &events, &mut probe, clock).await {
+        let result = poll_once(
+            &poller.client,
+            &config,
+            &events,
+            &mut state,
+            &mut sinks,
+            clock,
+        )
+        .await;
+        handle_poll_result(
+            result,
+            &config,
+            &events,
+            &mut state,
+            &mut sinks,
+            &mut poller,
+            clock,
+        )
+        .await;
+    } else {
+        poller.schedule.record(false);
     }
 
+    //SIGHUP re-reads the config file without losing in-memory state (unix only)
+    let mut hangup = listen_hangup();
+
+    //heartbeats run on their own clock so quiet logs still show the monitor is alive
+    let mut heartbeat = heartbeat_for(config.heartbeat_interval_secs, clock);
+
+    //a fixed deadline rather than a fresh sleep per iteration, so heartbeats and control commands
+    //handled in between do not push the next poll back
+    let mut next_poll = tokio::time::Instant::now() + poller.next_delay(&config, &state);
+
     //run polling loop until ctrl+c
-    let mut interval = tokio::time::interval(Duration::from_secs(config.poll_interval_secs));
     loop {
         tokio::select! {
-            _ = interval.tick() => {
-                if let Err(err) = poll_once(&client, &config, &mut state).await {
-                    //log errors to events file so failures are visible later
+            _ = tokio::time::sleep_until(next_poll) => {
+                if !probe_ready(&poller.client, &config, &events, &mut probe, clock).await {
+                    next_poll = tokio::time::Instant::now() + poller.schedule.current();
+                    continue;
+                }
+                let result = poll_once(&poller.client, &config, &events, &mut state, &mut sinks, clock).await;
+                handle_poll_result(result, &config, &events, &mut state, &mut sinks, &mut poller, clock).await;
+                next_poll = tokio::time::Instant::now() + poller.next_delay(&config, &state);
+            }
+            _ = next_heartbeat(heartbeat.as_ref(), clock) => {
+                if heartbeat.as_mut().is_some_and(|hb| hb.due(clock.now())) {
+                    sinks.sample_self(&config, &mut state);
                     let _ = append_event_jsonl(
-                        &config.storage.events_path,
-                        serde_json::json!({
-                            "ts": Utc::now(),
-                            "event": "poll_error",
-                            "error": err.to_string()
-                        })
+                        &events,
+                        heartbeat_event(clock.now(), &state),
                     );
                 }
             }
+            Some((cmd, reply)) = next_control(&mut control_rx) => {
+                let _ = reply.send(handle_control(&config, &events, &mut state, cmd, clock));
+            }
+            _ = next_hangup(&mut hangup) => {
+                if reload_config(&config_paths, &mut config, &events, &mut state, clock) {
+                    heartbeat = heartbeat_for(config.heartbeat_interval_secs, clock);
+                    //the interval settings may have changed, so start over from the new base
+                    poller.reset_schedule(&config);
+                    next_poll = tokio::time::Instant::now() + poller.schedule.current();
+                }
+            }
             _ = signal::ctrl_c() => {
-                let ts = Utc::now();
-            let mut errs: Vec<String> = Vec::new();
+                let ts = clock.now();
+                let mut errs: Vec<String> = Vec::new();
 
-            if let Err(err) = append_event_jsonl(
-                &config.storage.events_path,
-                serde_json::json!({"ts": ts, "event": "service_stop"}),
-            ) {
-                eprintln!("[bitaxe_monitor] WARN: failed to write service_stop: {err}");
-                errs.push(format!("service_stop: {err}"));
-            }
+                if let Err(err) = append_event_jsonl(
+                    &events,
+                    with_severity(serde_json::json!({"ts": ts, "event": "service_stop"})),
+                ) {
+                    eprintln!("[bitaxe_monitor] WARN: failed to write service_stop: {err}");
+                    errs.push(format!("service_stop: {err}"));
+                }
+                //deliver queued alerts (e.g. the last reboot) before the event log is closed
+                if let Some(queue) = sinks.notify_queue.take() {
+                    let timeout = config
+                        .notifications
+                        .as_ref()
+                        .and_then(|n| n.flush_timeout_secs)
+                        .unwrap_or(10);
+                    let report = queue.shutdown(Duration::from_secs(timeout)).await;
+                    if let Err(err) = append_event_jsonl(
+                        &events,
+                        with_severity(serde_json::json!({
+                            "ts": clock.now(),
+                            "event": "notifications_flushed",
+                            "delivered": report.delivered,
+                            "dropped": report.dropped
+                        })),
+                    ) {
+                        errs.push(format!("notifications_flushed: {err}"));
+                    }
+                }
+                //write out any batched events before they are forwarded below
+                if let Some(writer) = &event_writer {
+                    events.remove_sink();
+                    writer.shutdown().await;
+                }
 
-            //add guidance so users know full history of records lives in events.jsonl
-            //this message explains that this file only keeps the latest values
-            let mut state = state.clone();
-            state._note = Some("historical bests (past records) are logged in events.jsonl; this file stores only the latest values".to_string());
-            if let Err(err) = save_state(&config.storage.state_path, &state) {
-                eprintln!("[bitaxe_monitor] WARN: failed to save myBitAxeInfo.json: {err}");
-                errs.push(format!("save_state: {err}"));
-            }
+                if probe.is_none() {
+                    //add guidance so users know full history of records lives in events.jsonl
+                    //this message explains that this file only keeps the latest values
+                    state._note = Some("historical bests (past records) are logged in events.jsonl; this file stores only the latest values".to_string());
+                    //shutdown always saves, whatever state_save_interval_secs says
+                    if let Err(err) = save_state_debounced(&config, &mut state, true, clock) {
+                        eprintln!("[bitaxe_monitor] WARN: failed to save myBitAxeInfo.json: {err}");
+                        errs.push(format!("save_state: {err}"));
+                    }
+                } else {
+                    //never got a working poll: leave the state file as it was
+                }
 
-            if errs.is_empty() {
-                println!("[bitaxe_monitor] Graceful shutdown received → saved myBitAxeInfo.json and wrote service_stop to events.jsonl");
-                break;
-            } else {
-                return Err(anyhow::anyhow!("shutdown errors: {}", errs.join("; ")));
-            }
+                //flush the otlp batch, which already holds service_stop
+                #[cfg(feature = "otlp")]
+                if let Some(fwd) = sinks.otlp.take() {
+                    if let Err(err) = fwd.shutdown() {
+                        eprintln!("[bitaxe_monitor] WARN: failed to flush otlp logs: {err}");
+                        errs.push(format!("otlp: {err}"));
+                    }
+                }
+
+                //flush the partial batch and write the footer so the parquet file is readable
+                #[cfg(feature = "parquet")]
+                if let Some(sink) = sinks.parquet.take() {
+                    if let Err(err) = sink.close() {
+                        eprintln!("[bitaxe_monitor] WARN: failed to close parquet file: {err}");
+                        errs.push(format!("parquet: {err}"));
+                    }
+                }
+
+                if errs.is_empty() {
+                    if probe.is_none() {
+                        println!("[bitaxe_monitor] Graceful shutdown received → saved myBitAxeInfo.json and wrote service_stop to events.jsonl");
+                    } else {
+                        println!("[bitaxe_monitor] Graceful shutdown received → no poll succeeded, so myBitAxeInfo.json was left as it was; wrote service_stop to events.jsonl");
+                    }
+                    break;
+                } else {
+                    return Err(anyhow::anyhow!("shutdown errors: {}", errs.join("; ")));
+                }
             }
         }
     }
 
     Ok(())
 }
+#[cfg(unix)]
+type HangupSignal = signal::unix::Signal;
+#[cfg(not(unix))]
+type HangupSignal = ();
+
+#[cfg(unix)]
+fn listen_hangup() -> Option<HangupSignal> {
+    match signal::unix::signal(signal::unix::SignalKind::hangup()) {
+        Ok(sig) => Some(sig),
+        Err(err) => {
+            eprintln!("[bitaxe_monitor] WARN: config reload on SIGHUP unavailable: {err}");
+            None
+        }
+    }
+}
+
+//there is no SIGHUP on windows; the reload branch simply never fires
+#[cfg(not(unix))]
+fn listen_hangup() -> Option<HangupSignal> {
+    None
+}
+
+#[cfg(unix)]
+async fn next_hangup(sig: &mut Option<HangupSignal>) {
+    if let Some(sig) = sig {
+        if sig.recv().await.is_some() {
+            return;
+        }
+    }
+    std::future::pending::<()>().await
+}
+
+#[cfg(not(unix))]
+async fn next_hangup(_sig: &mut Option<HangupSignal>) {
+    std::future::pending::<()>().await
+}
+
+fn heartbeat_for(secs: Option<u64>, clock: &dyn Clock) -> Option<Heartbeat> {
+    secs.map(|s| Heartbeat::new(Duration::from_secs(s), clock.now()))
+}
+
+//sleeps until the heartbeat should be due; the caller still asks the heartbeat, since the clock
+//decides and not the sleep
+async fn next_heartbeat(heartbeat: Option<&Heartbeat>, clock: &dyn Clock) {
+    match heartbeat {
+        Some(hb) => tokio::time::sleep(hb.until_due(clock.now())).await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn next_control(
+    rx: &mut Option<tokio::sync::mpsc::Receiver<ControlRequest>>,
+) -> Option<ControlRequest> {
+    match rx {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+//apply a control socket command to the live state; returns the reply line for the client
+fn handle_control(
+    config: &AppConfig,
+    events: &EventLog,
+    state: &mut MonitorState,
+    cmd: ControlCommand,
+    clock: &dyn Clock,
+) -> String {
+    let dir = config
+        .checkpoint_dir
+        .as_deref()
+        .unwrap_or(control::DEFAULT_CHECKPOINT_DIR);
+    let result = match &cmd {
+        ControlCommand::Checkpoint(name) => control::checkpoint(state, dir, name).map(|path| {
+            (
+                "checkpoint_saved",
+                name,
+                format!("ok: saved {} to {}", name, path),
+            )
+        }),
+        //a restore is persisted right away so a crash before the next poll keeps it
+        ControlCommand::Restore(name) => control::restore(state, dir, name)
+            .and_then(|()| save_state_debounced(config, state, true, clock))
+            .map(|()| {
+                (
+                    "checkpoint_restored",
+                    name,
+                    format!("ok: restored {}", name),
+                )
+            }),
+    };
+    match result {
+        Ok((event, name, reply)) => {
+            let _ = append_event_jsonl(
+                events,
+                with_severity(
+                    serde_json::json!({ "ts": clock.now(), "event": event, "name": name }),
+                ),
+            );
+            reply
+        }
+        Err(err) => format!("error: {:#}", err),
+    }
+}
+
+//swap in the config file's current contents; an invalid file is logged as config_reload_failed and
+//the running config is kept. returns true when the config was replaced
+fn reload_config(
+    paths: &[PathBuf],
+    config: &mut AppConfig,
+    events: &EventLog,
+    state: &mut MonitorState,
+    clock: &dyn Clock,
+) -> bool {
+    let reloaded = config::load_config(paths).and_then(|mut next| {
+        let kept = config::keep_startup_settings(config, &mut next)?;
+        Ok((next, kept))
+    });
+    match reloaded {
+        Ok((next, kept)) => {
+            *config = next;
+            if !kept.is_empty() {
+                eprintln!(
+                    "[bitaxe_monitor] WARN: changes to {} take effect after a restart",
+                    kept.join(", ")
+                );
+            }
+            let hash = config::config_hash(config);
+            state.config_hash = Some(hash.clone());
+            println!("[bitaxe_monitor] reloaded config from {:?}", paths);
+            let _ = append_event_jsonl(
+                events,
+                with_severity(serde_json::json!({
+                    "ts": clock.now(),
+                    "event": "config_reloaded",
+                    "config_hash": hash,
+                    "restart_required": kept
+                })),
+            );
+            true
+        }
+        Err(err) => {
+            eprintln!(
+                "[bitaxe_monitor] WARN: config reload failed, keeping the running config: {err:#}"
+            );
+            let _ = append_event_jsonl(
+                events,
+                with_severity(serde_json::json!({
+                    "ts": clock.now(),
+                    "event": "config_reload_failed",
+                    "error": format!("{err:#}")
+                })),
+            );
+            false
+        }
+    }
+}
+
 //check command-line args for a summary flag; if present, print best metrics and exit
-fn maybe_print_summary_and_exit(config: &AppConfig) -> Result<bool> {
+fn maybe_print_summary_and_exit(config: &AppConfig, window: Option<&str>) -> Result<bool> {
     //accept either "summary" or "--summary" for convenience
     let has_summary_flag = std::env::args()
         .skip(1)
@@ -191,21 +788,117 @@ fn maybe_print_summary_and_exit(config: &AppConfig) -> Result<bool> {
     if !has_summary_flag {
         return Ok(false);
     }
+    if let Some(window) = window {
+        print_window_summary(config, window)?;
+        return Ok(true);
+    }
 
     //load saved state so we can report best values observed so far
-    match load_state(&config.storage.state_path) {
+    let state_path = resolve_state_path(&config.storage.state_path, Utc::now());
+    match load_state_for(&config.storage.state_path, Utc::now(), &config.storage) {
         Ok(state) => {
-            println!("state file: {}", &config.storage.state_path);
+            println!("state file: {}", state_path);
             if let Some(v) = state.tool_best_hashrate_ths {
-                println!("best hashrate: {:.2} TH/s", v);
+                println!(
+                    "best hashrate: {:.2} TH/s{}",
+                    v,
+                    set_at_suffix(state.tool_best_hashrate_ths_at)
+                );
             } else {
                 println!("best hashrate: n/a");
             }
             if let Some(v) = state.tool_best_efficiency_j_per_th {
-                println!("best efficiency: {:.2} J/TH", v);
+                println!(
+                    "best efficiency: {:.2} J/TH{}",
+                    v,
+                    set_at_suffix(state.tool_best_efficiency_j_per_th_at)
+                );
             } else {
                 println!("best efficiency: n/a");
             }
+            if let Some(v) = state.tool_worst_hashrate_ths {
+                println!(
+                    "worst hashrate: {:.2} TH/s{}",
+                    v,
+                    set_at_suffix(state.tool_worst_hashrate_ths_at)
+                );
+            }
+            if let Some(v) = state.tool_worst_efficiency_j_per_th {
+                println!(
+                    "worst efficiency: {:.2} J/TH{}",
+                    v,
+                    set_at_suffix(state.tool_worst_efficiency_j_per_th_at)
+                );
+            }
+            if let Some(v) = state.tool_best_difficulty {
+                match &state.tool_best_difficulty_display {
+                    Some(display) => println!(
+                        "best difficulty: {} ({:.0}){}",
+                        display,
+                        v,
+                        set_at_suffix(state.tool_best_difficulty_at)
+                    ),
+                    None => println!(
+                        "best difficulty: {:.0}{}",
+                        v,
+                        set_at_suffix(state.tool_best_difficulty_at)
+                    ),
+                }
+            }
+            if let Some(v) = state.session_best_difficulty {
+                match &state.session_best_difficulty_display {
+                    Some(display) => println!("session best difficulty: {} ({:.0})", display, v),
+                    None => println!("session best difficulty: {:.0}", v),
+                }
+            }
+            if let Some(v) = state.tool_max_temp_c {
+                println!(
+                    "max chip temperature: {:.1} °C{}",
+                    v,
+                    set_at_suffix(state.tool_max_temp_c_at)
+                );
+            }
+            if let Some(v) = state.tool_min_power_w {
+                println!(
+                    "min power draw: {:.1} W{}",
+                    v,
+                    set_at_suffix(state.tool_min_power_w_at)
+                );
+            }
+            if let Some(v) = state.tool_max_power_w {
+                println!(
+                    "max power draw: {:.1} W{}",
+                    v,
+                    set_at_suffix(state.tool_max_power_w_at)
+                );
+            }
+            if let Some(v) = state.rolling_avg_hashrate_ths() {
+                println!(
+                    "rolling avg hashrate (last {}): {:.2} TH/s",
+                    state.recent_hashrate_ths.len(),
+                    v
+                );
+            }
+            let samples: Vec<f64> = state.recent_hashrate_ths.iter().copied().collect();
+            let trend = metrics::sparkline(&samples);
+            if !trend.is_empty() {
+                println!("hashrate trend: {}", trend);
+            }
+            if let Some(v) = state.last_jitter_ms {
+                println!("network jitter (latency stddev): {:.1} ms", v);
+            }
+            if let Some(v) = state.last_efficiency_slope_per_hour {
+                println!("efficiency trend: {:+.3} J/TH per hour", v);
+            }
+            if let Some(since) = state.circuit.open_since {
+                println!(
+                    "circuit: open since {} ({} failed polls)",
+                    since.format("%Y-%m-%d %H:%M UTC"),
+                    state.circuit.failures
+                );
+            } else if config.circuit_breaker.is_some() {
+                println!("circuit: closed");
+            }
             if let Some(v) = state.last_displayed_all_time {
                 println!("device all-time best: {:.2}", v);
             }
@@ -213,215 +906,1158 @@ fn maybe_print_summary_and_exit(config: &AppConfig) -> Result<bool> {
                 println!("device boot best: {:.2}", v);
             }
             println!(
-                "monitor global best (internal): {:.2}",
-                state.tool_global_all_time_best
+                "monitor global best (internal): {:.2}{}",
+                state.tool_global_all_time_best,
+                set_at_suffix(state.tool_global_all_time_best_at)
             );
         }
         Err(_) => {
-            println!("state file not found yet: {}", &config.storage.state_path);
+            println!("state file not found yet: {}", state_path);
             println!("run the monitor first to populate best values");
         }
     }
     Ok(true)
 }
 
-async fn poll_once(client: &Client, config: &AppConfig, state: &mut MonitorState) -> Result<()> {
+//bests reached within the last `window`, read back from events.jsonl and, when configured, the wide
+//records file (which has every poll rather than only new records and heartbeats)
+fn print_window_summary(config: &AppConfig, window: &str) -> Result<()> {
+    let since = Utc::now() - history::parse_window(window)?;
+    let mut bests = history::WindowBests::default();
+    bests.scan_file(&config.storage.events_path, since)?;
+    if let Some(records_path) = &config.storage.records_path {
+        if Path::new(records_path).exists() {
+            bests.scan_file(records_path, since)?;
+        }
+    }
+
+    println!(
+        "window: last {} (since {})",
+        window.trim(),
+        since.format("%Y-%m-%d %H:%M UTC")
+    );
+    match bests.best_hashrate_ths {
+        Some(v) => println!("best hashrate: {:.2} TH/s", v),
+        None => println!("best hashrate: n/a"),
+    }
+    match (bests.best_difficulty, &bests.best_difficulty_display) {
+        (Some(v), Some(display)) => println!("best difficulty: {} ({:.0})", display, v),
+        (Some(v), None) => println!("best difficulty: {:.0}", v),
+        (None, _) => println!("best difficulty: n/a"),
+    }
+    match bests.peak_temp_c {
+        Some(v) => println!("peak chip temperature: {:.1} °C", v),
+        None => println!("peak chip temperature: n/a"),
+    }
+    println!("lines in window: {}", bests.events);
+    if bests.skipped > 0 {
+        eprintln!(
+            "[bitaxe_monitor] WARN: skipped {} unreadable lines",
+            bests.skipped
+        );
+    }
+    Ok(())
+}
+
+//--test-notify: the delivery path of every channel without waiting for a real event; fails when any
+//channel did, so scripts can check the exit code
+async fn test_notify(config: &AppConfig) -> Result<()> {
+    let client = build_sink_client(&config.http)?;
+    let results = notify::send_test(
+        &client,
+        config.notifications.as_ref(),
+        config.device_name.as_deref(),
+        Utc::now(),
+    )
+    .await;
+    if results.is_empty() {
+        bail!("no notification channels are configured under notifications");
+    }
+    let mut failed = 0;
+    for (name, result) in &results {
+        match result {
+            Ok(()) => println!("[bitaxe_monitor] test notification via {}: ok", name),
+            Err(err) => {
+                failed += 1;
+                eprintln!(
+                    "[bitaxe_monitor] test notification via {}: FAILED: {:#}",
+                    name, err
+                );
+            }
+        }
+    }
+    if failed > 0 {
+        bail!(
+            "{} of {} notification channels failed",
+            failed,
+            results.len()
+        );
+    }
+    Ok(())
+}
+
+//outputs set up once at startup and fed after every poll; each is None when not configured (or
+//not compiled in)
+#[derive(Default)]
+struct PollSinks {
+    //plain client for webhooks, remote write and influx; device headers never go through it
+    client: Client,
+    #[cfg(feature = "parquet")]
+    parquet: Option<ParquetSink>,
+    stream: Option<stream::StreamHub>,
+    notify_queue: Option<notify::NotifyQueue>,
+    statsd: Option<statsd::StatsdSink>,
+    self_metrics: Option<self_metrics::SelfMetrics>,
+    #[cfg(feature = "exporter")]
+    exporter: Option<exporter::SharedSnapshot>,
+    #[cfg(feature = "otlp")]
+    otlp: Option<Arc<otlp::OtlpForwarder>>,
+}
+
+impl PollSinks {
+    //with self_metrics, refresh the monitor's own rss and cpu in the live state; the sampler is
+    //made on first use and dropped (with the values) when a reload turns it off
+    fn sample_self(&mut self, config: &AppConfig, state: &mut MonitorState) {
+        if !config.self_metrics.unwrap_or(false) {
+            self.self_metrics = None;
+            state.process_rss_bytes = None;
+            state.process_cpu_percent = None;
+            return;
+        }
+        if self.self_metrics.is_none() {
+            match self_metrics::SelfMetrics::new() {
+                Ok(sampler) => self.self_metrics = Some(sampler),
+                Err(err) => {
+                    eprintln!("[bitaxe_monitor] WARN: {:#}", err);
+                    return;
+                }
+            }
+        }
+        if let Some(sampler) = self.self_metrics.as_mut() {
+            sampler.sample(state);
+        }
+    }
+
+    //refresh the exporter/dashboard snapshot; polled_at is None after a failed poll, which changes
+    //the state (e.g. an open circuit) but not the time of the last successful poll
+    fn publish(&self, state: &MonitorState, polled_at: Option<chrono::DateTime<Utc>>) {
+        #[cfg(feature = "exporter")]
+        if let Some(shared) = &self.exporter {
+            match polled_at {
+                Some(at) => exporter::publish(shared, state, at),
+                None => exporter::publish_state(shared, state),
+            }
+        }
+        #[cfg(not(feature = "exporter"))]
+        let _ = (state, polled_at);
+    }
+
+    //events reach the otlp batch as they are written; failed exports are logged locally as
+    //otlp_error and never stop polling
+    fn report_otlp_errors(&self, events: &EventLog, clock: &dyn Clock) {
+        #[cfg(feature = "otlp")]
+        if let Some(err) = self.otlp.as_ref().and_then(|fwd| fwd.take_error()) {
+            let _ = append_event_jsonl(
+                events,
+                with_severity(serde_json::json!({
+                    "ts": clock.now(),
+                    "event": "otlp_error",
+                    "error": err
+                })),
+            );
+        }
+        #[cfg(not(feature = "otlp"))]
+        let _ = (events, clock);
+    }
+}
+
+//returns true when the poll produced an event so adaptive polling can snap back to the base interval
+async fn poll_once(
+    client: &Client,
+    config: &AppConfig,
+    events: &EventLog,
+    state: &mut MonitorState,
+    sinks: &mut PollSinks,
+    clock: &dyn Clock,
+) -> Result<bool> {
+    //the digest goes out at the first poll after midnight, whether or not the device answers it
+    post_daily_digest(&sinks.client, config, events, state, clock).await?;
     //fetch the endpoint json with simple retry/backoff so transient network errors do not cause missed polls
-    let text = fetch_text_with_retries(client, config, 3, Duration::from_millis(500)).await?;
+    let started = Instant::now();
+    //right after a reboot, give up quickly and let the next poll try again
+    let policy = if state.reboot_grace.active(clock.now()) {
+        RetryPolicy {
+            max_retries: 1,
+            base_delay: Duration::from_millis(250),
+            max_delay: None,
+            jitter: false,
+        }
+    } else {
+        RetryPolicy::from_config(config.http.retry.as_ref(), 3, Duration::from_millis(500))
+    };
+    let text = fetch_text_with_retries(client, &config.http, policy).await?;
+    record_fetch_latency(config, events, state, started.elapsed(), clock)?;
+    record_payload_snapshot(config, events, state, &text, clock)?;
+    if let Some(downtime) = state.reboot_grace.on_success(clock.now()) {
+        append_event_jsonl(
+            events,
+            with_severity(serde_json::json!({
+                "ts": clock.now(),
+                "event": "reboot_complete",
+                "downtime_secs": downtime
+            })),
+        )?;
+    }
     let json: Value =
         serde_json::from_str(&text).with_context(|| "endpoint did not return valid json")?;
 
+    //optional metrics that come and go are reported as events and skipped while missing
+    let lenient_pointers;
+    let pointers = if config.metric_availability_events.unwrap_or(false) {
+        for (name, available) in metrics::update_metric_availability(state, &json, &config.pointers)
+        {
+            append_event_jsonl(
+                events,
+                with_severity(serde_json::json!({
+                    "ts": clock.now(),
+                    "event": if available { "metric_available" } else { "metric_unavailable" },
+                    "metric": name.trim_start_matches("json_pointer_"),
+                })),
+            )?;
+        }
+        lenient_pointers = metrics::without_missing_optional(&json, &config.pointers);
+        &lenient_pointers
+    } else {
+        &config.pointers
+    };
+
     //pull metric numbers from json using user-provided json pointers
-    let ExtractedMetrics {
-        displayed_all_time,
-        displayed_boot_best,
-        uptime_secs,
-        boot_id,
-        hashrate_ths,
-        efficiency_j_per_th,
-    } = extract_metrics_from_json(&json, &config.pointers)
+    let tracked = metrics::TrackedMetrics::from_config(config.metrics.as_ref());
+    let mut extracted = metrics::extract_tracked_metrics(&json, pointers, tracked)
         .with_context(|| "failed extracting metrics using json pointers")?;
 
+    //drop wrapped or absurd uptimes for this poll so they never reach the reboot heuristic
+    let max_uptime = config
+        .thresholds
+        .as_ref()
+        .and_then(|t| t.max_plausible_uptime_secs)
+        .unwrap_or(315_360_000);
+    if let Some(bad) =
+        metrics::derive_uptime_from_boot_time(&mut extracted, clock.now().timestamp())
+    {
+        append_event_jsonl(
+            events,
+            with_severity(serde_json::json!({
+                "ts": clock.now(),
+                "event": "implausible_boot_time",
+                "boot_time": bad,
+                "max_skew_secs": metrics::BOOT_TIME_MAX_SKEW_SECS
+            })),
+        )?;
+    }
+    if let Some(bad) = metrics::take_implausible_uptime(&mut extracted, max_uptime) {
+        append_event_jsonl(
+            events,
+            with_severity(serde_json::json!({
+                "ts": clock.now(),
+                "event": "implausible_uptime",
+                "uptime_secs": bad,
+                "max_plausible_uptime_secs": max_uptime
+            })),
+        )?;
+    }
+
+    //a garbage spike is dropped for this poll rather than becoming a best that never goes away
+    let outliers = metrics::take_outliers(
+        &mut extracted,
+        config
+            .thresholds
+            .as_ref()
+            .and_then(|t| t.max_plausible_hashrate_ths),
+        config
+            .thresholds
+            .as_ref()
+            .and_then(|t| t.max_plausible_temp_c),
+    );
+    for (metric, value, max) in outliers {
+        append_event_jsonl(
+            events,
+            with_severity(serde_json::json!({
+                "ts": clock.now(),
+                "event": "outlier_rejected",
+                "metric": metric,
+                "value": value,
+                "max": max
+            })),
+        )?;
+    }
+
+    //skip stale samples (e.g. served from a caching proxy) so old values never count toward bests
+    if let Some(ts) = extracted.sample_time {
+        state.last_sample_time = Some(ts);
+        let max_age = config
+            .thresholds
+            .as_ref()
+            .and_then(|t| t.max_sample_age_secs);
+        let now = clock.now().timestamp();
+        if let Some(max_age) = max_age.filter(|m| metrics::is_sample_stale(ts, now, *m)) {
+            append_event_jsonl(
+                events,
+                with_severity(serde_json::json!({
+                    "ts": clock.now(),
+                    "event": "stale_sample",
+                    "sample_time": ts,
+                    "age_secs": now - ts as i64,
+                    "max_sample_age_secs": max_age
+                })),
+            )?;
+            //save right away so last_sample_time is kept, but report a quiet poll: a device stuck
+            //behind a cache must not keep the adaptive interval at its base
+            save_state_debounced(config, state, true, clock)?;
+            return Ok(false);
+        }
+    }
+
     //evaluate for reboots and new bests
-    let (eps_hash, eps_eff) = if let Some(t) = &config.thresholds {
-        (
-            t.epsilon_hashrate_ths.unwrap_or(0.01),
-            t.epsilon_efficiency_j_per_th.unwrap_or(0.01),
-        )
-    } else {
-        (0.01, 0.01)
-    };
-    let displayed = Displayed {
-        all_time: displayed_all_time,
-        boot_best: displayed_boot_best,
-    };
-    let metrics_values = Metrics {
-        uptime_secs,
-        boot_id,
-        hashrate_ths,
-        efficiency_j_per_th,
-    };
-    let thresholds = Thresholds {
-        epsilon_hashrate_ths: eps_hash,
-        epsilon_efficiency_j_per_th: eps_eff,
-    };
-    let outcome = metrics::detect_changes(state, displayed, metrics_values, thresholds);
+    let (displayed, metrics_values) = extracted.detection_inputs();
+    let thresholds = Thresholds::from_config(config.thresholds.as_ref());
+    let mut outcome =
+        metrics::detect_changes(state, displayed, metrics_values, thresholds, clock.now());
+    if let Some(flap) = &config.boot_flapping {
+        outcome.boot_flapping = state.boot_flapping.record(
+            clock.now(),
+            outcome.boot_detected,
+            flap.window_secs,
+            flap.max_boots,
+        );
+    }
+
+    outcome.temp_c_raw = extracted.temp_c_raw;
+
+    if let (true, Some(grace)) = (outcome.boot_detected, config.post_reboot_grace_secs) {
+        state.reboot_grace.start(clock.now(), grace);
+    }
+
+    //overclock settings are read before the events are written so a new best carries them
+    state.last_voltage_mv = extracted.voltage_mv;
+    state.last_frequency_mhz = extracted.frequency_mhz;
 
     //record events and persist state
-    handle_detection_outcome(&config.storage.events_path, state, outcome)?;
+    let had_event = outcome.has_events();
+    let emitted = handle_detection_outcome(
+        events,
+        state,
+        outcome,
+        config.human_values.unwrap_or(false),
+        config
+            .boot_flapping
+            .as_ref()
+            .and_then(|f| f.escalate)
+            .unwrap_or(false),
+        clock,
+    )?;
+    state.last_hashrate_ths = extracted.hashrate_ths;
+    state.last_efficiency_j_per_th = extracted.efficiency_j_per_th;
+    if let Some(digest) = state.digest.as_mut() {
+        digest.observe(
+            extracted.hashrate_ths,
+            extracted.efficiency_j_per_th,
+            &emitted,
+        );
+    }
+    //smooth out instantaneous hashrate readings; pushed before saving so the window persists
+    if let Some(h) = extracted.hashrate_ths.filter(|v| v.is_finite()) {
+        state.push_hashrate_sample(h, config.rolling_window.unwrap_or(10));
+    }
+    //value distributions persist with the state so they survive restarts
+    if let Some(h) = &config.histograms {
+        if let (Some(edges), Some(v)) = (&h.hashrate_ths, extracted.hashrate_ths) {
+            state.observe_histogram("hashrate_ths", edges, v);
+        }
+        if let (Some(edges), Some(v)) = (&h.efficiency_j_per_th, extracted.efficiency_j_per_th) {
+            state.observe_histogram("efficiency_j_per_th", edges, v);
+        }
+    }
+
     //add guidance so users know full history of records lives in events.jsonl
     state._note = Some("historical bests (past records) are logged in events.jsonl; this file stores only the latest values".to_string());
-    save_state(&config.storage.state_path, state)?;
+    //with notifications.dedupe, a record already announced (e.g. before a restart) is not sent
+    //again; what was announced goes into the state saved below
+    let to_notify = if config
+        .notifications
+        .as_ref()
+        .and_then(|n| n.dedupe)
+        .unwrap_or(false)
+    {
+        notify::unannounced(&mut state.last_notified, &emitted, clock.now())
+    } else {
+        emitted.clone()
+    };
+
+    //dated state paths roll over at UTC midnight; the new file starts from the in-memory bests
+    //a poll with events (new bests, reboots, alerts) is saved right away, quiet ones are debounced
+    save_state_debounced(config, state, had_event, clock)?;
+
+    //notify after state is saved so a slow webhook never delays persisting the new bests
+    notify_events(
+        &sinks.client,
+        config,
+        events,
+        sinks.notify_queue.as_ref(),
+        &to_notify,
+        clock,
+    )
+    .await?;
+
+    record_efficiency_trend(config, events, state, extracted.efficiency_j_per_th, clock)?;
+
+    //buffer the sample for parquet; a full batch is written as one row group
+    #[cfg(feature = "parquet")]
+    if let Some(sink) = &mut sinks.parquet {
+        sink.push(ParquetSample::from_metrics(clock.now(), &extracted))?;
+    }
+
+    //append one wide record per poll when enabled; separate from the change-driven events log
+    if config.wide_records.unwrap_or(false) {
+        //with wide_record_on_change_only, quiet polls are skipped apart from a periodic keepalive
+        let write_record = !config.wide_record_on_change_only.unwrap_or(false)
+            || state.wide_record_gate.should_write(
+                &extracted,
+                thresholds,
+                config.wide_record_keepalive_polls.unwrap_or(60),
+            );
+        if let (true, Some(records_path)) = (write_record, &config.storage.records_path) {
+            append_jsonl(
+                records_path,
+                metrics::wide_record(
+                    clock.now(),
+                    config.device_name.as_deref(),
+                    &extracted,
+                    state,
+                ),
+            )?;
+        }
+    }
+
+    //live feed for socket clients; slow or gone clients never hold up the poll
+    if let Some(hub) = &sinks.stream {
+        hub.publish(&metrics::wide_record(
+            clock.now(),
+            config.device_name.as_deref(),
+            &extracted,
+            state,
+        ));
+    }
+
+    //push to a prometheus remote-write endpoint; failures become events so polling carries on
+    if let Some(rw) = &config.remote_write {
+        let request = remote_write::build_write_request(
+            clock.now(),
+            config.device_name.as_deref(),
+            &extracted,
+            state,
+        );
+        let pushed = match remote_write::encode_body(&request) {
+            Ok(body) => remote_write::push(&sinks.client, rw, body).await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = pushed {
+            append_event_jsonl(
+                events,
+                with_severity(serde_json::json!({
+                    "ts": clock.now(),
+                    "event": "remote_write_error",
+                    "error": err.to_string()
+                })),
+            )?;
+        }
+    }
+
+    //one influxdb point per poll; failures become events so polling carries on
+    if let Some(influx_cfg) = &config.influx {
+        if let Some(line) =
+            influx::to_line_protocol(clock.now(), config.device_name.as_deref(), &extracted)
+        {
+            if let Err(err) = influx::write(&sinks.client, influx_cfg, line).await {
+                append_event_jsonl(
+                    events,
+                    with_severity(serde_json::json!({
+                        "ts": clock.now(),
+                        "event": "influx_error",
+                        "error": err.to_string()
+                    })),
+                )?;
+            }
+        }
+    }
+
+    //fire-and-forget; send errors never reach the events file
+    if let Some(sink) = &sinks.statsd {
+        sink.send(&extracted).await;
+    }
+
+    Ok(had_event)
+}
+
+//write the state unless storage.state_save_interval_secs says the last save is recent enough
+fn save_state_debounced(
+    config: &AppConfig,
+    state: &mut MonitorState,
+    force: bool,
+    clock: &dyn Clock,
+) -> Result<()> {
+    let now = clock.now();
+    if !state_save_due(
+        state.last_saved,
+        now,
+        config.storage.state_save_interval_secs,
+        force,
+    ) {
+        return Ok(());
+    }
+    save_state_to(
+        &resolve_state_path(&config.storage.state_path, clock.now()),
+        state,
+        &config.storage,
+    )?;
+    state.last_saved = Some(now);
+    Ok(())
+}
+
+fn print_histograms(config: &AppConfig, only: Option<&str>) -> Result<()> {
+    let now = Utc::now();
+    let state_path = resolve_state_path(&config.storage.state_path, now);
+    let Ok(state) = load_state_for(&config.storage.state_path, now, &config.storage) else {
+        println!("no saved state at {}", state_path);
+        return Ok(());
+    };
+    let mut printed = false;
+    for (metric, hist) in &state.histograms {
+        if only.is_some_and(|m| m != metric) {
+            continue;
+        }
+        println!("{} ({} samples)", metric, hist.count());
+        print!("{}", hist.render_ascii(40));
+        printed = true;
+    }
+    if !printed {
+        println!(
+            "no histogram data in {}; set bucket edges under histograms in the config",
+            state_path
+        );
+    }
+    Ok(())
+}
 
+fn reset_state(config: &AppConfig, hashrate: bool, efficiency: bool, all: bool) -> Result<()> {
+    if !(hashrate || efficiency || all) {
+        anyhow::bail!("nothing selected: pass --hashrate, --efficiency and/or --all");
+    }
+    let now = Utc::now();
+    let state_path = resolve_state_path(&config.storage.state_path, now);
+    let Ok(mut state) = load_state_for(&config.storage.state_path, now, &config.storage) else {
+        println!("no saved state at {}; nothing to reset", state_path);
+        return Ok(());
+    };
+    let cleared = state.reset(hashrate, efficiency, all);
+    if cleared.is_empty() {
+        println!("selected bests were already empty in {}", state_path);
+        return Ok(());
+    }
+    let events = EventLog::new(
+        &config.storage,
+        config.tag_events_with_build.unwrap_or(false),
+        Arc::new(SystemClock),
+    );
+    append_event_jsonl(
+        &events,
+        with_severity(serde_json::json!({"ts": now, "event": "state_reset", "cleared": cleared})),
+    )?;
+    save_state_to(&state_path, &state, &config.storage)?;
+    if all {
+        println!("cleared all saved state in {}", state_path);
+    } else {
+        println!("cleared in {}: {}", state_path, cleared.join(", "));
+    }
     Ok(())
 }
 
-//make a few attempts with exponential backoff to get a response so short network glitches do not surface as errors
-async fn fetch_text_with_retries(
+//false while probe_until_ready is still waiting for the config to work; the probe is a side-effect
+//free preflight, and the first success opens the event gate and writes service_start + monitor_ready
+async fn probe_ready(
     client: &Client,
     config: &AppConfig,
-    max_retries: usize,
-    base_delay: Duration,
-) -> Result<String> {
-    let mut attempt: usize = 0;
-    loop {
-        //rebuild request each attempt because RequestBuilder is single-use
-        let req = client.get(&config.http.endpoint_url);
-
-        let send_result = req.send().await;
-        match send_result {
-            Ok(resp) => match resp.error_for_status() {
-                Ok(ok_resp) => match ok_resp.text().await {
-                    Ok(body) => return Ok(body),
-                    Err(err) => {
-                        if attempt < max_retries {
-                            let factor = 1u64 << attempt;
-                            let delay_ms = (base_delay.as_millis() as u64).saturating_mul(factor);
-                            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
-                            attempt += 1;
-                            continue;
-                        } else {
-                            return Err(anyhow::anyhow!(err));
-                        }
-                    }
-                },
-                Err(err) => {
-                    if attempt < max_retries {
-                        let factor = 1u64 << attempt;
-                        let delay_ms = (base_delay.as_millis() as u64).saturating_mul(factor);
-                        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
-                        attempt += 1;
-                        continue;
-                    } else {
-                        return Err(anyhow::anyhow!(err));
-                    }
+    events: &EventLog,
+    probe: &mut Option<persist::ReadinessGate>,
+    clock: &dyn Clock,
+) -> bool {
+    let Some(gate) = probe.as_mut() else {
+        return true;
+    };
+    match preflight_check(client, config).await {
+        Ok(_) => {
+            let start = service_start_event(
+                clock.now(),
+                &config.http.endpoint_url,
+                config.poll_interval_secs,
+            );
+            if let Some(gate) = probe.take() {
+                if let Err(err) = gate.ready(events, clock.now(), start) {
+                    eprintln!("[bitaxe_monitor] WARN: failed to write monitor_ready: {err}");
                 }
-            },
-            Err(err) => {
-                if attempt < max_retries {
-                    let factor = 1u64 << attempt;
-                    let delay_ms = (base_delay.as_millis() as u64).saturating_mul(factor);
-                    tokio::time::sleep(Duration::from_millis(delay_ms)).await;
-                    attempt += 1;
-                    continue;
-                } else {
-                    return Err(anyhow::anyhow!(err));
+            }
+            println!("[bitaxe_monitor] first successful poll; output enabled");
+            true
+        }
+        Err(err) => {
+            gate.failed();
+            eprintln!("[bitaxe_monitor] WARN: still probing: {err:#}");
+            false
+        }
+    }
+}
+
+//a failed poll is a poll_error, except right after a reboot where the first failure becomes a
+//single rebooting event and the rest are suppressed until the device answers again
+fn record_poll_error(
+    config: &AppConfig,
+    events: &EventLog,
+    state: &mut MonitorState,
+    err: &anyhow::Error,
+    clock: &dyn Clock,
+) {
+    let now = clock.now();
+    if let Some(digest) = state.digest.as_mut() {
+        digest.errors += 1;
+    }
+    //the circuit counts every failed poll, including those inside the reboot grace window
+    if let Some(cb) = &config.circuit_breaker {
+        if state.circuit.on_failure(now, cb.failure_threshold) {
+            let _ = append_event_jsonl(
+                events,
+                with_severity(serde_json::json!({
+                    "ts": now,
+                    "event": "circuit_open",
+                    "failures": state.circuit.failures,
+                    "error": err.to_string()
+                })),
+            );
+        }
+    }
+    let event = match state.reboot_grace.on_failure(now) {
+        Some(false) => return,
+        Some(true) => with_severity(serde_json::json!({
+            "ts": now,
+            "event": "rebooting",
+            "grace_secs": config.post_reboot_grace_secs,
+            "error": err.to_string()
+        })),
+        None => {
+            //with failure_backoff, identical errors in a row are logged once and counted
+            if config.failure_backoff.is_some() {
+                let (log, ended) = state.repeated_errors.on_error(now, &err.to_string());
+                if let Some(run) = ended {
+                    record_error_run(events, run, clock);
+                }
+                if !log {
+                    return;
                 }
             }
+            with_severity(serde_json::json!({
+                "ts": now,
+                "event": "poll_error",
+                "error": err.to_string()
+            }))
+        }
+    };
+    let _ = append_event_jsonl(events, event);
+}
+
+//a successful poll ends the failure backoff, any run of repeated errors and an open circuit
+fn record_poll_success(
+    events: &EventLog,
+    state: &mut MonitorState,
+    backoff: Option<&mut FailureBackoff>,
+    clock: &dyn Clock,
+) {
+    if let Some(b) = backoff {
+        b.record(true);
+    }
+    if let Some(run) = state.repeated_errors.on_success() {
+        record_error_run(events, run, clock);
+    }
+    if let Some((since, failures)) = state.circuit.on_success() {
+        let now = clock.now();
+        let _ = append_event_jsonl(
+            events,
+            with_severity(serde_json::json!({
+                "ts": now,
+                "event": "circuit_closed",
+                "open_since": since,
+                "open_secs": (now - since).num_seconds(),
+                "failed_polls": failures
+            })),
+        );
+    }
+}
+
+//one line for a run of identical poll errors: the error, how many polls failed with it, and since when
+fn record_error_run(
+    events: &EventLog,
+    (error, since, count): metrics::ErrorRun,
+    clock: &dyn Clock,
+) {
+    let _ = append_event_jsonl(
+        events,
+        with_severity(serde_json::json!({
+            "ts": clock.now(),
+            "event": "poll_error_repeated",
+            "error": error,
+            "count": count,
+            "since": since
+        })),
+    );
+}
+
+//one endpoint's polling state: the client (rebuilt when a pinned address moves), the adaptive
+//schedule and the failure backoff
+struct Poller {
+    client: Client,
+    pinned: Option<PinnedAddr>,
+    schedule: AdaptiveInterval,
+    backoff: Option<FailureBackoff>,
+}
+
+impl Poller {
+    fn new(config: &AppConfig, client: Client, pinned: Option<PinnedAddr>) -> Self {
+        let (schedule, backoff) = schedule_for(config);
+        Self {
+            client,
+            pinned,
+            schedule,
+            backoff,
+        }
+    }
+
+    fn reset_schedule(&mut self, config: &AppConfig) {
+        (self.schedule, self.backoff) = schedule_for(config);
+    }
+
+    //delay before the next poll: the (adaptive) schedule, widened while polls keep failing and at
+    //least circuit_breaker.open_interval_secs while the circuit is open
+    fn next_delay(&self, config: &AppConfig, state: &MonitorState) -> Duration {
+        let delay = match &self.backoff {
+            Some(b) => b.delay(self.schedule.current()),
+            None => self.schedule.current(),
+        };
+        match &config.circuit_breaker {
+            Some(cb) if state.circuit.is_open() => {
+                delay.max(Duration::from_secs(cb.open_interval_secs.unwrap_or(300)))
+            }
+            _ => delay,
         }
     }
 }
 
-//fetch once and try extracting metrics so configuration problems are caught immediately
-async fn preflight_check(client: &Client, config: &AppConfig) -> Result<()> {
-    let text = fetch_text_with_retries(client, config, 2, Duration::from_millis(300)).await?;
-    let json: Value = serde_json::from_str(&text)
-        .with_context(|| "endpoint did not return valid json during preflight")?;
-    let _ = extract_metrics_from_json(&json, &config.pointers).with_context(|| {
-        "failed extracting metrics during preflight using configured json pointers"
-    })?;
-    Ok(())
+//the delay before each poll is recomputed so adaptive mode can stretch it while the device is
+//stable; consecutive failed polls stretch it further when failure_backoff is configured
+fn schedule_for(config: &AppConfig) -> (AdaptiveInterval, Option<FailureBackoff>) {
+    let schedule = AdaptiveInterval::new(
+        Duration::from_secs(config.poll_interval_secs),
+        config.adaptive_interval.as_ref(),
+        Duration::from_secs(config::min_poll_interval_secs(config)),
+    );
+    (
+        schedule,
+        config.failure_backoff.as_ref().map(FailureBackoff::new),
+    )
 }
 
-fn handle_detection_outcome(
-    path: &str,
-    state: &MonitorState,
-    outcome: DetectionOutcome,
-) -> Result<()> {
-    //write structured events based on detected changes so the events log shows reboots and new records in order
-    let now = Utc::now();
+//everything that follows a poll, whether it worked or not: the exporter snapshot, the circuit
+//breaker and failure backoff, re-pinning after a failure and the adaptive schedule
+async fn handle_poll_result(
+    result: Result<bool>,
+    config: &AppConfig,
+    events: &EventLog,
+    state: &mut MonitorState,
+    sinks: &mut PollSinks,
+    poller: &mut Poller,
+    clock: &dyn Clock,
+) {
+    sinks.sample_self(config, state);
+    let had_event = match result {
+        Ok(had_event) => {
+            sinks.publish(state, Some(clock.now()));
+            record_poll_success(events, state, poller.backoff.as_mut(), clock);
+            had_event
+        }
+        Err(err) => {
+            //log errors to events file so failures are visible later
+            record_poll_error(config, events, state, &err, clock);
+            sinks.publish(state, None);
+            if let Some(b) = poller.backoff.as_mut() {
+                b.record(false);
+            }
+            if let Some(pin) = poller.pinned.as_mut() {
+                repin_endpoint(config, events, pin, &mut poller.client, clock).await;
+            }
+            true
+        }
+    };
+    poller.schedule.record(had_event);
+    sinks.report_otlp_errors(events, clock);
+}
 
-    //record a boot event when a fresh start is observed so timelines show when the device restarted
-    if outcome.boot_detected {
-        append_event_jsonl(
-            path,
-            serde_json::json!({
-                "ts": now,
-                "event": "boot_detected",
-                "state": state
-            }),
-        )?;
+//after a poll where every attempt failed, resolve the host again and rebuild the client if it moved
+async fn repin_endpoint(
+    config: &AppConfig,
+    events: &EventLog,
+    pin: &mut PinnedAddr,
+    client: &mut Client,
+    clock: &dyn Clock,
+) {
+    let Ok(Some(fresh)) = resolve_pin(&config.http.endpoint_url).await else {
+        return;
+    };
+    let changed = fresh.addr != pin.addr;
+    if changed {
+        match build_client(&config.http, Some(&fresh)) {
+            Ok(c) => *client = c,
+            Err(_) => return,
+        }
     }
+    let _ = append_event_jsonl(
+        events,
+        with_severity(serde_json::json!({
+            "ts": clock.now(),
+            "event": "dns_repinned",
+            "host": fresh.host,
+            "previous_addr": pin.addr.to_string(),
+            "addr": fresh.addr.to_string(),
+            "changed": changed
+        })),
+    );
+    *pin = fresh;
+}
 
-    //record a session best when the current boot produces a new top value so each run keeps its own high-water mark
-    if let Some(v) = outcome.new_device_boot_best {
+//keep the raw body every storage.snapshot_interval_secs (before parsing, so bad json is kept too)
+fn record_payload_snapshot(
+    config: &AppConfig,
+    events: &EventLog,
+    state: &mut MonitorState,
+    text: &str,
+    clock: &dyn Clock,
+) -> Result<()> {
+    let (Some(interval), Some(dir)) = (
+        config.storage.snapshot_interval_secs,
+        config.storage.snapshot_dir.as_deref(),
+    ) else {
+        return Ok(());
+    };
+    let now = clock.now();
+    let due = state
+        .last_snapshot_at
+        .is_none_or(|last| (now - last).num_seconds() >= interval as i64);
+    if !due {
+        return Ok(());
+    }
+    state.last_snapshot_at = Some(now);
+    let max = config.storage.max_snapshots.unwrap_or(100);
+    if let Err(err) = write_payload_snapshot(dir, text, now, max) {
         append_event_jsonl(
-            path,
-            serde_json::json!({
+            events,
+            with_severity(serde_json::json!({
                 "ts": now,
-                "event": "new_device_boot_best",
-                "value": v
-            }),
+                "event": "snapshot_error",
+                "dir": dir,
+                "error": err.to_string()
+            })),
         )?;
     }
+    Ok(())
+}
 
-    //record a lifetime best for this device when a new all-time high appears so progress across many runs is captured
-    if let Some(v) = outcome.new_device_all_time_best {
-        append_event_jsonl(
-            path,
-            serde_json::json!({
-                "ts": now,
-                "event": "new_device_all_time_best",
-                "value": v
-            }),
-        )?;
+//track fetch duration jitter and emit high_jitter once each time it rises above the threshold
+fn record_fetch_latency(
+    config: &AppConfig,
+    events: &EventLog,
+    state: &mut MonitorState,
+    elapsed: Duration,
+    clock: &dyn Clock,
+) -> Result<()> {
+    let thresholds = config.thresholds.as_ref();
+    let window = thresholds.and_then(|t| t.jitter_window).unwrap_or(20);
+    state.latency.push(elapsed.as_secs_f64() * 1000.0, window);
+    state.last_jitter_ms = state.latency.jitter_ms();
+
+    let (Some(limit), Some(jitter)) = (
+        thresholds.and_then(|t| t.jitter_threshold_ms),
+        state.last_jitter_ms,
+    ) else {
+        return Ok(());
+    };
+    //wait for a full window so a couple of early samples cannot trigger the alert
+    if state.latency.sample_count() < window {
+        return Ok(());
+    }
+    if jitter > limit {
+        if !state.latency.high_jitter_active {
+            state.latency.high_jitter_active = true;
+            append_event_jsonl(
+                events,
+                with_severity(serde_json::json!({
+                    "ts": clock.now(),
+                    "event": "high_jitter",
+                    "jitter_ms": jitter,
+                    "mean_latency_ms": state.latency.mean_ms(),
+                    "threshold_ms": limit,
+                    "window": window
+                })),
+            )?;
+        }
+    } else {
+        state.latency.high_jitter_active = false;
     }
+    Ok(())
+}
 
-    //record the best value this tool has ever seen so the monitor can celebrate its own highest reading
-    if let Some(v) = outcome.new_tool_all_time_best {
+//track the efficiency slope and emit efficiency_drift once each time it rises above the threshold
+fn record_efficiency_trend(
+    config: &AppConfig,
+    events: &EventLog,
+    state: &mut MonitorState,
+    efficiency: Option<f64>,
+    clock: &dyn Clock,
+) -> Result<()> {
+    let Some(eff) = efficiency.filter(|v| v.is_finite()) else {
+        return Ok(());
+    };
+    let thresholds = config.thresholds.as_ref();
+    let window = thresholds
+        .and_then(|t| t.efficiency_trend_window)
+        .unwrap_or(30);
+    state
+        .efficiency_trend
+        .push(clock.now().timestamp(), eff, window);
+    state.last_efficiency_slope_per_hour = state.efficiency_trend.slope_per_hour();
+
+    let Some(limit) = thresholds.and_then(|t| t.efficiency_drift_per_hour) else {
+        return Ok(());
+    };
+    if let Some(slope) = state.efficiency_trend.drift_crossed(limit, window) {
         append_event_jsonl(
-            path,
-            serde_json::json!({
-                "ts": now,
-                "event": "new_tool_all_time_best",
-                "value": v
-            }),
+            events,
+            with_severity(serde_json::json!({
+                "ts": clock.now(),
+                "event": "efficiency_drift",
+                "slope_j_per_th_per_hour": slope,
+                "threshold_per_hour": limit,
+                "window": window,
+                "efficiency_j_per_th": eff
+            })),
         )?;
     }
+    Ok(())
+}
 
-    // record new best hashrate (TH/s) when present
-    if let Some(v) = outcome.new_tool_best_hashrate_ths {
-        append_event_jsonl(
-            path,
-            serde_json::json!({
-                "ts": now,
-                "event": "new_tool_best_hashrate_ths",
-                "value": v
-            }),
-        )?;
+//fetch once and try extracting metrics so configuration problems are caught immediately
+//returns the response and extracted metrics so callers can report what was resolved
+//must stay free of persistence (no events, no state) since --check-config relies on that
+async fn preflight_check(client: &Client, config: &AppConfig) -> Result<(Value, ExtractedMetrics)> {
+    let policy =
+        RetryPolicy::from_config(config.http.retry.as_ref(), 2, Duration::from_millis(300));
+    let text = fetch_text_with_retries(client, &config.http, policy).await?;
+    let json: Value = serde_json::from_str(&text)
+        .with_context(|| "endpoint did not return valid json during preflight")?;
+    //declared pointer types are checked before the lenient extraction coerces anything
+    metrics::check_pointer_types(&json, &config.pointers)?;
+    let tracked = metrics::TrackedMetrics::from_config(config.metrics.as_ref());
+    let metrics =
+        metrics::extract_tracked_metrics(&json, &config.pointers, tracked).with_context(|| {
+            "failed extracting metrics during preflight using configured json pointers"
+        })?;
+    //with efficiency, power and hashrate all configured, they should agree
+    let tolerance = config
+        .thresholds
+        .as_ref()
+        .and_then(|t| t.efficiency_consistency_tolerance_pct)
+        .unwrap_or(5.0);
+    if let Some(msg) = metrics::efficiency_consistency_warning(&metrics, tolerance) {
+        eprintln!("[bitaxe_monitor] WARN: {}", msg);
     }
+    Ok((json, metrics))
+}
 
-    // record new best efficiency (lowest J/TH) when present
-    if let Some(v) = outcome.new_tool_best_efficiency_j_per_th {
-        append_event_jsonl(
-            path,
-            serde_json::json!({
-                "ts": now,
-                "event": "new_tool_best_efficiency_j_per_th",
-                "value": v
-            }),
-        )?;
+//post subscribed events to every configured channel; a failed post is logged as notify_error and
+//polling carries on
+async fn notify_events(
+    client: &Client,
+    config: &AppConfig,
+    events: &EventLog,
+    queue: Option<&notify::NotifyQueue>,
+    emitted: &[Value],
+    clock: &dyn Clock,
+) -> Result<()> {
+    for notifier in notify::configured(config.notifications.as_ref())
+        .into_iter()
+        .filter(|n| n.mode() == NotifyMode::Realtime)
+    {
+        for event in emitted.iter().filter(|e| notifier.subscribed(e)) {
+            let content = notifier.render(config.device_name.as_deref(), event);
+            if let Some(queue) = queue {
+                queue.push(notify::QueuedMessage {
+                    notifier: notifier.clone(),
+                    notified_event: event
+                        .get("event")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    content,
+                });
+                continue;
+            }
+            if let Err(err) = notifier.send(client, &content).await {
+                append_event_jsonl(
+                    events,
+                    with_severity(serde_json::json!({
+                        "ts": clock.now(),
+                        "event": "notify_error",
+                        "target": notifier.name(),
+                        "notified_event": event.get("event"),
+                        "error": err.to_string()
+                    })),
+                )?;
+            }
+        }
     }
+    Ok(())
+}
 
+//with a channel in digest mode, keep the day's summary in the state and post it once the day is
+//over; without one, drop whatever summary an earlier config left behind
+async fn post_daily_digest(
+    client: &Client,
+    config: &AppConfig,
+    events: &EventLog,
+    state: &mut MonitorState,
+    clock: &dyn Clock,
+) -> Result<()> {
+    let digest_mode = notify::configured(config.notifications.as_ref())
+        .iter()
+        .any(|n| n.mode() == NotifyMode::Digest);
+    if !digest_mode {
+        state.digest = None;
+        return Ok(());
+    }
+    let digest = state.digest.get_or_insert_with(Default::default);
+    let sent = notify::post_daily_digest(
+        client,
+        config.notifications.as_ref(),
+        config.device_name.as_deref(),
+        digest,
+        clock.now(),
+    )
+    .await;
+    for (target, result) in sent {
+        if let Err(err) = result {
+            append_event_jsonl(
+                events,
+                with_severity(serde_json::json!({
+                    "ts": clock.now(),
+                    "event": "notify_error",
+                    "target": target,
+                    "notified_event": "daily_digest",
+                    "error": err.to_string()
+                })),
+            )?;
+        }
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+
+    #[tokio::test]
+    async fn test_stale_samples_let_the_adaptive_interval_grow() {
+        use axum::{routing::get, Json, Router};
+
+        // a device behind a cache: the same sample, 10000 s old, on every poll
+        let app = Router::new().route(
+            "/info",
+            get(|| async {
+                Json(serde_json::json!({
+                    "best": 10, "session": 4, "uptime": 600, "ts": 1_699_990_000u64
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let events_path = dir.path().join("events.jsonl");
+        let config_path = dir.path().join("config.json");
+        let config = serde_json::json!({
+            "http": { "endpoint_url": format!("http://{}/info", addr) },
+            "pointers": {
+                "json_pointer_all_time": "/best",
+                "json_pointer_boot_best": "/session",
+                "json_pointer_uptime_secs": "/uptime",
+                "json_pointer_sample_time": "/ts"
+            },
+            "poll_interval_secs": 10,
+            "adaptive_interval": { "max_interval_secs": 80, "stable_polls": 2 },
+            "storage": {
+                "events_path": events_path.to_string_lossy(),
+                "state_path": dir.path().join("state.json").to_string_lossy()
+            },
+            "thresholds": {
+                "epsilon_hashrate_ths": 0.01,
+                "epsilon_efficiency_j_per_th": 0.01,
+                "max_sample_age_secs": 60
+            }
+        });
+        std::fs::write(&config_path, config.to_string()).unwrap();
+        let config = config::load_config(&[&config_path]).unwrap();
+
+        let clock = Arc::new(FixedClock::new(
+            chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+        ));
+        let events = EventLog::new(&config.storage, false, clock.clone());
+        let mut state = MonitorState::new();
+        let mut sinks = PollSinks::default();
+        let mut poller = Poller::new(&config, Client::new(), None);
+        for _ in 0..4 {
+            let result = poll_once(
+                &poller.client,
+                &config,
+                &events,
+                &mut state,
+                &mut sinks,
+                clock.as_ref(),
+            )
+            .await;
+            assert!(!result.as_ref().unwrap());
+            handle_poll_result(
+                result,
+                &config,
+                &events,
+                &mut state,
+                &mut sinks,
+                &mut poller,
+                clock.as_ref(),
+            )
+            .await;
+            clock.advance(chrono::Duration::seconds(10));
+        }
+        // four quiet polls with stable_polls = 2 double the interval twice
+        assert_eq!(poller.schedule.current(), Duration::from_secs(40));
+        // every stale sample is still logged, and none counted toward the bests
+        let log = std::fs::read_to_string(&events_path).unwrap();
+        assert_eq!(log.matches("\"stale_sample\"").count(), 4);
+        assert_eq!(state.tool_global_all_time_best, 0.0);
+    }
+}
@@ -1,6 +1,12 @@
-use crate::config::JsonPointers;
+use crate::config::{
+    DecimalSeparator, EventSeverity, JsonPointers, MetricsConfig, PointerSpec, SelectorKind,
+    ThresholdsConfig, ValueKind,
+};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, VecDeque};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
@@ -13,34 +19,726 @@ pub struct MonitorState {
     // track tool-best hashrate (max TH/s) and efficiency (min J/TH)
     pub tool_best_hashrate_ths: Option<f64>,
     pub tool_best_efficiency_j_per_th: Option<f64>,
+    // when each tool_* best, worst or extreme was last set; absent in state files from older versions
+    pub tool_global_all_time_best_at: Option<DateTime<Utc>>,
+    pub tool_best_hashrate_ths_at: Option<DateTime<Utc>>,
+    pub tool_best_efficiency_j_per_th_at: Option<DateTime<Utc>>,
+    pub tool_worst_hashrate_ths_at: Option<DateTime<Utc>>,
+    pub tool_worst_efficiency_j_per_th_at: Option<DateTime<Utc>>,
+    pub tool_best_difficulty_at: Option<DateTime<Utc>>,
+    pub tool_max_temp_c_at: Option<DateTime<Utc>>,
+    pub tool_min_power_w_at: Option<DateTime<Utc>>,
+    pub tool_max_power_w_at: Option<DateTime<Utc>>,
+    // track worst steady-state hashrate (min TH/s) and efficiency (max J/TH) for degradation checks
+    pub tool_worst_hashrate_ths: Option<f64>,
+    pub tool_worst_efficiency_j_per_th: Option<f64>,
+    // highest best difficulty seen, with the device's own formatting for display
+    pub tool_best_difficulty: Option<f64>,
+    pub tool_best_difficulty_display: Option<String>,
+    // highest session (since boot) difficulty seen this boot, with its display string; cleared on
+    // boot_detected
+    pub session_best_difficulty: Option<f64>,
+    pub session_best_difficulty_display: Option<String>,
+    // highest chip temperature (°C) observed
+    pub tool_max_temp_c: Option<f64>,
+    // lowest and highest power draw (W) observed
+    pub tool_min_power_w: Option<f64>,
+    pub tool_max_power_w: Option<f64>,
+    // true while temp_c is above temp_alert_c so temp_alert fires once per excursion; persisted so a
+    // restart while hot does not alert again
+    pub temp_alert_active: bool,
+    // whether each configured optional pointer (by field name) resolved on the latest poll; only
+    // kept with metric_availability_events
+    pub metric_availability: BTreeMap<String, bool>,
+    // share counters from the previous poll, the baseline for the per-poll reject ratio
+    pub last_shares_accepted: Option<u64>,
+    pub last_shares_rejected: Option<u64>,
+    // device-reported sample timestamp (epoch seconds) from the most recent poll
+    pub last_sample_time: Option<u64>,
+    // standard deviation of recent fetch durations (ms); persisted so --summary can report it
+    pub last_jitter_ms: Option<f64>,
+    // least-squares slope of efficiency over recent samples (J/TH per hour; positive means getting worse)
+    pub last_efficiency_slope_per_hour: Option<f64>,
+    // sha-256 of the config this state was last saved under; see config::config_hash
+    pub config_hash: Option<String>,
+    // last rolling_window hashrate samples (TH/s); persisted so the average survives restarts
+    pub recent_hashrate_ths: VecDeque<f64>,
+    // last efficiency_baseline_window efficiency samples (J/TH) since boot, whose median is the norm
+    // efficiency_degraded compares against; only kept with efficiency_degraded_pct
+    pub efficiency_baseline: VecDeque<f64>,
+    // true while efficiency is worse than the baseline by more than efficiency_degraded_pct, so the
+    // event fires once per excursion
+    pub efficiency_degraded_active: bool,
+    // consecutive polls with hashrate below min_hashrate_ths, and whether hashrate_stalled has fired
+    // for the current run; persisted so a restart mid-stall does not alert again
+    pub hashrate_below_min_polls: u32,
+    pub hashrate_stalled: bool,
+    // value distributions keyed by metric ("hashrate_ths", "efficiency_j_per_th"); only kept for
+    // metrics with bucket edges under histograms in the config
+    pub histograms: BTreeMap<String, Histogram>,
+    // consecutive failed polls and, once circuit_breaker.failure_threshold is reached, when the circuit
+    // opened; persisted so --summary shows an outage still going on at shutdown
+    pub circuit: CircuitBreaker,
+    // the current UTC day's tally for notification channels in digest mode; None without one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<DailyDigest>,
+    // with notifications.dedupe, the last value announced per record event, so a restart does not
+    // announce the same record again
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub last_notified: BTreeMap<String, LastNotified>,
+    // in-memory window of fetch durations; rebuilt after restart rather than persisted
+    #[serde(skip)]
+    pub latency: LatencyWindow,
+    // last wide record written, for wide_record_on_change_only; not persisted
+    #[serde(skip)]
+    pub wide_record_gate: WideRecordGate,
+    // in-memory (timestamp, efficiency) samples for the drift slope; not persisted
+    #[serde(skip)]
+    pub efficiency_trend: EfficiencyTrend,
+    // window after boot_detected in which failed polls are expected; not persisted
+    #[serde(skip)]
+    pub reboot_grace: RebootGrace,
+    // recent reboot times for boot_flapping detection; not persisted
+    #[serde(skip)]
+    pub boot_flapping: BootFlapping,
+    // current run of identical poll errors, with failure_backoff; not persisted
+    #[serde(skip)]
+    pub repeated_errors: RepeatedErrors,
+    // when the last raw payload snapshot was written; not persisted, so a restart takes one right away
+    #[serde(skip)]
+    pub last_snapshot_at: Option<DateTime<Utc>>,
+    // when the state file was last written, for storage.state_save_interval_secs; not persisted
+    #[serde(skip)]
+    pub last_saved: Option<DateTime<Utc>>,
+    // live values from the latest successful poll, reported by heartbeat events; not persisted
+    #[serde(skip)]
+    pub last_hashrate_ths: Option<f64>,
+    #[serde(skip)]
+    pub last_efficiency_j_per_th: Option<f64>,
+    #[serde(skip)]
+    pub last_voltage_mv: Option<f64>,
+    #[serde(skip)]
+    pub last_frequency_mhz: Option<f64>,
+    // the monitor's own resident memory and cpu use, with self_metrics; not persisted
+    #[serde(skip)]
+    pub process_rss_bytes: Option<u64>,
+    #[serde(skip)]
+    pub process_cpu_percent: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub _note: Option<String>,
 }
 
 impl MonitorState {
+    //all-time best starts at 0.0 so the first reading always counts; everything else starts empty
     pub fn new() -> Self {
         Self {
-            last_displayed_all_time: None,
-            last_displayed_boot_best: None,
-            last_uptime_secs: None,
-            last_boot_marker: None,
             tool_global_all_time_best: 0.0,
-            tool_best_hashrate_ths: None,
-            tool_best_efficiency_j_per_th: None,
-            _note: None,
+            ..Self::default()
+        }
+    }
+
+    //keep at most window samples; a smaller window after a config change trims the saved ones
+    pub fn push_hashrate_sample(&mut self, hashrate_ths: f64, window: usize) {
+        self.recent_hashrate_ths.push_back(hashrate_ths);
+        while self.recent_hashrate_ths.len() > window.max(1) {
+            self.recent_hashrate_ths.pop_front();
+        }
+    }
+
+    //clear the selected bests (e.g. after swapping hardware) and return the names of the fields that
+    //held a value; `all` starts over from an empty state, keeping only the config hash
+    pub fn reset(&mut self, hashrate: bool, efficiency: bool, all: bool) -> Vec<&'static str> {
+        let mut cleared = Vec::new();
+        if all {
+            let fresh = MonitorState {
+                config_hash: self.config_hash.take(),
+                ..MonitorState::new()
+            };
+            *self = fresh;
+            cleared.push("all");
+            return cleared;
+        }
+        let mut clear = |name: &'static str, field: &mut Option<f64>| {
+            if field.take().is_some() {
+                cleared.push(name);
+            }
+        };
+        if hashrate {
+            clear("tool_best_hashrate_ths", &mut self.tool_best_hashrate_ths);
+            clear("tool_worst_hashrate_ths", &mut self.tool_worst_hashrate_ths);
+            self.tool_best_hashrate_ths_at = None;
+            self.tool_worst_hashrate_ths_at = None;
+            self.recent_hashrate_ths.clear();
+            self.histograms.remove("hashrate_ths");
+        }
+        if efficiency {
+            clear(
+                "tool_best_efficiency_j_per_th",
+                &mut self.tool_best_efficiency_j_per_th,
+            );
+            clear(
+                "tool_worst_efficiency_j_per_th",
+                &mut self.tool_worst_efficiency_j_per_th,
+            );
+            self.tool_best_efficiency_j_per_th_at = None;
+            self.tool_worst_efficiency_j_per_th_at = None;
+            self.efficiency_baseline.clear();
+            self.efficiency_degraded_active = false;
+            self.histograms.remove("efficiency_j_per_th");
+        }
+        cleared
+    }
+
+    //count a value into the named histogram; changed bucket edges start the distribution over
+    pub fn observe_histogram(&mut self, metric: &str, edges: &[f64], value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        let hist = self
+            .histograms
+            .entry(metric.to_string())
+            .or_insert_with(|| Histogram::new(edges));
+        if hist.edges != edges {
+            *hist = Histogram::new(edges);
+        }
+        hist.observe(value);
+    }
+
+    pub fn rolling_avg_hashrate_ths(&self) -> Option<f64> {
+        if self.recent_hashrate_ths.is_empty() {
+            return None;
+        }
+        Some(self.recent_hashrate_ths.iter().sum::<f64>() / self.recent_hashrate_ths.len() as f64)
+    }
+}
+
+impl DetectionOutcome {
+    //true when this poll produced anything worth an event (used to reset adaptive polling)
+    pub fn has_events(&self) -> bool {
+        self.boot_detected
+            || self.new_device_all_time_best.is_some()
+            || self.new_device_boot_best.is_some()
+            || self.new_tool_all_time_best.is_some()
+            || self.new_tool_best_hashrate_ths.is_some()
+            || self.new_tool_best_efficiency_j_per_th.is_some()
+            || self.new_tool_worst_hashrate_ths.is_some()
+            || self.new_tool_worst_efficiency_j_per_th.is_some()
+            || self.new_best_difficulty.is_some()
+            || self.new_session_best_difficulty.is_some()
+            || self.new_tool_max_temp_c.is_some()
+            || self.new_tool_min_power_w.is_some()
+            || self.new_tool_max_power_w.is_some()
+            || self.temp_alert.is_some()
+            || self.high_reject_ratio.is_some()
+            || self.efficiency_degraded.is_some()
+            || self.hashrate_stalled.is_some()
+            || self.hashrate_recovered.is_some()
+            || self.boot_flapping.is_some()
+    }
+}
+
+//sliding window of fetch durations used to compute network jitter (stddev of latency)
+#[derive(Debug, Clone, Default)]
+pub struct LatencyWindow {
+    samples_ms: VecDeque<f64>,
+    // set while jitter is above threshold so high_jitter fires once per excursion
+    pub high_jitter_active: bool,
+}
+
+impl LatencyWindow {
+    pub fn push(&mut self, duration_ms: f64, window: usize) {
+        self.samples_ms.push_back(duration_ms);
+        while self.samples_ms.len() > window.max(2) {
+            self.samples_ms.pop_front();
+        }
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.samples_ms.len()
+    }
+
+    pub fn mean_ms(&self) -> Option<f64> {
+        if self.samples_ms.is_empty() {
+            return None;
+        }
+        Some(self.samples_ms.iter().sum::<f64>() / self.samples_ms.len() as f64)
+    }
+
+    //population standard deviation; needs at least two samples to mean anything
+    pub fn jitter_ms(&self) -> Option<f64> {
+        if self.samples_ms.len() < 2 {
+            return None;
+        }
+        let mean = self.mean_ms()?;
+        let var = self
+            .samples_ms
+            .iter()
+            .map(|v| (v - mean).powi(2))
+            .sum::<f64>()
+            / self.samples_ms.len() as f64;
+        Some(var.sqrt())
+    }
+}
+
+//ring buffer of (epoch seconds, J/TH) samples used to measure efficiency drift
+#[derive(Debug, Clone, Default)]
+pub struct EfficiencyTrend {
+    samples: VecDeque<(i64, f64)>,
+    // set while the slope is above the drift threshold so efficiency_drift fires once per excursion
+    pub drift_active: bool,
+}
+
+impl EfficiencyTrend {
+    pub fn push(&mut self, ts_secs: i64, efficiency: f64, window: usize) {
+        self.samples.push_back((ts_secs, efficiency));
+        while self.samples.len() > window.max(2) {
+            self.samples.pop_front();
+        }
+    }
+
+    //J/TH per hour; time is measured from the oldest sample to keep the regression well conditioned
+    pub fn slope_per_hour(&self) -> Option<f64> {
+        let (t0, _) = *self.samples.front()?;
+        let points: Vec<(f64, f64)> = self
+            .samples
+            .iter()
+            .map(|(t, e)| ((t - t0) as f64 / 3600.0, *e))
+            .collect();
+        least_squares_slope(&points)
+    }
+
+    //Some(slope) the first time a full window's slope exceeds limit; re-arms once it falls back
+    pub fn drift_crossed(&mut self, limit: f64, window: usize) -> Option<f64> {
+        //only judge a full window so a short burst right after startup is not reported as drift
+        if self.samples.len() < window {
+            return None;
+        }
+        let slope = self.slope_per_hour()?;
+        if slope <= limit {
+            self.drift_active = false;
+            return None;
+        }
+        if self.drift_active {
+            return None;
+        }
+        self.drift_active = true;
+        Some(slope)
+    }
+}
+
+//after a reboot the device is often unreachable for a moment; failures inside the grace window are
+//reported once as rebooting instead of as repeated poll_error events
+#[derive(Debug, Clone, Default)]
+pub struct RebootGrace {
+    until: Option<DateTime<Utc>>,
+    outage_started: Option<DateTime<Utc>>,
+}
+
+impl RebootGrace {
+    pub fn start(&mut self, now: DateTime<Utc>, grace_secs: u64) {
+        self.until = Some(now + chrono::Duration::seconds(grace_secs as i64));
+        self.outage_started = None;
+    }
+
+    pub fn active(&self, now: DateTime<Utc>) -> bool {
+        self.until.is_some_and(|until| now < until)
+    }
+
+    //None outside the window (report a normal poll_error); Some(true) for the first failure of the
+    //outage (emit rebooting); Some(false) for later failures, which are suppressed
+    pub fn on_failure(&mut self, now: DateTime<Utc>) -> Option<bool> {
+        if !self.active(now) {
+            return None;
+        }
+        if self.outage_started.is_some() {
+            return Some(false);
+        }
+        self.outage_started = Some(now);
+        Some(true)
+    }
+
+    //Some(downtime seconds) when the device answers again after a grace-window outage
+    pub fn on_success(&mut self, now: DateTime<Utc>) -> Option<f64> {
+        let started = self.outage_started.take()?;
+        self.until = None;
+        Some((now - started).num_milliseconds() as f64 / 1000.0)
+    }
+}
+
+//a run of failed polls with the same error; only the first is logged as poll_error and the rest are
+//counted, then summarized once the run ends
+#[derive(Debug, Clone, Default)]
+pub struct RepeatedErrors {
+    current: Option<(String, DateTime<Utc>, u64)>,
+}
+
+//(error, first seen, polls in the run) for a run that failed more than once
+pub type ErrorRun = (String, DateTime<Utc>, u64);
+
+impl RepeatedErrors {
+    //true when the error starts a new run and should be logged; a different error ends the
+    //previous run, which is returned when it repeated
+    pub fn on_error(&mut self, now: DateTime<Utc>, error: &str) -> (bool, Option<ErrorRun>) {
+        if let Some((msg, _, count)) = self.current.as_mut() {
+            if msg == error {
+                *count += 1;
+                return (false, None);
+            }
+        }
+        let ended = self.finish();
+        self.current = Some((error.to_string(), now, 1));
+        (true, ended)
+    }
+
+    pub fn on_success(&mut self) -> Option<ErrorRun> {
+        self.finish()
+    }
+
+    fn finish(&mut self) -> Option<ErrorRun> {
+        self.current.take().filter(|(_, _, count)| *count > 1)
+    }
+}
+
+//a record value sent to the notification channels, and when
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LastNotified {
+    pub value: f64,
+    pub ts: DateTime<Utc>,
+}
+
+//one UTC day of what a digest-mode notification channel reports; persisted so a restart keeps the
+//day's tally and a day that ended while the monitor was down is still sent
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyDigest {
+    pub day: Option<NaiveDate>,
+    pub best_hashrate_ths: Option<f64>,
+    pub best_efficiency_j_per_th: Option<f64>,
+    pub reboots: u32,
+    pub errors: u32,
+    // warn-level events written by polls (temp_alert, hashrate_stalled, ...) by name, with counts
+    pub alerts: BTreeMap<String, u32>,
+}
+
+impl DailyDigest {
+    //move on to the day `now` falls in; returns the previous day once it is over
+    pub fn roll(&mut self, now: DateTime<Utc>) -> Option<DailyDigest> {
+        let today = now.date_naive();
+        if self.day == Some(today) {
+            return None;
+        }
+        let fresh = DailyDigest {
+            day: Some(today),
+            ..Default::default()
+        };
+        let previous = std::mem::replace(self, fresh);
+        previous.day.map(|_| previous)
+    }
+
+    //one successful poll: its readings and the events it wrote
+    pub fn observe(
+        &mut self,
+        hashrate_ths: Option<f64>,
+        efficiency_j_per_th: Option<f64>,
+        emitted: &[Value],
+    ) {
+        if let Some(h) = hashrate_ths.filter(|v| v.is_finite()) {
+            self.best_hashrate_ths = Some(self.best_hashrate_ths.map_or(h, |b| b.max(h)));
+        }
+        if let Some(e) = efficiency_j_per_th.filter(|v| v.is_finite() && *v > 0.0) {
+            self.best_efficiency_j_per_th =
+                Some(self.best_efficiency_j_per_th.map_or(e, |b| b.min(e)));
+        }
+        for event in emitted {
+            let name = event.get("event").and_then(Value::as_str).unwrap_or("");
+            if name == "boot_detected" {
+                self.reboots += 1;
+            } else if crate::persist::event_severity(event) == EventSeverity::Warn {
+                *self.alerts.entry(name.to_string()).or_default() += 1;
+            }
+        }
+    }
+}
+
+//opens after a number of failed polls in a row and closes on the next success, so an outage shows up
+//as one circuit_open/circuit_closed pair instead of only scattered poll_error lines
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CircuitBreaker {
+    pub failures: u32,
+    pub open_since: Option<DateTime<Utc>>,
+}
+
+impl CircuitBreaker {
+    pub fn is_open(&self) -> bool {
+        self.open_since.is_some()
+    }
+
+    //true when this failure is the one that opens the circuit
+    pub fn on_failure(&mut self, now: DateTime<Utc>, threshold: u32) -> bool {
+        self.failures = self.failures.saturating_add(1);
+        if self.open_since.is_none() && self.failures >= threshold {
+            self.open_since = Some(now);
+            return true;
+        }
+        false
+    }
+
+    //(opened at, failed polls) when this success closes an open circuit
+    pub fn on_success(&mut self) -> Option<(DateTime<Utc>, u32)> {
+        let failures = std::mem::take(&mut self.failures);
+        self.open_since.take().map(|since| (since, failures))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlapChange {
+    // reboots inside the window went above the limit
+    Started { boots: usize },
+    // a full window passed without a reboot
+    Cleared,
+}
+
+//counts reboots in a sliding window so a restart loop is reported as one boot_flapping event, with
+//individual boot events held back until a whole window passes without a reboot
+#[derive(Debug, Clone, Default)]
+pub struct BootFlapping {
+    boots: VecDeque<DateTime<Utc>>,
+    active: bool,
+}
+
+impl BootFlapping {
+    pub fn active(&self) -> bool {
+        self.active
+    }
+
+    //call on every successful poll; boot is whether this poll detected a reboot
+    pub fn record(
+        &mut self,
+        now: DateTime<Utc>,
+        boot: bool,
+        window_secs: u64,
+        max_boots: u32,
+    ) -> Option<FlapChange> {
+        let cutoff = now - chrono::Duration::seconds(window_secs as i64);
+        while self.boots.front().is_some_and(|t| *t <= cutoff) {
+            self.boots.pop_front();
+        }
+        if boot {
+            self.boots.push_back(now);
+        }
+        if !self.active && self.boots.len() > max_boots as usize {
+            self.active = true;
+            return Some(FlapChange::Started {
+                boots: self.boots.len(),
+            });
+        }
+        if self.active && self.boots.is_empty() {
+            self.active = false;
+            return Some(FlapChange::Cleared);
+        }
+        None
+    }
+}
+
+//bucketed counts with prometheus semantics: a value lands in the first bucket whose upper edge is
+//>= the value, and anything above the last edge in the trailing +Inf bucket
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Histogram {
+    pub edges: Vec<f64>,
+    // one count per edge plus the +Inf bucket; not cumulative
+    pub counts: Vec<u64>,
+    pub sum: f64,
+}
+
+impl Histogram {
+    pub fn new(edges: &[f64]) -> Self {
+        Self {
+            edges: edges.to_vec(),
+            counts: vec![0; edges.len() + 1],
+            sum: 0.0,
+        }
+    }
+
+    pub fn observe(&mut self, value: f64) {
+        let idx = self
+            .edges
+            .iter()
+            .position(|edge| value <= *edge)
+            .unwrap_or(self.edges.len());
+        self.counts[idx] += 1;
+        self.sum += value;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    //running totals per bucket, as prometheus _bucket series expect
+    #[cfg(any(feature = "exporter", test))]
+    pub fn cumulative(&self) -> Vec<u64> {
+        self.counts
+            .iter()
+            .scan(0, |acc, c| {
+                *acc += c;
+                Some(*acc)
+            })
+            .collect()
+    }
+
+    //one bar per bucket scaled so the fullest bucket is `width` characters wide
+    pub fn render_ascii(&self, width: usize) -> String {
+        let mut labels: Vec<String> = self.edges.iter().map(|e| format!("<= {}", e)).collect();
+        labels.push(match self.edges.last() {
+            Some(last) => format!("> {}", last),
+            None => "all".to_string(),
+        });
+        let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+        let peak = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        let mut out = String::new();
+        for (label, count) in labels.iter().zip(&self.counts) {
+            let bar = (*count as usize * width).div_ceil(peak as usize);
+            out.push_str(&format!(
+                "{:<lw$} |{:<w$}| {}\n",
+                label,
+                "#".repeat(bar),
+                count,
+                lw = label_width,
+                w = width
+            ));
+        }
+        out
+    }
+}
+
+//ordinary least-squares slope of y over x; None with fewer than two points or no spread in x
+pub fn least_squares_slope(points: &[(f64, f64)]) -> Option<f64> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if sxx == 0.0 {
+        return None;
+    }
+    let sxy: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    Some(sxy / sxx)
+}
+
+//decides whether a wide record is worth writing when only changes (plus a keepalive) are wanted
+#[derive(Debug, Clone, Default)]
+pub struct WideRecordGate {
+    last_written: Option<ExtractedMetrics>,
+    polls_since_write: u32,
+}
+
+impl WideRecordGate {
+    //true when a tracked metric moved past its epsilon since the last written record, or when
+    //keepalive_polls polls have passed without a write; uptime alone never counts as a change
+    pub fn should_write(
+        &mut self,
+        m: &ExtractedMetrics,
+        thresholds: Thresholds,
+        keepalive_polls: u32,
+    ) -> bool {
+        self.polls_since_write += 1;
+        let changed = match &self.last_written {
+            None => true,
+            Some(prev) => {
+                fn moved(a: Option<f64>, b: Option<f64>, eps: f64) -> bool {
+                    match (a, b) {
+                        (Some(a), Some(b)) => (a - b).abs() > eps,
+                        (a, b) => a.is_some() != b.is_some(),
+                    }
+                }
+                prev.displayed_all_time != m.displayed_all_time
+                    || prev.displayed_boot_best != m.displayed_boot_best
+                    || prev.boot_id != m.boot_id
+                    || moved(
+                        prev.hashrate_ths,
+                        m.hashrate_ths,
+                        thresholds.epsilon_hashrate_ths,
+                    )
+                    || moved(
+                        prev.efficiency_j_per_th,
+                        m.efficiency_j_per_th,
+                        thresholds.epsilon_efficiency_j_per_th,
+                    )
+                    || moved(prev.best_difficulty, m.best_difficulty, 0.0)
+            }
+        };
+        if changed || self.polls_since_write >= keepalive_polls {
+            self.last_written = Some(m.clone());
+            self.polls_since_write = 0;
+            true
+        } else {
+            false
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ExtractedMetrics {
     pub displayed_all_time: f64,
     pub displayed_boot_best: f64,
     pub uptime_secs: Option<u64>,
     pub boot_id: Option<String>,
+    // optional device boot timestamp (epoch seconds); uptime is derived from it when not reported
+    pub boot_time: Option<u64>,
     // optional live metrics for hashrate (TH/s) and efficiency (J/TH)
     pub hashrate_ths: Option<f64>,
     pub efficiency_j_per_th: Option<f64>,
+    // optional device sample timestamp (epoch seconds) used to reject stale cached responses
+    pub sample_time: Option<u64>,
+    // optional best difficulty parsed for comparison, plus the original string for display
+    pub best_difficulty: Option<f64>,
+    pub best_difficulty_display: Option<String>,
+    // optional best difficulty since boot, parsed and as displayed
+    pub session_difficulty: Option<f64>,
+    pub session_difficulty_display: Option<String>,
+    // optional chip temperature (°C), after temp_scale / temp_offset_c
+    pub temp_c: Option<f64>,
+    // the reading before calibration; None unless temp_scale or temp_offset_c is set
+    pub temp_c_raw: Option<f64>,
+    // power draw (W) when json_pointer_power_w is configured
+    pub power_w: Option<f64>,
+    // optional core voltage (mV) and ASIC frequency (MHz) settings, for correlating overclocks
+    pub voltage_mv: Option<f64>,
+    pub frequency_mhz: Option<f64>,
+    // optional cumulative share counters; both reset when the device reboots
+    pub shares_accepted: Option<u64>,
+    pub shares_rejected: Option<u64>,
+}
+
+impl ExtractedMetrics {
+    //split one extraction into the two halves detect_changes takes
+    pub fn detection_inputs(&self) -> (Displayed, Metrics) {
+        (
+            Displayed {
+                all_time: self.displayed_all_time,
+                boot_best: self.displayed_boot_best,
+            },
+            Metrics {
+                uptime_secs: self.uptime_secs,
+                boot_id: self.boot_id.clone(),
+                hashrate_ths: self.hashrate_ths,
+                efficiency_j_per_th: self.efficiency_j_per_th,
+                best_difficulty: self.best_difficulty,
+                best_difficulty_display: self.best_difficulty_display.clone(),
+                session_difficulty: self.session_difficulty,
+                session_difficulty_display: self.session_difficulty_display.clone(),
+                temp_c: self.temp_c,
+                power_w: self.power_w,
+                shares_accepted: self.shares_accepted,
+                shares_rejected: self.shares_rejected,
+            },
+        )
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -52,6 +750,35 @@ pub struct DetectionOutcome {
     // records when monitor observes new maxima/minima for live stats
     pub new_tool_best_hashrate_ths: Option<f64>,
     pub new_tool_best_efficiency_j_per_th: Option<f64>,
+    // records new steady-state lows (hashrate) and highs (J/TH) outside the warmup window
+    pub new_tool_worst_hashrate_ths: Option<f64>,
+    pub new_tool_worst_efficiency_j_per_th: Option<f64>,
+    // new highest best difficulty (display string lives in state)
+    pub new_best_difficulty: Option<f64>,
+    // new highest session difficulty since the last reboot (display string lives in state)
+    pub new_session_best_difficulty: Option<f64>,
+    // new highest chip temperature (°C)
+    pub new_tool_max_temp_c: Option<f64>,
+    // new lowest / highest power draw (W)
+    pub new_tool_min_power_w: Option<f64>,
+    pub new_tool_max_power_w: Option<f64>,
+    // (observed °C, threshold °C) when temp_c crossed above thresholds.temp_alert_c this poll
+    pub temp_alert: Option<(f64, f64)>,
+    // this poll's uncalibrated temperature, written next to the calibrated value in temperature
+    // events; None without temp_scale / temp_offset_c
+    pub temp_c_raw: Option<f64>,
+    // (reject ratio, threshold) when the shares submitted since the last poll were rejected at a
+    // higher rate than thresholds.reject_ratio_alert
+    pub high_reject_ratio: Option<(f64, f64)>,
+    // (observed J/TH, baseline median J/TH) when efficiency fell more than
+    // thresholds.efficiency_degraded_pct behind the recent median this poll
+    pub efficiency_degraded: Option<(f64, f64)>,
+    // (observed TH/s, threshold TH/s) when hashrate has now been below thresholds.min_hashrate_ths for
+    // stall_polls polls in a row, and the reading that ended a stall
+    pub hashrate_stalled: Option<(f64, f64)>,
+    pub hashrate_recovered: Option<f64>,
+    // set by the caller from BootFlapping when boot_flapping is configured
+    pub boot_flapping: Option<FlapChange>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -60,12 +787,60 @@ pub struct Metrics {
     pub boot_id: Option<String>,
     pub hashrate_ths: Option<f64>,
     pub efficiency_j_per_th: Option<f64>,
+    pub best_difficulty: Option<f64>,
+    pub best_difficulty_display: Option<String>,
+    pub session_difficulty: Option<f64>,
+    pub session_difficulty_display: Option<String>,
+    pub temp_c: Option<f64>,
+    pub power_w: Option<f64>,
+    pub shares_accepted: Option<u64>,
+    pub shares_rejected: Option<u64>,
 }
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Thresholds {
     pub epsilon_hashrate_ths: f64,
     pub epsilon_efficiency_j_per_th: f64,
+    // seconds after boot during which samples are treated as ramp-up and skipped for worst tracking
+    pub warmup_secs: u64,
+    // skip the boot_best-reset reboot fallback when all_time rises in the same poll (firmware that
+    // copies a new all-time best over boot_best)
+    pub ignore_boot_best_reset_on_all_time_update: bool,
+    // chip temperature (°C) above which temp_alert is emitted
+    pub temp_alert_c: Option<f64>,
+    // fraction (0..=1) of shares rejected since the last poll above which high_reject_ratio is emitted
+    pub reject_ratio_alert: Option<f64>,
+    // percent by which J/TH may exceed the median of the last efficiency_baseline_window samples
+    // before efficiency_degraded is emitted
+    pub efficiency_degraded_pct: Option<f64>,
+    pub efficiency_baseline_window: usize,
+    // hashrate (TH/s) below which a poll counts toward hashrate_stalled, and how many in a row it takes
+    pub min_hashrate_ths: Option<f64>,
+    pub stall_polls: u32,
+}
+
+impl Thresholds {
+    //configured thresholds with the defaults poll_once has always used for unset keys
+    pub fn from_config(cfg: Option<&ThresholdsConfig>) -> Self {
+        Self {
+            epsilon_hashrate_ths: cfg.and_then(|t| t.epsilon_hashrate_ths).unwrap_or(0.01),
+            epsilon_efficiency_j_per_th: cfg
+                .and_then(|t| t.epsilon_efficiency_j_per_th)
+                .unwrap_or(0.01),
+            warmup_secs: cfg.and_then(|t| t.warmup_secs).unwrap_or(300),
+            ignore_boot_best_reset_on_all_time_update: cfg
+                .and_then(|t| t.ignore_boot_best_reset_on_all_time_update)
+                .unwrap_or(false),
+            temp_alert_c: cfg.and_then(|t| t.temp_alert_c),
+            reject_ratio_alert: cfg.and_then(|t| t.reject_ratio_alert),
+            efficiency_degraded_pct: cfg.and_then(|t| t.efficiency_degraded_pct),
+            efficiency_baseline_window: cfg
+                .and_then(|t| t.efficiency_baseline_window)
+                .unwrap_or(30),
+            min_hashrate_ths: cfg.and_then(|t| t.min_hashrate_ths),
+            stall_polls: cfg.and_then(|t| t.stall_polls).unwrap_or(3),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -74,40 +849,207 @@ pub struct Displayed {
     pub boot_best: f64,
 }
 
+//convert a single json value to f64, accepting numbers and numeric strings with unit suffixes
+fn value_to_f64(v: &Value, pointer: &str, sep: DecimalSeparator) -> anyhow::Result<f64> {
+    match v {
+        Value::Number(n) => n
+            .as_f64()
+            .ok_or_else(|| anyhow::anyhow!("number out of range")),
+        Value::String(s) => parse_number_with_unit(s, sep)
+            .map_err(|e| anyhow::anyhow!(format!("{} at {}", e, pointer))),
+        _ => Err(anyhow::anyhow!(format!("non-numeric value at {}", pointer))),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Aggregate {
+    Min,
+    Max,
+    Avg,
+    Sum,
+}
+
+//"<array pointer>[]<element pointer>|<min|max|avg|sum>", e.g. "/asics[]/frequency|avg" or "/temps[]|max"
+#[derive(Debug, Clone, Copy)]
+struct AggregatePointer<'a> {
+    array: &'a str,
+    element: &'a str,
+    agg: Aggregate,
+}
+
+impl<'a> AggregatePointer<'a> {
+    //returns Ok(None) for plain pointers so callers can fall through to the scalar path
+    fn parse(pointer: &'a str) -> anyhow::Result<Option<Self>> {
+        let Some((array, rest)) = pointer.split_once("[]") else {
+            return Ok(None);
+        };
+        let (element, agg) = rest.rsplit_once('|').ok_or_else(|| {
+            anyhow::anyhow!(format!(
+                "array pointer {} needs an aggregate suffix: |min, |max, |avg or |sum",
+                pointer
+            ))
+        })?;
+        let agg = match agg {
+            "min" => Aggregate::Min,
+            "max" => Aggregate::Max,
+            "avg" => Aggregate::Avg,
+            "sum" => Aggregate::Sum,
+            other => {
+                return Err(anyhow::anyhow!(format!(
+                    "unsupported aggregate '{}' in {}",
+                    other, pointer
+                )))
+            }
+        };
+        Ok(Some(Self {
+            array,
+            element,
+            agg,
+        }))
+    }
+
+    fn evaluate(&self, json: &Value, sep: DecimalSeparator) -> anyhow::Result<f64> {
+        let arr = json
+            .pointer(self.array)
+            .ok_or_else(|| anyhow::anyhow!(format!("json pointer not found: {}", self.array)))?
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!(format!("value at {} is not an array", self.array)))?;
+        if arr.is_empty() {
+            return Err(anyhow::anyhow!(format!(
+                "cannot aggregate empty array at {}",
+                self.array
+            )));
+        }
+
+        let mut values = Vec::with_capacity(arr.len());
+        for (i, item) in arr.iter().enumerate() {
+            let v = if self.element.is_empty() {
+                item
+            } else {
+                item.pointer(self.element).ok_or_else(|| {
+                    anyhow::anyhow!(format!(
+                        "json pointer not found: {}/{}{}",
+                        self.array, i, self.element
+                    ))
+                })?
+            };
+            values.push(value_to_f64(v, self.array, sep)?);
+        }
+
+        let out = match self.agg {
+            Aggregate::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            Aggregate::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Aggregate::Sum => values.iter().sum(),
+            Aggregate::Avg => values.iter().sum::<f64>() / values.len() as f64,
+        };
+        Ok(out)
+    }
+}
+
+//first candidate present in the response; aggregates only need their array to exist
+fn resolve_candidate<'a>(json: &Value, spec: &'a PointerSpec) -> Option<&'a str> {
+    spec.candidates().iter().map(String::as_str).find(|p| {
+        let lookup = match AggregatePointer::parse(p) {
+            Ok(Some(agg)) => agg.array,
+            _ => p,
+        };
+        json.pointer(lookup).is_some()
+    })
+}
+
+//a single pointer is returned as-is so its own lookup reports the precise failure;
+//lists fail here naming every candidate that was tried
+fn require_candidate<'a>(json: &Value, spec: &'a PointerSpec) -> anyhow::Result<&'a str> {
+    match spec.candidates() {
+        [only] => Ok(only.as_str()),
+        all => resolve_candidate(json, spec).ok_or_else(|| {
+            anyhow::anyhow!(format!("json pointer not found (tried {})", all.join(", ")))
+        }),
+    }
+}
+
+//which metrics are read at all; see config::MetricsConfig
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrackedMetrics {
+    pub hashrate: bool,
+    pub efficiency: bool,
+    pub temp: bool,
+}
+
+impl Default for TrackedMetrics {
+    fn default() -> Self {
+        Self {
+            hashrate: true,
+            efficiency: true,
+            temp: true,
+        }
+    }
+}
+
+impl TrackedMetrics {
+    pub fn from_config(cfg: Option<&MetricsConfig>) -> Self {
+        Self {
+            hashrate: cfg.and_then(|c| c.track_hashrate).unwrap_or(true),
+            efficiency: cfg.and_then(|c| c.track_efficiency).unwrap_or(true),
+            temp: cfg.and_then(|c| c.track_temp).unwrap_or(true),
+        }
+    }
+}
+
+//every metric tracked, for tests; the binary goes through extract_tracked_metrics with the configured set
+#[cfg(test)]
 pub fn extract_metrics_from_json(
     json: &Value,
     ptrs: &JsonPointers,
+) -> anyhow::Result<ExtractedMetrics> {
+    extract_tracked_metrics(json, ptrs, TrackedMetrics::default())
+}
+
+//disabled metrics are skipped before their pointers are looked at, so a stale pointer for one cannot
+//fail the poll and nothing downstream sees a value for it
+pub fn extract_tracked_metrics(
+    json: &Value,
+    ptrs: &JsonPointers,
+    tracked: TrackedMetrics,
 ) -> anyhow::Result<ExtractedMetrics> {
     //convert json pointer value to f64 to support numeric strings
-    fn extract_f64(json: &Value, pointer: &str) -> anyhow::Result<f64> {
+    //pointers like "/asics[]/frequency|avg" aggregate a field across an array instead
+    fn extract_f64(json: &Value, pointer: &str, sep: DecimalSeparator) -> anyhow::Result<f64> {
+        if let Some(agg) = AggregatePointer::parse(pointer)? {
+            return agg.evaluate(json, sep);
+        }
         let v = json
             .pointer(pointer)
             .ok_or_else(|| anyhow::anyhow!(format!("json pointer not found: {}", pointer)))?;
-        match v {
-            Value::Number(n) => n
-                .as_f64()
-                .ok_or_else(|| anyhow::anyhow!("number out of range")),
-            Value::String(s) => parse_number_with_unit(s)
-                .map_err(|e| anyhow::anyhow!(format!("{} at {}", e, pointer))),
-            _ => Err(anyhow::anyhow!(format!("non-numeric value at {}", pointer))),
-        }
+        value_to_f64(v, pointer, sep)
     }
 
-    fn extract_u64_opt(json: &Value, pointer_opt: &Option<String>) -> anyhow::Result<Option<u64>> {
-        if let Some(pointer) = pointer_opt.as_ref() {
+    fn extract_u64_opt(
+        json: &Value,
+        pointer_opt: &Option<PointerSpec>,
+    ) -> anyhow::Result<Option<u64>> {
+        if let Some(spec) = pointer_opt.as_ref() {
+            let pointer = require_candidate(json, spec)?;
             let v = json
                 .pointer(pointer)
                 .ok_or_else(|| anyhow::anyhow!(format!("json pointer not found: {}", pointer)))?;
+            //a negative value is an error, not a huge wrapped counter
+            let negative = |i: i64| anyhow::anyhow!("negative value {} at {}", i, pointer);
             match v {
-                Value::Number(n) => n
-                    .as_u64()
-                    .map(Some)
-                    .ok_or_else(|| anyhow::anyhow!("number out of range")),
-                Value::String(s) => Ok(Some(
-                    s.parse::<u64>()
-                        .map_err(|_| anyhow::anyhow!("invalid integer"))?,
-                )),
-                _ => Err(anyhow::anyhow!("non-integer uptime")),
+                Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+                    (Some(u), _) => Ok(Some(u)),
+                    (None, Some(i)) => u64::try_from(i).map(Some).map_err(|_| negative(i)),
+                    (None, None) => Err(anyhow::anyhow!("number out of range")),
+                },
+                Value::String(s) => {
+                    let s = s.trim();
+                    match (s.parse::<u64>(), s.parse::<i64>()) {
+                        (Ok(u), _) => Ok(Some(u)),
+                        (Err(_), Ok(i)) => u64::try_from(i).map(Some).map_err(|_| negative(i)),
+                        (Err(_), Err(_)) => Err(anyhow::anyhow!("invalid integer")),
+                    }
+                }
+                _ => Err(anyhow::anyhow!(format!("non-integer value at {}", pointer))),
             }
         } else {
             Ok(None)
@@ -116,9 +1058,10 @@ pub fn extract_metrics_from_json(
 
     fn extract_string_opt(
         json: &Value,
-        pointer_opt: &Option<String>,
+        pointer_opt: &Option<PointerSpec>,
     ) -> anyhow::Result<Option<String>> {
-        if let Some(pointer) = pointer_opt.as_ref() {
+        if let Some(spec) = pointer_opt.as_ref() {
+            let pointer = require_candidate(json, spec)?;
             let v = json
                 .pointer(pointer)
                 .ok_or_else(|| anyhow::anyhow!(format!("json pointer not found: {}", pointer)))?;
@@ -132,8 +1075,42 @@ pub fn extract_metrics_from_json(
         }
     }
 
-    let displayed_all_time = extract_f64(json, &ptrs.json_pointer_all_time)?;
-    let displayed_boot_best = extract_f64(json, &ptrs.json_pointer_boot_best)?;
+    //parsed value plus the string as the device sent it, so "1.23T" is shown as formatted
+    fn extract_difficulty_opt(
+        json: &Value,
+        pointer_opt: &Option<PointerSpec>,
+        sep: DecimalSeparator,
+        what: &str,
+    ) -> anyhow::Result<(Option<f64>, Option<String>)> {
+        let Some(spec) = pointer_opt.as_ref() else {
+            return Ok((None, None));
+        };
+        let pointer = require_candidate(json, spec)?;
+        let value = extract_f64(json, pointer, sep)?;
+        if !value.is_finite() {
+            return Err(anyhow::anyhow!("non-finite {} difficulty value", what));
+        }
+        let display = match json.pointer(pointer) {
+            Some(Value::String(s)) => s.trim().to_string(),
+            Some(other) => other.to_string(),
+            None => value.to_string(),
+        };
+        Ok((Some(value), Some(display)))
+    }
+
+    let resolved = resolve_selectors(json, ptrs);
+    let ptrs = &*resolved;
+    let sep = ptrs.decimal_separator.unwrap_or_default();
+    let displayed_all_time = extract_f64(
+        json,
+        require_candidate(json, &ptrs.json_pointer_all_time)?,
+        sep,
+    )?;
+    let displayed_boot_best = extract_f64(
+        json,
+        require_candidate(json, &ptrs.json_pointer_boot_best)?,
+        sep,
+    )?;
 
     //reject NaN/inf so downstream logic only sees real numbers
     if !displayed_all_time.is_finite() {
@@ -144,72 +1121,541 @@ pub fn extract_metrics_from_json(
     }
     let uptime_secs = extract_u64_opt(json, &ptrs.json_pointer_uptime_secs)?;
     let boot_id = extract_string_opt(json, &ptrs.json_pointer_boot_id)?;
+    let sample_time = extract_u64_opt(json, &ptrs.json_pointer_sample_time)?;
+    let boot_time = extract_u64_opt(json, &ptrs.json_pointer_boot_time)?;
 
     // helper to extract optional f64 given an optional pointer
-    fn extract_f64_opt(json: &Value, pointer_opt: &Option<String>) -> anyhow::Result<Option<f64>> {
-        if let Some(p) = pointer_opt.as_ref() {
-            Ok(Some(extract_f64(json, p)?))
+    fn extract_f64_opt(
+        json: &Value,
+        pointer_opt: &Option<PointerSpec>,
+        sep: DecimalSeparator,
+    ) -> anyhow::Result<Option<f64>> {
+        if let Some(spec) = pointer_opt.as_ref() {
+            Ok(Some(extract_f64(
+                json,
+                require_candidate(json, spec)?,
+                sep,
+            )?))
         } else {
             Ok(None)
         }
     }
 
     // optional: extract hashrate and apply scale to TH/s when configured (e.g., GH/s -> TH/s)
-    let mut hashrate_ths = extract_f64_opt(json, &ptrs.json_pointer_hashrate_ths)?;
+    let mut hashrate_ths = if tracked.hashrate {
+        extract_f64_opt(json, &ptrs.json_pointer_hashrate_ths, sep)?
+    } else {
+        None
+    };
     if let (Some(scale), Some(h)) = (ptrs.hashrate_scale, hashrate_ths) {
         hashrate_ths = Some(h * scale);
     }
 
     // optional: extract efficiency directly when provided
-    let mut efficiency_j_per_th = extract_f64_opt(json, &ptrs.json_pointer_efficiency_j_per_th)?;
+    let mut efficiency_j_per_th = if tracked.efficiency {
+        extract_f64_opt(json, &ptrs.json_pointer_efficiency_j_per_th, sep)?
+    } else {
+        None
+    };
 
-    // optional: extract power (W) and compute efficiency when not provided
-    if efficiency_j_per_th.is_none() {
-        if let (Some(power_w), Some(h_ths)) = (
-            extract_f64_opt(json, &ptrs.json_pointer_power_w)?,
-            hashrate_ths,
-        ) {
+    // optional: extract power (W), or derive it from voltage and current, and compute efficiency when
+    // not provided
+    let mut power_w = extract_f64_opt(json, &ptrs.json_pointer_power_w, sep)?;
+    //only the pointer value is scaled; voltage x current is already in watts
+    if let (Some(scale), Some(p)) = (ptrs.power_scale, power_w) {
+        power_w = Some(p * scale);
+    }
+    if power_w.is_none() {
+        let voltage_v = extract_f64_opt(json, &ptrs.json_pointer_voltage_v, sep)?;
+        let current_a = extract_f64_opt(json, &ptrs.json_pointer_current_a, sep)?;
+        if let (Some(v), Some(i)) = (voltage_v, current_a) {
+            power_w = power_from_voltage_current(v, i);
+        }
+    }
+    if efficiency_j_per_th.is_none() && tracked.efficiency {
+        if let (Some(power_w), Some(h_ths)) = (power_w, hashrate_ths) {
             if power_w.is_finite() && h_ths.is_finite() && h_ths > 0.0 {
                 efficiency_j_per_th = Some(power_w / h_ths);
             }
         }
     }
 
+    // optional: best difficulty (all-time) and session difficulty (since boot), display strings kept
+    let (best_difficulty, best_difficulty_display) =
+        extract_difficulty_opt(json, &ptrs.json_pointer_best_difficulty, sep, "best")?;
+    let (session_difficulty, session_difficulty_display) =
+        extract_difficulty_opt(json, &ptrs.json_pointer_session_difficulty, sep, "session")?;
+
+    // optional: chip temperature in °C, calibrated before anything tracks or alarms on it
+    let temp_c_raw = if tracked.temp {
+        extract_f64_opt(json, &ptrs.json_pointer_temp_c, sep)?
+    } else {
+        None
+    };
+    let calibrated = ptrs.temp_scale.is_some() || ptrs.temp_offset_c.is_some();
+    let temp_c =
+        temp_c_raw.map(|t| t * ptrs.temp_scale.unwrap_or(1.0) + ptrs.temp_offset_c.unwrap_or(0.0));
+    let temp_c_raw = temp_c_raw.filter(|_| calibrated);
+
+    // optional: core voltage (mV) and frequency (MHz)
+    let voltage_mv = extract_f64_opt(json, &ptrs.json_pointer_voltage_mv, sep)?;
+    let frequency_mhz = extract_f64_opt(json, &ptrs.json_pointer_frequency_mhz, sep)?;
+
+    // optional: cumulative accepted/rejected share counters
+    let shares_accepted = extract_u64_opt(json, &ptrs.json_pointer_shares_accepted)?;
+    let shares_rejected = extract_u64_opt(json, &ptrs.json_pointer_shares_rejected)?;
+
     Ok(ExtractedMetrics {
         displayed_all_time,
         displayed_boot_best,
         uptime_secs,
         boot_id,
+        boot_time,
         hashrate_ths,
         efficiency_j_per_th,
+        power_w,
+        sample_time,
+        best_difficulty,
+        best_difficulty_display,
+        session_difficulty,
+        session_difficulty_display,
+        temp_c,
+        temp_c_raw,
+        voltage_mv,
+        frequency_mhz,
+        shares_accepted,
+        shares_rejected,
     })
 }
 
-pub fn detect_changes(
-    state: &mut MonitorState,
-    displayed: Displayed,
-    metrics: Metrics,
-    thresholds: Thresholds,
-) -> DetectionOutcome {
-    let mut out = DetectionOutcome::default();
-
-    let displayed_all_time = displayed.all_time;
-    let displayed_boot_best = displayed.boot_best;
-    let uptime_secs = metrics.uptime_secs;
-    let boot_id_str = metrics.boot_id.as_deref();
+//P = V * I; negative or non-finite readings (sensor glitches) give no power rather than a bogus one
+fn power_from_voltage_current(voltage_v: f64, current_a: f64) -> Option<f64> {
+    if voltage_v.is_finite() && current_a.is_finite() && voltage_v >= 0.0 && current_a >= 0.0 {
+        Some(voltage_v * current_a)
+    } else {
+        None
+    }
+}
 
-    //derive boot marker priority: boot_id > uptime_secs > boot_best reset heuristic
-    //used combinators map and or_else to simplify the code
-    let current_marker = boot_id_str
-        .map(|id| id.to_string())
-        .or_else(|| uptime_secs.map(|up| format!("uptime:{}", up)));
+//remove an uptime above max_secs (wrapped counters, values near u64::MAX) and return it for logging,
+//so the reboot heuristic falls back to other signals for this poll instead of seeing garbage
+pub fn take_implausible_uptime(m: &mut ExtractedMetrics, max_secs: u64) -> Option<u64> {
+    match m.uptime_secs {
+        Some(up) if up > max_secs => m.uptime_secs.take(),
+        _ => None,
+    }
+}
 
-    //detect reboot only on real signals so we do not emit on every poll
-    // prefer explicit boot_id changes; otherwise detect when uptime decreases; finally, fall back to device boot-best reset
-    let boot_id_changed = match (state.last_boot_marker.as_ref(), boot_id_str) {
-        // compare only when the previous marker was also a boot_id (not an uptime-derived marker)
-        (Some(prev_marker), Some(curr_id)) if !prev_marker.starts_with("uptime:") => {
-            prev_marker != curr_id
+//remove hashrate (TH/s) and chip temperature readings above their plausible bounds and return each
+//as (metric, value, bound) for logging. Efficiency goes with a rejected hashrate since it is usually
+//derived from it; the poll carries on as if those pointers were not configured
+pub fn take_outliers(
+    m: &mut ExtractedMetrics,
+    max_hashrate_ths: Option<f64>,
+    max_temp_c: Option<f64>,
+) -> Vec<(&'static str, f64, f64)> {
+    let mut rejected = Vec::new();
+    if let (Some(h), Some(max)) = (m.hashrate_ths, max_hashrate_ths) {
+        if h > max {
+            rejected.push(("hashrate_ths", h, max));
+            m.hashrate_ths = None;
+            m.efficiency_j_per_th = None;
+        }
+    }
+    if let (Some(t), Some(max)) = (m.temp_c, max_temp_c) {
+        if t > max {
+            rejected.push(("temp_c", t, max));
+            m.temp_c = None;
+        }
+    }
+    rejected
+}
+
+//how far a reported boot_time may lie ahead of this machine's clock before it is rejected
+pub const BOOT_TIME_MAX_SKEW_SECS: u64 = 60;
+
+//for devices that only report when they booted: uptime = now - boot_time, unless an uptime was read
+//directly. A boot_time further in the future than the allowed skew is returned for logging and
+//leaves uptime unset, so the reboot heuristic falls back to other signals for this poll
+pub fn derive_uptime_from_boot_time(m: &mut ExtractedMetrics, now_epoch_secs: i64) -> Option<u64> {
+    if m.uptime_secs.is_some() {
+        return None;
+    }
+    let boot_time = m.boot_time?;
+    let age = now_epoch_secs.saturating_sub(boot_time as i64);
+    if age < -(BOOT_TIME_MAX_SKEW_SECS as i64) {
+        return Some(boot_time);
+    }
+    m.uptime_secs = Some(age.max(0) as u64);
+    None
+}
+
+//" (set 2024-03-02 14:10 UTC)" for --summary lines; empty when the time is unknown (older state)
+pub fn set_at_suffix(at: Option<DateTime<Utc>>) -> String {
+    at.map(|t| format!(" (set {})", t.format("%Y-%m-%d %H:%M UTC")))
+        .unwrap_or_default()
+}
+
+//one block character per sample, scaled between the window's min and max; a flat window draws at
+//mid height and an empty one draws nothing
+pub fn sparkline(samples: &[f64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let finite: Vec<f64> = samples.iter().copied().filter(|v| v.is_finite()).collect();
+    let min = finite.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = finite.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    finite
+        .iter()
+        .map(|v| {
+            if max > min {
+                let idx = ((v - min) / (max - min) * (BARS.len() - 1) as f64).round() as usize;
+                BARS[idx.min(BARS.len() - 1)]
+            } else {
+                BARS[BARS.len() / 2 - 1]
+            }
+        })
+        .collect()
+}
+
+//a sample is stale when its device timestamp lags "now" by more than the allowed age
+//timestamps slightly in the future (clock skew) count as fresh
+pub fn is_sample_stale(sample_time: u64, now_epoch_secs: i64, max_age_secs: u64) -> bool {
+    let age = now_epoch_secs.saturating_sub(sample_time as i64);
+    age > 0 && age as u64 > max_age_secs
+}
+
+//with selector_kind jsonpath, replace every candidate with the json pointer of the first node it
+//matches in this response, so the rest of extraction only deals in pointers; a path that matches
+//nothing is kept as written and reported as not found, the same as a missing pointer
+pub fn resolve_selectors<'a>(json: &Value, ptrs: &'a JsonPointers) -> Cow<'a, JsonPointers> {
+    if ptrs.selector_kind != Some(SelectorKind::JsonPath) {
+        return Cow::Borrowed(ptrs);
+    }
+    let to_pointer = |path: &String| {
+        serde_json_path::JsonPath::parse(path)
+            .ok()
+            .and_then(|p| {
+                p.query_located(json)
+                    .first()
+                    .map(|node| node.location().to_json_pointer())
+            })
+            .unwrap_or_else(|| path.clone())
+    };
+    let resolve = |spec: &PointerSpec| match spec {
+        PointerSpec::One(p) => PointerSpec::One(to_pointer(p)),
+        PointerSpec::Candidates(list) => {
+            PointerSpec::Candidates(list.iter().map(to_pointer).collect())
+        }
+    };
+    let mut out = ptrs.clone();
+    out.selector_kind = Some(SelectorKind::Pointer);
+    out.json_pointer_all_time = resolve(&ptrs.json_pointer_all_time);
+    out.json_pointer_boot_best = resolve(&ptrs.json_pointer_boot_best);
+    for (_, ptr) in out.optional_pointers_mut() {
+        if let Some(spec) = ptr {
+            *spec = resolve(spec);
+        }
+    }
+    Cow::Owned(out)
+}
+
+//null counts as missing: firmware often reports a sensor it has not read yet as null
+pub fn pointer_available(json: &Value, spec: &PointerSpec) -> bool {
+    resolve_candidate(json, spec).is_some_and(|p| {
+        let lookup = match AggregatePointer::parse(p) {
+            Ok(Some(agg)) => agg.array,
+            _ => p,
+        };
+        json.pointer(lookup).is_some_and(|v| !v.is_null())
+    })
+}
+
+//record which optional pointers resolve in this response and return the transitions as
+//(field name, now available); a pointer seen missing first is recorded without a transition
+pub fn update_metric_availability(
+    state: &mut MonitorState,
+    json: &Value,
+    ptrs: &JsonPointers,
+) -> Vec<(&'static str, bool)> {
+    let ptrs = resolve_selectors(json, ptrs);
+    let mut changes = Vec::new();
+    // the first two are the required all_time and boot_best pointers
+    for (name, spec) in ptrs.named_pointers().into_iter().skip(2) {
+        let available = pointer_available(json, spec);
+        let prev = state
+            .metric_availability
+            .insert(name.to_string(), available);
+        if prev.unwrap_or(false) != available {
+            changes.push((name, available));
+        }
+    }
+    changes
+}
+
+//ptrs without the optional pointers this response lacks, so those metrics are skipped for the
+//poll instead of failing it
+pub fn without_missing_optional(json: &Value, ptrs: &JsonPointers) -> JsonPointers {
+    let mut out = resolve_selectors(json, ptrs).into_owned();
+    for (_, ptr) in out.optional_pointers_mut() {
+        if ptr
+            .as_ref()
+            .is_some_and(|spec| !pointer_available(json, spec))
+        {
+            *ptr = None;
+        }
+    }
+    out
+}
+
+//check declared pointer types against a live response; stricter than extraction, which coerces
+//numeric strings, so pointing hashrate at an object or a label fails with a precise message
+pub fn check_pointer_types(json: &Value, ptrs: &JsonPointers) -> anyhow::Result<()> {
+    let Some(types) = ptrs.expected_types.as_ref() else {
+        return Ok(());
+    };
+    let ptrs = resolve_selectors(json, ptrs);
+    let mut problems: Vec<String> = Vec::new();
+    for (name, spec) in ptrs.named_pointers() {
+        let Some(expected) = types.get(name) else {
+            continue;
+        };
+        //with fallbacks, the candidate that resolves is the one whose type matters
+        let Some(pointer) = resolve_candidate(json, spec) else {
+            problems.push(format!("{} ({}): not found", name, spec));
+            continue;
+        };
+        //aggregates always produce a number, so only their declared kind matters
+        if AggregatePointer::parse(pointer).ok().flatten().is_some() {
+            if *expected != ValueKind::Number {
+                problems.push(format!(
+                    "{} ({}): aggregate pointers resolve to a number, not {}",
+                    name, pointer, expected
+                ));
+            }
+            continue;
+        }
+        let Some(found) = json.pointer(pointer) else {
+            continue;
+        };
+        let ok = match expected {
+            ValueKind::Number => found.is_number(),
+            ValueKind::Integer => found.is_i64() || found.is_u64(),
+            ValueKind::String => found.is_string(),
+        };
+        if !ok {
+            problems.push(format!(
+                "{} ({}): expected {}, found {}",
+                name,
+                pointer,
+                expected,
+                json_type_name(found)
+            ));
+        }
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "pointer type mismatch: {}",
+            problems.join("; ")
+        ))
+    }
+}
+
+fn json_type_name(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(n) if n.is_f64() => "number (float)",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+//render each configured pointer with the raw value it resolved to so a setup can be checked by eye
+pub fn describe_resolved_values(
+    json: &Value,
+    ptrs: &JsonPointers,
+    metrics: &ExtractedMetrics,
+) -> Vec<String> {
+    let mut lines: Vec<String> = resolve_selectors(json, ptrs)
+        .named_pointers()
+        .into_iter()
+        .map(|(name, spec)| {
+            let Some(pointer) = resolve_candidate(json, spec) else {
+                return format!("{} ({}) = <missing>", name, spec);
+            };
+            // aggregate pointers show the array they reduce over
+            let lookup = match AggregatePointer::parse(pointer) {
+                Ok(Some(agg)) => agg.array,
+                _ => pointer,
+            };
+            let raw = json
+                .pointer(lookup)
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "<missing>".to_string());
+            format!("{} ({}) = {}", name, pointer, raw)
+        })
+        .collect();
+
+    // show the derived values too since scaling and power-based efficiency are easy to misconfigure
+    match metrics.hashrate_ths {
+        Some(v) => lines.push(format!("hashrate (scaled): {:.4} TH/s", v)),
+        None => lines.push("hashrate (scaled): n/a".to_string()),
+    }
+    match metrics.efficiency_j_per_th {
+        Some(v) => lines.push(format!("efficiency (computed): {:.2} J/TH", v)),
+        None => lines.push("efficiency (computed): n/a".to_string()),
+    }
+    lines
+}
+
+//offline counterpart of preflight for a captured response: the same type check and extraction, and
+//the resolved values on success so a config can be checked in ci without a device
+pub fn validate_pointers_sample(
+    json: &Value,
+    ptrs: &JsonPointers,
+    tracked: TrackedMetrics,
+) -> anyhow::Result<Vec<String>> {
+    check_pointer_types(json, ptrs)?;
+    let metrics = extract_tracked_metrics(json, ptrs, tracked)?;
+    Ok(describe_resolved_values(json, ptrs, &metrics))
+}
+
+//a device-reported efficiency far from power/hashrate usually means one of the three pointers is
+//aimed at the wrong field; tolerance_pct is relative to the computed value
+pub fn efficiency_consistency_warning(m: &ExtractedMetrics, tolerance_pct: f64) -> Option<String> {
+    let (eff, power, h) = (m.efficiency_j_per_th?, m.power_w?, m.hashrate_ths?);
+    if !(power.is_finite() && h.is_finite() && h > 0.0 && eff.is_finite()) {
+        return None;
+    }
+    let computed = power / h;
+    let diff_pct = (eff - computed).abs() / computed.abs() * 100.0;
+    (diff_pct > tolerance_pct).then(|| {
+        format!(
+            "reported efficiency {:.2} J/TH differs from power/hashrate {:.2} J/TH by {:.1}% (tolerance {}%); check the efficiency, power and hashrate pointers, hashrate_scale and power_scale",
+            eff, computed, diff_pct, tolerance_pct
+        )
+    })
+}
+
+//flatten one poll into a single record (current metrics plus current bests) for simple ingestion
+pub fn wide_record(
+    ts: DateTime<Utc>,
+    device: Option<&str>,
+    m: &ExtractedMetrics,
+    state: &MonitorState,
+) -> Value {
+    serde_json::json!({
+        "ts": ts,
+        "device": device,
+        "displayed_all_time": m.displayed_all_time,
+        "displayed_boot_best": m.displayed_boot_best,
+        "uptime_secs": m.uptime_secs,
+        "boot_id": m.boot_id,
+        "hashrate_ths": m.hashrate_ths,
+        "efficiency_j_per_th": m.efficiency_j_per_th,
+        "sample_time": m.sample_time,
+        "best_difficulty": m.best_difficulty,
+        "best_difficulty_display": m.best_difficulty_display,
+        "session_difficulty": m.session_difficulty,
+        "session_difficulty_display": m.session_difficulty_display,
+        "temp_c": m.temp_c,
+        "voltage_mv": m.voltage_mv,
+        "frequency_mhz": m.frequency_mhz,
+        "tool_global_all_time_best": state.tool_global_all_time_best,
+        "tool_best_hashrate_ths": state.tool_best_hashrate_ths,
+        "tool_best_efficiency_j_per_th": state.tool_best_efficiency_j_per_th,
+        "tool_worst_hashrate_ths": state.tool_worst_hashrate_ths,
+        "tool_worst_efficiency_j_per_th": state.tool_worst_efficiency_j_per_th,
+        "tool_best_difficulty": state.tool_best_difficulty,
+        "tool_best_difficulty_display": state.tool_best_difficulty_display,
+        "session_best_difficulty": state.session_best_difficulty,
+        "session_best_difficulty_display": state.session_best_difficulty_display,
+        "tool_max_temp_c": state.tool_max_temp_c,
+        "tool_min_power_w": state.tool_min_power_w,
+        "tool_max_power_w": state.tool_max_power_w
+    })
+}
+
+//scale a value to the largest SI prefix that keeps it >= 1, e.g. 1.23e12 H/s -> "1.23 TH/s";
+//an empty unit gives the compact device style, e.g. "4.50G" for difficulty
+pub fn format_si(value: f64, unit: &str) -> String {
+    const PREFIXES: [(f64, &str); 6] = [
+        (1e18, "E"),
+        (1e15, "P"),
+        (1e12, "T"),
+        (1e9, "G"),
+        (1e6, "M"),
+        (1e3, "k"),
+    ];
+    let (scaled, prefix) = PREFIXES
+        .iter()
+        .find(|(scale, _)| value.is_finite() && value.abs() >= *scale)
+        .map(|(scale, p)| (value / scale, *p))
+        .unwrap_or((value, ""));
+    if unit.is_empty() {
+        format!("{:.2}{}", scaled, prefix)
+    } else {
+        format!("{:.2} {}{}", scaled, prefix, unit)
+    }
+}
+
+//add "<kind>_human" next to an event's numeric "value" for human-read event streams
+pub fn add_human_values(event: &mut Value) {
+    let Some(obj) = event.as_object_mut() else {
+        return;
+    };
+    let Some(v) = obj.get("value").and_then(Value::as_f64) else {
+        return;
+    };
+    let (key, text) = match obj.get("event").and_then(Value::as_str) {
+        Some("new_tool_best_hashrate_ths" | "new_tool_worst_hashrate_ths") => {
+            ("hashrate_human", format_si(v * 1e12, "H/s"))
+        }
+        Some("new_tool_best_efficiency_j_per_th" | "new_tool_worst_efficiency_j_per_th") => {
+            ("efficiency_human", format!("{:.2} J/TH", v))
+        }
+        Some(
+            "new_device_boot_best"
+            | "new_device_all_time_best"
+            | "new_tool_all_time_best"
+            | "new_best_difficulty"
+            | "new_session_best_difficulty",
+        ) => ("difficulty_human", format_si(v, "")),
+        _ => return,
+    };
+    obj.insert(key.to_string(), Value::String(text));
+}
+
+//now is the time of the poll, stamped on every best/worst/extreme it sets
+pub fn detect_changes(
+    state: &mut MonitorState,
+    displayed: Displayed,
+    metrics: Metrics,
+    thresholds: Thresholds,
+    now: DateTime<Utc>,
+) -> DetectionOutcome {
+    let mut out = DetectionOutcome::default();
+
+    let displayed_all_time = displayed.all_time;
+    let displayed_boot_best = displayed.boot_best;
+    let uptime_secs = metrics.uptime_secs;
+    let boot_id_str = metrics.boot_id.as_deref();
+
+    //derive boot marker priority: boot_id > uptime_secs > boot_best reset heuristic
+    //used combinators map and or_else to simplify the code
+    let current_marker = boot_id_str
+        .map(|id| id.to_string())
+        .or_else(|| uptime_secs.map(|up| format!("uptime:{}", up)));
+
+    //detect reboot only on real signals so we do not emit on every poll
+    // prefer explicit boot_id changes; otherwise detect when uptime decreases; finally, fall back to device boot-best reset
+    let boot_id_changed = match (state.last_boot_marker.as_ref(), boot_id_str) {
+        // compare only when the previous marker was also a boot_id (not an uptime-derived marker)
+        (Some(prev_marker), Some(curr_id)) if !prev_marker.starts_with("uptime:") => {
+            prev_marker != curr_id
         }
         _ => false,
     };
@@ -221,7 +1667,13 @@ pub fn detect_changes(
             out.boot_detected = true;
         }
     } else if let Some(prev_boot_best) = state.last_displayed_boot_best {
-        if displayed_boot_best + f64::EPSILON < prev_boot_best {
+        //a boot_best drop that coincides with all_time rising can be the firmware resetting
+        //boot_best on a new all-time best rather than a reboot
+        let all_time_rose = state
+            .last_displayed_all_time
+            .is_some_and(|prev| displayed_all_time > prev);
+        let benign_reset = thresholds.ignore_boot_best_reset_on_all_time_update && all_time_rose;
+        if displayed_boot_best + f64::EPSILON < prev_boot_best && !benign_reset {
             out.boot_detected = true;
         }
     }
@@ -255,6 +1707,7 @@ pub fn detect_changes(
     let candidate = displayed_all_time.max(displayed_boot_best);
     if candidate > state.tool_global_all_time_best {
         state.tool_global_all_time_best = candidate;
+        state.tool_global_all_time_best_at = Some(now);
         out.new_tool_all_time_best = Some(candidate);
     }
 
@@ -267,6 +1720,7 @@ pub fn detect_changes(
         };
         if is_better {
             state.tool_best_hashrate_ths = Some(h);
+            state.tool_best_hashrate_ths_at = Some(now);
             out.new_tool_best_hashrate_ths = Some(h);
         }
     }
@@ -280,21 +1734,205 @@ pub fn detect_changes(
         };
         if is_better {
             state.tool_best_efficiency_j_per_th = Some(eff);
+            state.tool_best_efficiency_j_per_th_at = Some(now);
             out.new_tool_best_efficiency_j_per_th = Some(eff);
         }
     }
 
+    // track best difficulty numerically but keep the display string of the value that set it
+    if let Some(d) = metrics.best_difficulty.filter(|v| v.is_finite()) {
+        if state.tool_best_difficulty.is_none_or(|prev| d > prev) {
+            state.tool_best_difficulty = Some(d);
+            state.tool_best_difficulty_at = Some(now);
+            state.tool_best_difficulty_display = metrics.best_difficulty_display.clone();
+            out.new_best_difficulty = Some(d);
+        }
+    }
+
+    // session difficulty starts over with each boot, so its running max does too
+    if out.boot_detected {
+        state.session_best_difficulty = None;
+        state.session_best_difficulty_display = None;
+    }
+    if let Some(d) = metrics.session_difficulty.filter(|v| v.is_finite()) {
+        if state.session_best_difficulty.is_none_or(|prev| d > prev) {
+            state.session_best_difficulty = Some(d);
+            state.session_best_difficulty_display = metrics.session_difficulty_display.clone();
+            out.new_session_best_difficulty = Some(d);
+        }
+    }
+
+    // track the power draw range; the first reading sets both ends
+    if let Some(p) = metrics.power_w.filter(|v| v.is_finite()) {
+        if state.tool_min_power_w.is_none_or(|prev| p < prev) {
+            state.tool_min_power_w = Some(p);
+            state.tool_min_power_w_at = Some(now);
+            out.new_tool_min_power_w = Some(p);
+        }
+        if state.tool_max_power_w.is_none_or(|prev| p > prev) {
+            state.tool_max_power_w = Some(p);
+            state.tool_max_power_w_at = Some(now);
+            out.new_tool_max_power_w = Some(p);
+        }
+    }
+
+    // track the hottest reading; any strictly higher value is a new max
+    if let Some(t) = metrics.temp_c.filter(|v| v.is_finite()) {
+        if state.tool_max_temp_c.is_none_or(|prev| t > prev) {
+            state.tool_max_temp_c = Some(t);
+            state.tool_max_temp_c_at = Some(now);
+            out.new_tool_max_temp_c = Some(t);
+        }
+        // alert only on the below -> above crossing; re-arm once it cools to the threshold or below
+        if let Some(limit) = thresholds.temp_alert_c {
+            if t > limit {
+                if !state.temp_alert_active {
+                    state.temp_alert_active = true;
+                    out.temp_alert = Some((t, limit));
+                }
+            } else {
+                state.temp_alert_active = false;
+            }
+        }
+    }
+
+    // reject ratio over the shares submitted since the last poll; the counters restart from zero on
+    // reboot, so a reboot (or a counter that went backwards) only re-baselines
+    if let (Some(acc), Some(rej)) = (metrics.shares_accepted, metrics.shares_rejected) {
+        if let (Some(prev_acc), Some(prev_rej), false) = (
+            state.last_shares_accepted,
+            state.last_shares_rejected,
+            out.boot_detected,
+        ) {
+            if acc >= prev_acc && rej >= prev_rej {
+                let (d_acc, d_rej) = (acc - prev_acc, rej - prev_rej);
+                let total = d_acc + d_rej;
+                if let (Some(limit), true) = (thresholds.reject_ratio_alert, total > 0) {
+                    let ratio = d_rej as f64 / total as f64;
+                    if ratio > limit {
+                        out.high_reject_ratio = Some((ratio, limit));
+                    }
+                }
+            }
+        }
+        state.last_shares_accepted = Some(acc);
+        state.last_shares_rejected = Some(rej);
+    }
+
+    // a device that answers but hashes (next to) nothing; fires once after stall_polls low readings in a
+    // row and again as recovered on the first reading back at or above the threshold
+    if let (Some(min), Some(h)) = (
+        thresholds.min_hashrate_ths,
+        metrics.hashrate_ths.filter(|v| v.is_finite()),
+    ) {
+        if h < min {
+            state.hashrate_below_min_polls = state.hashrate_below_min_polls.saturating_add(1);
+            if state.hashrate_below_min_polls >= thresholds.stall_polls.max(1)
+                && !state.hashrate_stalled
+            {
+                state.hashrate_stalled = true;
+                out.hashrate_stalled = Some((h, min));
+            }
+        } else {
+            state.hashrate_below_min_polls = 0;
+            if state.hashrate_stalled {
+                state.hashrate_stalled = false;
+                out.hashrate_recovered = Some(h);
+            }
+        }
+    }
+
+    // compare efficiency against the median of recent samples; a fresh boot behaves differently, so
+    // the baseline starts over on reboot and needs a full window before it is trusted
+    if let Some(pct) = thresholds.efficiency_degraded_pct {
+        if out.boot_detected {
+            state.efficiency_baseline.clear();
+            state.efficiency_degraded_active = false;
+        }
+        if let Some(eff) = metrics.efficiency_j_per_th.filter(|v| v.is_finite()) {
+            let window = thresholds.efficiency_baseline_window.max(1);
+            if state.efficiency_baseline.len() >= window {
+                let baseline = median(&state.efficiency_baseline);
+                if eff > baseline * (1.0 + pct / 100.0) {
+                    if !state.efficiency_degraded_active {
+                        state.efficiency_degraded_active = true;
+                        out.efficiency_degraded = Some((eff, baseline));
+                    }
+                } else {
+                    state.efficiency_degraded_active = false;
+                }
+            }
+            state.efficiency_baseline.push_back(eff);
+            while state.efficiency_baseline.len() > window {
+                state.efficiency_baseline.pop_front();
+            }
+        }
+    }
+
+    //skip worst tracking during boot ramp: on the reboot poll itself and while uptime is inside warmup
+    let in_warmup = out.boot_detected || uptime_secs.is_some_and(|up| up < thresholds.warmup_secs);
+    if !in_warmup {
+        // track worst hashrate (min). same jitter guard as bests, in the opposite direction
+        if let Some(h) = metrics.hashrate_ths.filter(|v| v.is_finite()) {
+            let is_worse = match state.tool_worst_hashrate_ths {
+                Some(prev) => prev - h >= thresholds.epsilon_hashrate_ths,
+                None => true,
+            };
+            if is_worse {
+                state.tool_worst_hashrate_ths = Some(h);
+                state.tool_worst_hashrate_ths_at = Some(now);
+                out.new_tool_worst_hashrate_ths = Some(h);
+            }
+        }
+
+        // track worst efficiency (max J/TH)
+        if let Some(eff) = metrics.efficiency_j_per_th.filter(|v| v.is_finite()) {
+            let is_worse = match state.tool_worst_efficiency_j_per_th {
+                Some(prev) => eff - prev >= thresholds.epsilon_efficiency_j_per_th,
+                None => true,
+            };
+            if is_worse {
+                state.tool_worst_efficiency_j_per_th = Some(eff);
+                state.tool_worst_efficiency_j_per_th_at = Some(now);
+                out.new_tool_worst_efficiency_j_per_th = Some(eff);
+            }
+        }
+    }
+
     out
 }
 
+//middle value of the samples (mean of the two middle ones for an even count)
+fn median(samples: &VecDeque<f64>) -> f64 {
+    let mut sorted: Vec<f64> = samples.iter().copied().collect();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
 //parses numbers that may have unit suffixes like 1.22G or 22.6M
 //supports K (1e3), M (1e6), G (1e9), T (1e12); falls back to plain float
-fn parse_number_with_unit(input: &str) -> anyhow::Result<f64> {
+//with a comma separator, "16,09" means 16.09 and dots are dropped as digit grouping
+fn parse_number_with_unit(input: &str, sep: DecimalSeparator) -> anyhow::Result<f64> {
     let s = input.trim();
     if s.is_empty() {
         return Err(anyhow::anyhow!("empty string"));
     }
 
+    // normalize comma decimals into dot form so the rest of the parser stays unchanged
+    let normalized;
+    let s = match sep {
+        DecimalSeparator::Dot => s,
+        DecimalSeparator::Comma => {
+            normalized = s.replace('.', "").replace(',', ".");
+            normalized.as_str()
+        }
+    };
+
     // take the first whitespace-separated token so strings like "16.09 J/TH" parse as 16.09
     let token = s.split_whitespace().next().unwrap();
 
@@ -304,18 +1942,25 @@ fn parse_number_with_unit(input: &str) -> anyhow::Result<f64> {
         return Ok(v);
     }
 
+    // a trailing "i" marks a binary prefix (Ki = 1024, Mi = 1024^2, ...); without it K/M/G/T stay decimal
+    let (token, binary) = match token.strip_suffix(['i', 'I']) {
+        Some(rest) if rest.ends_with(|c: char| c.is_ascii_alphabetic()) => (rest, true),
+        _ => (token, false),
+    };
+
     let last = token.chars().last().unwrap();
     if last.is_ascii_alphabetic() {
         let unit_char = last.to_ascii_uppercase();
         let number_part = &token[..token.len() - 1];
         let base: f64 = number_part.trim().parse()?;
-        let factor = match unit_char {
-            'K' => 1e3,
-            'M' => 1e6,
-            'G' => 1e9,
-            'T' => 1e12,
+        let power = match unit_char {
+            'K' => 1,
+            'M' => 2,
+            'G' => 3,
+            'T' => 4,
             _ => return Err(anyhow::anyhow!("unsupported unit suffix")),
         };
+        let factor = if binary { 1024f64 } else { 1000f64 }.powi(power);
         Ok(base * factor)
     } else {
         Ok(token.parse()?)
@@ -327,17 +1972,31 @@ mod tests {
     use super::*;
     use crate::config::JsonPointers;
 
+    //the poll time handed to detect_changes where the test does not care which it is
+    fn t0() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap()
+    }
+
     #[test]
     fn test_parse_number_with_unit_plain() {
-        let v = super::parse_number_with_unit("123.5").unwrap();
+        let v = super::parse_number_with_unit("123.5", DecimalSeparator::Dot).unwrap();
         assert!((v - 123.5).abs() < 1e-9);
     }
 
     #[test]
     fn test_parse_number_with_unit_units() {
-        assert!((super::parse_number_with_unit("1K").unwrap() - 1e3).abs() < 1e-6);
-        assert!((super::parse_number_with_unit("1.2M").unwrap() - 1.2e6).abs() < 1e-3);
-        assert!((super::parse_number_with_unit("0.5G").unwrap() - 0.5e9).abs() < 1.0);
+        assert!(
+            (super::parse_number_with_unit("1K", DecimalSeparator::Dot).unwrap() - 1e3).abs()
+                < 1e-6
+        );
+        assert!(
+            (super::parse_number_with_unit("1.2M", DecimalSeparator::Dot).unwrap() - 1.2e6).abs()
+                < 1e-3
+        );
+        assert!(
+            (super::parse_number_with_unit("0.5G", DecimalSeparator::Dot).unwrap() - 0.5e9).abs()
+                < 1.0
+        );
     }
 
     #[test]
@@ -350,15 +2009,14 @@ mod tests {
         };
         let metrics = Metrics {
             uptime_secs: Some(100),
-            boot_id: None,
-            hashrate_ths: None,
-            efficiency_j_per_th: None,
+            ..Default::default()
         };
         let thresholds = Thresholds {
             epsilon_hashrate_ths: 0.01,
             epsilon_efficiency_j_per_th: 0.01,
+            ..Default::default()
         };
-        let out1 = detect_changes(&mut state, displayed, metrics, thresholds);
+        let out1 = detect_changes(&mut state, displayed, metrics, thresholds, t0());
         assert!(out1.new_device_all_time_best.is_some());
         assert!(out1.new_device_boot_best.is_some());
         assert!(out1.new_tool_all_time_best.is_some());
@@ -370,15 +2028,14 @@ mod tests {
         };
         let metrics = Metrics {
             uptime_secs: Some(110),
-            boot_id: None,
-            hashrate_ths: None,
-            efficiency_j_per_th: None,
+            ..Default::default()
         };
         let thresholds = Thresholds {
             epsilon_hashrate_ths: 0.01,
             epsilon_efficiency_j_per_th: 0.01,
+            ..Default::default()
         };
-        let out2 = detect_changes(&mut state, displayed, metrics, thresholds);
+        let out2 = detect_changes(&mut state, displayed, metrics, thresholds, t0());
         assert!(out2.new_device_boot_best.is_some());
 
         // simulate reboot via uptime drop
@@ -388,21 +2045,20 @@ mod tests {
         };
         let metrics = Metrics {
             uptime_secs: Some(10),
-            boot_id: None,
-            hashrate_ths: None,
-            efficiency_j_per_th: None,
+            ..Default::default()
         };
         let thresholds = Thresholds {
             epsilon_hashrate_ths: 0.01,
             epsilon_efficiency_j_per_th: 0.01,
+            ..Default::default()
         };
-        let out3 = detect_changes(&mut state, displayed, metrics, thresholds);
+        let out3 = detect_changes(&mut state, displayed, metrics, thresholds, t0());
         assert!(out3.boot_detected);
     }
 
     #[test]
     fn test_parse_number_with_unit_invalid_suffix() {
-        let err = super::parse_number_with_unit("1.2X").unwrap_err();
+        let err = super::parse_number_with_unit("1.2X", DecimalSeparator::Dot).unwrap_err();
         let msg = format!("{}", err);
         assert!(msg.to_lowercase().contains("unsupported"));
     }
@@ -418,14 +2074,14 @@ mod tests {
         let metrics = Metrics {
             uptime_secs: Some(50),
             boot_id: Some("A".to_string()),
-            hashrate_ths: None,
-            efficiency_j_per_th: None,
+            ..Default::default()
         };
         let thresholds = Thresholds {
             epsilon_hashrate_ths: 0.01,
             epsilon_efficiency_j_per_th: 0.01,
+            ..Default::default()
         };
-        let _ = detect_changes(&mut state, displayed, metrics, thresholds);
+        let _ = detect_changes(&mut state, displayed, metrics, thresholds, t0());
         // change only boot_id to "B" (uptime increases), expect boot_detected
         let displayed = Displayed {
             all_time: 1.1,
@@ -434,14 +2090,14 @@ mod tests {
         let metrics = Metrics {
             uptime_secs: Some(60),
             boot_id: Some("B".to_string()),
-            hashrate_ths: None,
-            efficiency_j_per_th: None,
+            ..Default::default()
         };
         let thresholds = Thresholds {
             epsilon_hashrate_ths: 0.01,
             epsilon_efficiency_j_per_th: 0.01,
+            ..Default::default()
         };
-        let out = detect_changes(&mut state, displayed, metrics, thresholds);
+        let out = detect_changes(&mut state, displayed, metrics, thresholds, t0());
         assert!(out.boot_detected);
     }
 
@@ -464,8 +2120,7 @@ mod tests {
             json_pointer_boot_id: Some("/boot_id".into()),
             json_pointer_hashrate_ths: Some("/hashrate".into()),
             json_pointer_efficiency_j_per_th: Some("/efficiency".into()),
-            json_pointer_power_w: None,
-            hashrate_scale: None,
+            ..Default::default()
         };
 
         let m = extract_metrics_from_json(&json, &ptrs).unwrap();
@@ -496,8 +2151,7 @@ mod tests {
             json_pointer_boot_id: Some("/boot_id".into()),
             json_pointer_hashrate_ths: Some("/hashrate".into()),
             json_pointer_efficiency_j_per_th: Some("/efficiency".into()),
-            json_pointer_power_w: None,
-            hashrate_scale: None,
+            ..Default::default()
         };
 
         let m = extract_metrics_from_json(&json, &ptrs).unwrap();
@@ -518,18 +2172,48 @@ mod tests {
         let ptrs = JsonPointers {
             json_pointer_all_time: "/missing".into(),
             json_pointer_boot_best: "/boot_best".into(),
-            json_pointer_uptime_secs: None,
-            json_pointer_boot_id: None,
-            json_pointer_hashrate_ths: None,
-            json_pointer_efficiency_j_per_th: None,
-            json_pointer_power_w: None,
-            hashrate_scale: None,
+            ..Default::default()
         };
         let err = extract_metrics_from_json(&json, &ptrs).unwrap_err();
         let msg = format!("{}", err);
         assert!(msg.to_lowercase().contains("pointer"));
     }
 
+    #[test]
+    fn test_extract_metrics_fallback_pointer_used() {
+        // older firmware exposes "hashRate"; the primary "/hashrate" is absent so the fallback resolves
+        let json = serde_json::json!({ "all_time": 10, "boot_best": 5, "hashRate": 1.5 });
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/all_time".into(),
+            json_pointer_boot_best: "/boot_best".into(),
+            json_pointer_hashrate_ths: Some(PointerSpec::Candidates(vec![
+                "/hashrate".to_string(),
+                "/hashRate".to_string(),
+            ])),
+            ..Default::default()
+        };
+        let m = extract_metrics_from_json(&json, &ptrs).unwrap();
+        assert_eq!(m.hashrate_ths, Some(1.5));
+        let lines = describe_resolved_values(&json, &ptrs, &m);
+        assert_eq!(lines[2], "json_pointer_hashrate_ths (/hashRate) = 1.5");
+    }
+
+    #[test]
+    fn test_extract_metrics_all_fallbacks_missing_lists_tried() {
+        let json = serde_json::json!({ "all_time": 10, "boot_best": 5 });
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/all_time".into(),
+            json_pointer_boot_best: "/boot_best".into(),
+            json_pointer_hashrate_ths: Some(PointerSpec::Candidates(vec![
+                "/hashrate".to_string(),
+                "/hashRate".to_string(),
+            ])),
+            ..Default::default()
+        };
+        let msg = format!("{}", extract_metrics_from_json(&json, &ptrs).unwrap_err());
+        assert_eq!(msg, "json pointer not found (tried /hashrate, /hashRate)");
+    }
+
     #[test]
     fn test_extract_metrics_non_finite_rejected() {
         // NaN should be rejected so downstream logic avoids invalid math
@@ -537,15 +2221,1485 @@ mod tests {
         let ptrs = JsonPointers {
             json_pointer_all_time: "/all_time".into(),
             json_pointer_boot_best: "/boot_best".into(),
-            json_pointer_uptime_secs: None,
-            json_pointer_boot_id: None,
-            json_pointer_hashrate_ths: None,
-            json_pointer_efficiency_j_per_th: None,
-            json_pointer_power_w: None,
-            hashrate_scale: None,
+            ..Default::default()
         };
         let err = extract_metrics_from_json(&json, &ptrs).unwrap_err();
         let msg = format!("{}", err);
         assert!(msg.to_lowercase().contains("non-finite"));
     }
+
+    #[test]
+    fn test_describe_resolved_values_lists_pointers_and_efficiency() {
+        // fixture mirrors a device response where efficiency is derived from power and scaled hashrate
+        let json = serde_json::json!({
+            "bestDiff": "1.2M",
+            "bestSessionDiff": 5000,
+            "hashRate": 1200.0,
+            "power": 18.0
+        });
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/bestDiff".into(),
+            json_pointer_boot_best: "/bestSessionDiff".into(),
+            json_pointer_hashrate_ths: Some("/hashRate".into()),
+            json_pointer_power_w: Some("/power".into()),
+            hashrate_scale: Some(0.001),
+            ..Default::default()
+        };
+
+        let m = extract_metrics_from_json(&json, &ptrs).unwrap();
+        let lines = describe_resolved_values(&json, &ptrs, &m);
+        assert_eq!(lines[0], "json_pointer_all_time (/bestDiff) = \"1.2M\"");
+        assert_eq!(lines[1], "json_pointer_boot_best (/bestSessionDiff) = 5000");
+        assert_eq!(lines[2], "json_pointer_hashrate_ths (/hashRate) = 1200.0");
+        assert_eq!(lines[3], "json_pointer_power_w (/power) = 18.0");
+        assert_eq!(lines[4], "hashrate (scaled): 1.2000 TH/s");
+        assert_eq!(lines[5], "efficiency (computed): 15.00 J/TH");
+    }
+
+    #[test]
+    fn test_sample_freshness() {
+        // a sample within the window is fresh; one older than the window is stale
+        let now = 1_700_000_100;
+        assert!(!is_sample_stale(1_700_000_090, now, 30));
+        assert!(!is_sample_stale(1_700_000_070, now, 30));
+        assert!(is_sample_stale(1_700_000_000, now, 30));
+        // device clock slightly ahead of ours should not be treated as stale
+        assert!(!is_sample_stale(1_700_000_105, now, 30));
+    }
+
+    #[test]
+    fn test_extract_metrics_sample_time() {
+        let json = serde_json::json!({ "all_time": 1, "boot_best": 1, "ts": 1700000000 });
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/all_time".into(),
+            json_pointer_boot_best: "/boot_best".into(),
+            json_pointer_sample_time: Some("/ts".into()),
+            ..Default::default()
+        };
+        let m = extract_metrics_from_json(&json, &ptrs).unwrap();
+        assert_eq!(m.sample_time, Some(1_700_000_000));
+    }
+
+    #[test]
+    fn test_worst_tracking_skips_warmup() {
+        let mut state = MonitorState::new();
+        let thresholds = Thresholds {
+            epsilon_hashrate_ths: 0.01,
+            epsilon_efficiency_j_per_th: 0.01,
+            warmup_secs: 300,
+            ..Default::default()
+        };
+        let displayed = Displayed {
+            all_time: 1.0,
+            boot_best: 1.0,
+        };
+
+        // ramp-up sample inside warmup should not count as a worst value
+        let warmup = Metrics {
+            uptime_secs: Some(60),
+            hashrate_ths: Some(0.2),
+            efficiency_j_per_th: Some(40.0),
+            ..Default::default()
+        };
+        let out = detect_changes(&mut state, displayed, warmup, thresholds, t0());
+        assert!(out.new_tool_worst_hashrate_ths.is_none());
+        assert!(state.tool_worst_hashrate_ths.is_none());
+        assert!(state.tool_worst_efficiency_j_per_th.is_none());
+
+        // steady-state samples set and then lower the worst hashrate
+        let steady = Metrics {
+            uptime_secs: Some(600),
+            hashrate_ths: Some(1.2),
+            efficiency_j_per_th: Some(16.0),
+            ..Default::default()
+        };
+        let out = detect_changes(&mut state, displayed, steady, thresholds, t0());
+        assert_eq!(out.new_tool_worst_hashrate_ths, Some(1.2));
+        let low = Metrics {
+            uptime_secs: Some(660),
+            hashrate_ths: Some(0.9),
+            efficiency_j_per_th: Some(18.5),
+            ..Default::default()
+        };
+        let out = detect_changes(&mut state, displayed, low, thresholds, t0());
+        assert_eq!(out.new_tool_worst_hashrate_ths, Some(0.9));
+        assert_eq!(out.new_tool_worst_efficiency_j_per_th, Some(18.5));
+        assert_eq!(state.tool_worst_hashrate_ths, Some(0.9));
+        assert_eq!(state.tool_worst_efficiency_j_per_th, Some(18.5));
+    }
+
+    #[test]
+    fn test_parse_number_with_unit_comma_decimal() {
+        let comma = DecimalSeparator::Comma;
+        assert!((super::parse_number_with_unit("16,09", comma).unwrap() - 16.09).abs() < 1e-9);
+        assert!((super::parse_number_with_unit("16,09 J/TH", comma).unwrap() - 16.09).abs() < 1e-9);
+        // dots act as grouping in comma mode
+        assert!((super::parse_number_with_unit("1.234,5", comma).unwrap() - 1234.5).abs() < 1e-9);
+        assert!((super::parse_number_with_unit("1,2K", comma).unwrap() - 1200.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_parse_number_with_unit_dot_mode_unaffected() {
+        let dot = DecimalSeparator::Dot;
+        assert!((super::parse_number_with_unit("16.09", dot).unwrap() - 16.09).abs() < 1e-9);
+        // a comma is not a decimal point in dot mode
+        assert!(super::parse_number_with_unit("16,09", dot).is_err());
+    }
+
+    #[test]
+    fn test_extract_voltage_and_frequency() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/all_time".into(),
+            json_pointer_boot_best: "/boot_best".into(),
+            json_pointer_voltage_mv: Some("/coreVoltage".into()),
+            json_pointer_frequency_mhz: Some("/frequency".into()),
+            ..Default::default()
+        };
+        let json = serde_json::json!({
+            "all_time": 5, "boot_best": 5, "coreVoltage": 1200, "frequency": "575"
+        });
+        let m = extract_metrics_from_json(&json, &ptrs).unwrap();
+        assert_eq!(m.voltage_mv, Some(1200.0));
+        assert_eq!(m.frequency_mhz, Some(575.0));
+
+        // unconfigured pointers leave both unset
+        let plain = JsonPointers {
+            json_pointer_voltage_mv: None,
+            json_pointer_frequency_mhz: None,
+            ..ptrs
+        };
+        let m = extract_metrics_from_json(&json, &plain).unwrap();
+        assert_eq!((m.voltage_mv, m.frequency_mhz), (None, None));
+    }
+
+    #[test]
+    fn test_uptime_derived_from_boot_time() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/all_time".into(),
+            json_pointer_boot_best: "/boot_best".into(),
+            json_pointer_boot_time: Some("/bootTime".into()),
+            ..Default::default()
+        };
+        let now = 1_700_000_000i64;
+        let read = |boot_time: i64, now: i64| {
+            let json = serde_json::json!({ "all_time": 5, "boot_best": 5, "bootTime": boot_time });
+            let mut m = extract_metrics_from_json(&json, &ptrs).unwrap();
+            let rejected = derive_uptime_from_boot_time(&mut m, now);
+            (m, rejected)
+        };
+        let (m, rejected) = read(now - 3600, now);
+        assert_eq!((m.uptime_secs, rejected), (Some(3600), None));
+        // a little clock skew reads as just booted; far in the future is rejected
+        assert_eq!(read(now + 30, now).0.uptime_secs, Some(0));
+        let (m, rejected) = read(now + 3600, now);
+        assert_eq!((m.uptime_secs, rejected), (None, Some((now + 3600) as u64)));
+
+        // a directly reported uptime wins
+        let mut direct = ExtractedMetrics {
+            uptime_secs: Some(42),
+            boot_time: Some((now - 3600) as u64),
+            ..Default::default()
+        };
+        assert_eq!(derive_uptime_from_boot_time(&mut direct, now), None);
+        assert_eq!(direct.uptime_secs, Some(42));
+
+        // the reboot heuristic runs off the derived value: a later boot_time shrinks uptime
+        let mut state = MonitorState::new();
+        let mut poll = |boot_time: i64, at: i64| {
+            let (m, _) = read(boot_time, at);
+            detect_changes(
+                &mut state,
+                Displayed {
+                    all_time: 5.0,
+                    boot_best: 5.0,
+                },
+                Metrics {
+                    uptime_secs: m.uptime_secs,
+                    ..Default::default()
+                },
+                Thresholds::default(),
+                t0(),
+            )
+            .boot_detected
+        };
+        assert!(!poll(now - 3600, now));
+        assert!(!poll(now - 3600, now + 60));
+        assert!(poll(now + 100, now + 120));
+        assert_eq!(state.last_uptime_secs, Some(20));
+    }
+
+    #[test]
+    fn test_wide_record_contains_all_fields() {
+        let m = ExtractedMetrics {
+            displayed_all_time: 12.0,
+            displayed_boot_best: 9.0,
+            uptime_secs: Some(300),
+            boot_id: Some("B1".into()),
+            boot_time: None,
+            voltage_mv: None,
+            frequency_mhz: None,
+            hashrate_ths: Some(1.6),
+            efficiency_j_per_th: Some(15.8),
+            sample_time: None,
+            best_difficulty: None,
+            best_difficulty_display: None,
+            session_difficulty: None,
+            session_difficulty_display: None,
+            temp_c: None,
+            temp_c_raw: None,
+            power_w: None,
+            shares_accepted: None,
+            shares_rejected: None,
+        };
+        let mut state = MonitorState::new();
+        state.tool_global_all_time_best = 12.0;
+        state.tool_best_hashrate_ths = Some(1.7);
+
+        let ts = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let rec = wide_record(ts, Some("garage"), &m, &state);
+        let obj = rec.as_object().unwrap();
+        for key in [
+            "ts",
+            "device",
+            "displayed_all_time",
+            "displayed_boot_best",
+            "uptime_secs",
+            "boot_id",
+            "hashrate_ths",
+            "efficiency_j_per_th",
+            "sample_time",
+            "tool_global_all_time_best",
+            "tool_best_hashrate_ths",
+            "tool_best_efficiency_j_per_th",
+            "tool_worst_hashrate_ths",
+            "tool_worst_efficiency_j_per_th",
+        ] {
+            assert!(obj.contains_key(key), "missing {}", key);
+        }
+        assert_eq!(rec["device"], "garage");
+        assert_eq!(rec["hashrate_ths"], 1.6);
+        assert_eq!(rec["tool_best_hashrate_ths"], 1.7);
+        assert!(rec["sample_time"].is_null());
+    }
+
+    #[test]
+    fn test_latency_window_jitter() {
+        let mut w = LatencyWindow::default();
+        assert!(w.jitter_ms().is_none());
+        w.push(100.0, 4);
+        assert!(w.jitter_ms().is_none());
+        for ms in [200.0, 300.0, 400.0] {
+            w.push(ms, 4);
+        }
+        // population stddev of 100,200,300,400 is sqrt(12500)
+        assert!((w.mean_ms().unwrap() - 250.0).abs() < 1e-9);
+        assert!((w.jitter_ms().unwrap() - 12500f64.sqrt()).abs() < 1e-9);
+
+        // window slides: constant samples push the variance out
+        for _ in 0..4 {
+            w.push(50.0, 4);
+        }
+        assert_eq!(w.sample_count(), 4);
+        assert!(w.jitter_ms().unwrap().abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_best_difficulty_keeps_display_string() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/all_time".into(),
+            json_pointer_boot_best: "/boot_best".into(),
+            json_pointer_best_difficulty: Some("/bestDiff".into()),
+            ..Default::default()
+        };
+        let displayed = Displayed {
+            all_time: 1.0,
+            boot_best: 1.0,
+        };
+        let mut state = MonitorState::new();
+
+        let mut poll = |diff: &str| {
+            let json = serde_json::json!({ "all_time": 1, "boot_best": 1, "bestDiff": diff });
+            let m = extract_metrics_from_json(&json, &ptrs).unwrap();
+            let metrics = Metrics {
+                best_difficulty: m.best_difficulty,
+                best_difficulty_display: m.best_difficulty_display,
+                ..Default::default()
+            };
+            detect_changes(&mut state, displayed, metrics, Thresholds::default(), t0())
+        };
+
+        assert_eq!(poll("980G").new_best_difficulty, Some(980e9));
+        // a new high replaces both the number and the original formatting
+        assert_eq!(poll("1.23T").new_best_difficulty, Some(1.23e12));
+        // lower values leave the best and its display untouched
+        assert!(poll("2.5G").new_best_difficulty.is_none());
+        assert_eq!(state.tool_best_difficulty, Some(1.23e12));
+        assert_eq!(state.tool_best_difficulty_display.as_deref(), Some("1.23T"));
+    }
+
+    #[test]
+    fn test_session_best_difficulty_resets_on_reboot() {
+        let ptrs: JsonPointers = serde_json::from_value(serde_json::json!({
+            "json_pointer_all_time": "/all_time",
+            "json_pointer_boot_best": "/boot_best",
+            "json_pointer_uptime_secs": "/uptime",
+            "json_pointer_best_diff": "/bestDiff",
+            "json_pointer_session_diff": "/bestSessionDiff"
+        }))
+        .unwrap();
+        let mut state = MonitorState::new();
+        let mut poll = |uptime: u64, best: &str, session: &str| {
+            let json = serde_json::json!({
+                "all_time": 1, "boot_best": 1, "uptime": uptime,
+                "bestDiff": best, "bestSessionDiff": session
+            });
+            let (displayed, metrics) = extract_metrics_from_json(&json, &ptrs)
+                .unwrap()
+                .detection_inputs();
+            detect_changes(&mut state, displayed, metrics, Thresholds::default(), t0())
+        };
+
+        assert_eq!(
+            poll(600, "4.5G", "4.5G").new_session_best_difficulty,
+            Some(4.5e9)
+        );
+        assert!(poll(1200, "4.5G", "900M")
+            .new_session_best_difficulty
+            .is_none());
+        // after a reboot the first session value is a new session best even though it is lower
+        let out = poll(30, "4.5G", "12M");
+        assert!(out.boot_detected);
+        assert_eq!(out.new_session_best_difficulty, Some(12e6));
+        assert!(out.new_best_difficulty.is_none());
+        assert_eq!(
+            state.session_best_difficulty_display.as_deref(),
+            Some("12M")
+        );
+        assert_eq!(state.tool_best_difficulty, Some(4.5e9));
+    }
+
+    #[test]
+    fn test_pointer_types_match() {
+        let json = serde_json::json!({
+            "all_time": 10, "boot_best": "5", "hashrate": 1.2, "uptime": 300
+        });
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/all_time".into(),
+            json_pointer_boot_best: "/boot_best".into(),
+            json_pointer_hashrate_ths: Some("/hashrate".into()),
+            json_pointer_uptime_secs: Some("/uptime".into()),
+            expected_types: Some(
+                [
+                    ("json_pointer_all_time".to_string(), ValueKind::Integer),
+                    ("json_pointer_boot_best".to_string(), ValueKind::String),
+                    ("json_pointer_hashrate_ths".to_string(), ValueKind::Number),
+                    ("json_pointer_uptime_secs".to_string(), ValueKind::Number),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+        check_pointer_types(&json, &ptrs).unwrap();
+    }
+
+    #[test]
+    fn test_pointer_types_mismatch_flagged() {
+        // hashrate accidentally points at an object; uptime is a float where an integer is declared
+        let json = serde_json::json!({
+            "all_time": 10, "boot_best": 5, "hashrate": { "value": 1.2 }, "uptime": 300.5
+        });
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/all_time".into(),
+            json_pointer_boot_best: "/boot_best".into(),
+            json_pointer_hashrate_ths: Some("/hashrate".into()),
+            json_pointer_uptime_secs: Some("/uptime".into()),
+            expected_types: Some(
+                [
+                    ("json_pointer_hashrate_ths".to_string(), ValueKind::Number),
+                    ("json_pointer_uptime_secs".to_string(), ValueKind::Integer),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+        let msg = format!("{}", check_pointer_types(&json, &ptrs).unwrap_err());
+        assert!(
+            msg.contains("json_pointer_hashrate_ths (/hashrate): expected number, found object")
+        );
+        assert!(msg.contains(
+            "json_pointer_uptime_secs (/uptime): expected integer, found number (float)"
+        ));
+    }
+
+    #[test]
+    fn test_aggregate_pointer_functions() {
+        let json = serde_json::json!({
+            "asics": [
+                { "frequency": 500, "temp": "61.5" },
+                { "frequency": 550, "temp": "63.0" },
+                { "frequency": 525, "temp": "60.0" }
+            ],
+            "temps": [61.5, 63.0, 60.0],
+            "empty": []
+        });
+        let eval = |p: &str| {
+            AggregatePointer::parse(p)
+                .unwrap()
+                .unwrap()
+                .evaluate(&json, DecimalSeparator::Dot)
+        };
+        assert_eq!(eval("/asics[]/frequency|min").unwrap(), 500.0);
+        assert_eq!(eval("/asics[]/frequency|max").unwrap(), 550.0);
+        assert_eq!(eval("/asics[]/frequency|sum").unwrap(), 1575.0);
+        assert_eq!(eval("/asics[]/frequency|avg").unwrap(), 525.0);
+        // numeric strings inside elements parse like scalar values
+        assert_eq!(eval("/asics[]/temp|max").unwrap(), 63.0);
+        // bare arrays of numbers need no element pointer
+        assert!((eval("/temps[]|avg").unwrap() - 61.5).abs() < 1e-9);
+
+        let err = format!("{}", eval("/empty[]|avg").unwrap_err());
+        assert!(err.contains("empty array"));
+    }
+
+    #[test]
+    fn test_aggregate_pointer_in_extraction() {
+        let json = serde_json::json!({
+            "all_time": 1, "boot_best": 1,
+            "asics": [ { "hashrate": 600 }, { "hashrate": 650 } ]
+        });
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/all_time".into(),
+            json_pointer_boot_best: "/boot_best".into(),
+            json_pointer_hashrate_ths: Some("/asics[]/hashrate|sum".into()),
+            hashrate_scale: Some(0.001),
+            ..Default::default()
+        };
+        let m = extract_metrics_from_json(&json, &ptrs).unwrap();
+        assert!((m.hashrate_ths.unwrap() - 1.25).abs() < 1e-9);
+
+        // missing or unknown aggregate suffix is a clear error
+        let bad = JsonPointers {
+            json_pointer_hashrate_ths: Some("/asics[]/hashrate|median".into()),
+            ..ptrs
+        };
+        let err = format!("{}", extract_metrics_from_json(&json, &bad).unwrap_err());
+        assert!(err.contains("unsupported aggregate"));
+    }
+
+    #[test]
+    fn test_format_si_magnitudes() {
+        assert_eq!(format_si(1.23e12, "H/s"), "1.23 TH/s");
+        assert_eq!(format_si(950e9, "H/s"), "950.00 GH/s");
+        assert_eq!(format_si(2.5e15, "H/s"), "2.50 PH/s");
+        assert_eq!(format_si(12.0, "H/s"), "12.00 H/s");
+        assert_eq!(format_si(4.5e9, ""), "4.50G");
+        assert_eq!(format_si(1500.0, ""), "1.50k");
+        assert_eq!(format_si(0.0, ""), "0.00");
+    }
+
+    #[test]
+    fn test_add_human_values_keeps_raw() {
+        let mut ev = serde_json::json!({ "event": "new_tool_best_hashrate_ths", "value": 1.23 });
+        add_human_values(&mut ev);
+        assert_eq!(ev["value"], 1.23);
+        assert_eq!(ev["hashrate_human"], "1.23 TH/s");
+
+        let mut ev =
+            serde_json::json!({ "event": "new_tool_worst_efficiency_j_per_th", "value": 16.094 });
+        add_human_values(&mut ev);
+        assert_eq!(ev["efficiency_human"], "16.09 J/TH");
+
+        let mut ev = serde_json::json!({ "event": "new_device_all_time_best", "value": 3.2e10 });
+        add_human_values(&mut ev);
+        assert_eq!(ev["difficulty_human"], "32.00G");
+
+        // events without a numeric value are left untouched
+        let mut ev = serde_json::json!({ "event": "boot_detected", "state": {} });
+        add_human_values(&mut ev);
+        assert_eq!(ev.as_object().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_wide_record_gate_writes_on_change_and_keepalive() {
+        let sample = |hashrate: f64, uptime: u64| ExtractedMetrics {
+            displayed_all_time: 100.0,
+            displayed_boot_best: 50.0,
+            uptime_secs: Some(uptime),
+            boot_id: None,
+            boot_time: None,
+            voltage_mv: None,
+            frequency_mhz: None,
+            hashrate_ths: Some(hashrate),
+            efficiency_j_per_th: None,
+            sample_time: None,
+            best_difficulty: None,
+            best_difficulty_display: None,
+            session_difficulty: None,
+            session_difficulty_display: None,
+            temp_c: None,
+            temp_c_raw: None,
+            power_w: None,
+            shares_accepted: None,
+            shares_rejected: None,
+        };
+        let thresholds = Thresholds {
+            epsilon_hashrate_ths: 0.01,
+            ..Default::default()
+        };
+        let mut gate = WideRecordGate::default();
+        let mut written = Vec::new();
+        // first sample, four stable ones (uptime and sub-epsilon noise only), then a real change
+        let hashrates = [1.20, 1.205, 1.20, 1.201, 1.20, 1.50, 1.50];
+        for (i, h) in hashrates.iter().enumerate() {
+            written.push(gate.should_write(&sample(*h, i as u64 * 10), thresholds, 4));
+        }
+        assert_eq!(
+            written,
+            vec![true, false, false, false, true, true, false],
+            "first record, keepalive on the 4th quiet poll, then the change"
+        );
+    }
+
+    #[test]
+    fn test_boot_best_reset_with_all_time_update_suppressed() {
+        // no uptime pointer: only the boot_best-reset fallback can signal a reboot
+        let thresholds = Thresholds {
+            ignore_boot_best_reset_on_all_time_update: true,
+            ..Default::default()
+        };
+        let displayed = |all_time: f64, boot_best: f64| Displayed {
+            all_time,
+            boot_best,
+        };
+        let mut state = MonitorState::new();
+        detect_changes(
+            &mut state,
+            displayed(10.0, 8.0),
+            Metrics::default(),
+            thresholds,
+            t0(),
+        );
+        // all_time rises while boot_best drops in the same poll: benign firmware reset
+        let out = detect_changes(
+            &mut state,
+            displayed(12.0, 3.0),
+            Metrics::default(),
+            thresholds,
+            t0(),
+        );
+        assert!(!out.boot_detected);
+        assert_eq!(out.new_device_all_time_best, Some(12.0));
+        // boot_best drop without all_time movement is still a reboot
+        let out = detect_changes(
+            &mut state,
+            displayed(12.0, 1.0),
+            Metrics::default(),
+            thresholds,
+            t0(),
+        );
+        assert!(out.boot_detected);
+
+        // without the option the same pattern is treated as a reboot
+        let mut state = MonitorState::new();
+        detect_changes(
+            &mut state,
+            displayed(10.0, 8.0),
+            Metrics::default(),
+            Thresholds::default(),
+            t0(),
+        );
+        let out = detect_changes(
+            &mut state,
+            displayed(12.0, 3.0),
+            Metrics::default(),
+            Thresholds::default(),
+            t0(),
+        );
+        assert!(out.boot_detected);
+    }
+
+    #[test]
+    fn test_uptime_drop_still_reboot_when_boot_best_reset_ignored() {
+        let thresholds = Thresholds {
+            ignore_boot_best_reset_on_all_time_update: true,
+            ..Default::default()
+        };
+        let up = |secs: u64| Metrics {
+            uptime_secs: Some(secs),
+            ..Default::default()
+        };
+        let mut state = MonitorState::new();
+        let d1 = Displayed {
+            all_time: 10.0,
+            boot_best: 8.0,
+        };
+        detect_changes(&mut state, d1, up(5000), thresholds, t0());
+        // all_time rises too, but the uptime drop is a real reboot
+        let d2 = Displayed {
+            all_time: 12.0,
+            boot_best: 3.0,
+        };
+        let out = detect_changes(&mut state, d2, up(20), thresholds, t0());
+        assert!(out.boot_detected);
+    }
+
+    #[test]
+    fn test_implausible_uptime_rejected() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/all_time".into(),
+            json_pointer_boot_best: "/boot_best".into(),
+            json_pointer_uptime_secs: Some("/uptime".into()),
+            ..Default::default()
+        };
+        let max = 10 * 365 * 24 * 3600;
+        let uptime_after = |uptime: Value| {
+            let json = serde_json::json!({ "all_time": 1, "boot_best": 1, "uptime": uptime });
+            let mut m = extract_metrics_from_json(&json, &ptrs).unwrap();
+            let rejected = take_implausible_uptime(&mut m, max);
+            (m.uptime_secs, rejected)
+        };
+
+        // three years of uptime is large but real
+        let three_years = 3 * 365 * 24 * 3600u64;
+        assert_eq!(uptime_after(three_years.into()), (Some(three_years), None));
+        // near u64::MAX, as a number or a string
+        assert_eq!(uptime_after(u64::MAX.into()), (None, Some(u64::MAX)));
+        assert_eq!(
+            uptime_after("18446744073709551610".into()),
+            (None, Some(18446744073709551610))
+        );
+        // negative values are rejected outright instead of wrapping to a huge uptime
+        for uptime in [Value::from("-5"), Value::from(-5)] {
+            let json = serde_json::json!({ "all_time": 1, "boot_best": 1, "uptime": uptime });
+            let err = extract_metrics_from_json(&json, &ptrs).unwrap_err();
+            assert!(
+                format!("{:#}", err).contains("negative value -5"),
+                "{:#}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_absurd_hashrate_spike_does_not_set_best() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/all_time".into(),
+            json_pointer_boot_best: "/boot_best".into(),
+            json_pointer_hashrate_ths: Some("/hashrate".into()),
+            json_pointer_power_w: Some("/power".into()),
+            json_pointer_temp_c: Some("/temp".into()),
+            ..Default::default()
+        };
+        let mut state = MonitorState::new();
+        let mut poll = |hashrate: f64, temp: f64| {
+            let json = serde_json::json!({
+                "all_time": 1, "boot_best": 1, "hashrate": hashrate, "power": 18.0, "temp": temp
+            });
+            let mut m = extract_metrics_from_json(&json, &ptrs).unwrap();
+            let rejected = take_outliers(&mut m, Some(100.0), Some(150.0));
+            let (displayed, metrics) = m.detection_inputs();
+            detect_changes(&mut state, displayed, metrics, Thresholds::default(), t0());
+            rejected
+        };
+
+        assert!(poll(1.5, 60.0).is_empty());
+        assert_eq!(poll(9999.0, 61.0), [("hashrate_ths", 9999.0, 100.0)]);
+        assert_eq!(poll(1.25, 500.0), [("temp_c", 500.0, 150.0)]);
+        assert_eq!(state.tool_best_hashrate_ths, Some(1.5));
+        assert_eq!(state.tool_best_efficiency_j_per_th, Some(12.0));
+        assert_eq!(state.tool_max_temp_c, Some(61.0));
+    }
+
+    #[test]
+    fn test_efficiency_trend_slope() {
+        // exact line: y = 2x + 1
+        let slope = least_squares_slope(&[(0.0, 1.0), (1.0, 3.0), (2.0, 5.0), (3.0, 7.0)]);
+        assert!((slope.unwrap() - 2.0).abs() < 1e-9);
+        assert_eq!(least_squares_slope(&[(1.0, 1.0)]), None);
+        assert_eq!(least_squares_slope(&[(1.0, 1.0), (1.0, 2.0)]), None);
+
+        // efficiency rising 0.5 J/TH every 10 minutes = 3 J/TH per hour; the window keeps the last 4
+        let mut trend = EfficiencyTrend::default();
+        for i in 0..6i64 {
+            trend.push(1_700_000_000 + i * 600, 16.0 + 0.5 * i as f64, 4);
+        }
+        assert_eq!(trend.samples.len(), 4);
+        assert!((trend.slope_per_hour().unwrap() - 3.0).abs() < 1e-9);
+
+        // drift fires once while above the limit, then re-arms after a flat stretch
+        let crossed = trend.drift_crossed(1.0, 4).expect("drift event");
+        assert!((crossed - 3.0).abs() < 1e-9);
+        assert_eq!(trend.drift_crossed(1.0, 4), None);
+        for i in 6..10i64 {
+            trend.push(1_700_000_000 + i * 600, 18.5, 4);
+        }
+        assert_eq!(trend.drift_crossed(1.0, 4), None);
+        assert!(!trend.drift_active);
+    }
+
+    #[test]
+    fn test_temperature_extracted_and_max_tracked() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/all_time".into(),
+            json_pointer_boot_best: "/boot_best".into(),
+            json_pointer_temp_c: Some("/temp".into()),
+            ..Default::default()
+        };
+        let mut state = MonitorState::new();
+        let mut poll = |temp: serde_json::Value| {
+            let json = serde_json::json!({ "all_time": 1, "boot_best": 1, "temp": temp });
+            let m = extract_metrics_from_json(&json, &ptrs).unwrap();
+            let metrics = Metrics {
+                temp_c: m.temp_c,
+                ..Default::default()
+            };
+            detect_changes(
+                &mut state,
+                Displayed {
+                    all_time: 1.0,
+                    boot_best: 1.0,
+                },
+                metrics,
+                Thresholds::default(),
+                t0(),
+            )
+        };
+        assert_eq!(poll(55.5.into()).new_tool_max_temp_c, Some(55.5));
+        assert_eq!(poll("61.25".into()).new_tool_max_temp_c, Some(61.25));
+        // cooler readings leave the max alone
+        assert!(poll(58.into()).new_tool_max_temp_c.is_none());
+        assert_eq!(state.tool_max_temp_c, Some(61.25));
+    }
+
+    #[test]
+    fn test_best_timestamps_set_with_bests() {
+        // state files from before the *_at fields still load
+        let mut state: MonitorState =
+            serde_json::from_str(r#"{"tool_best_hashrate_ths": 1.2}"#).unwrap();
+        assert_eq!(state.tool_best_hashrate_ths_at, None);
+        assert_eq!(set_at_suffix(state.tool_best_hashrate_ths_at), "");
+
+        let poll = |state: &mut MonitorState, hashrate: f64| {
+            detect_changes(
+                state,
+                Displayed::default(),
+                Metrics {
+                    hashrate_ths: Some(hashrate),
+                    ..Default::default()
+                },
+                Thresholds::default(),
+                t0(),
+            )
+        };
+        poll(&mut state, 1.5);
+        let set_at = state.tool_best_hashrate_ths_at.expect("stamped");
+        assert_eq!(set_at, t0());
+        // a lower reading leaves the best and its time alone
+        poll(&mut state, 1.4);
+        assert_eq!(state.tool_best_hashrate_ths_at, Some(set_at));
+
+        let t = DateTime::from_timestamp(1_709_388_600, 0).unwrap();
+        assert_eq!(set_at_suffix(Some(t)), " (set 2024-03-02 14:10 UTC)");
+    }
+
+    #[test]
+    fn test_power_min_max_tracked() {
+        let mut state = MonitorState::new();
+        let mut poll = |power: f64| {
+            let metrics = Metrics {
+                power_w: Some(power),
+                ..Default::default()
+            };
+            let out = detect_changes(
+                &mut state,
+                Displayed::default(),
+                metrics,
+                Thresholds::default(),
+                t0(),
+            );
+            (out.new_tool_min_power_w, out.new_tool_max_power_w)
+        };
+        assert_eq!(poll(14.0), (Some(14.0), Some(14.0)));
+        assert_eq!(poll(16.5), (None, Some(16.5)));
+        assert_eq!(poll(12.0), (Some(12.0), None));
+        // within the range: nothing new, and a bad reading is ignored
+        assert_eq!(poll(15.0), (None, None));
+        assert_eq!(poll(f64::NAN), (None, None));
+        assert_eq!(state.tool_min_power_w, Some(12.0));
+        assert_eq!(state.tool_max_power_w, Some(16.5));
+    }
+
+    #[test]
+    fn test_temp_alert_fires_once_per_crossing() {
+        let mut state = MonitorState::new();
+        let thresholds = Thresholds {
+            temp_alert_c: Some(70.0),
+            ..Default::default()
+        };
+        let mut poll = |temp: f64| {
+            let metrics = Metrics {
+                temp_c: Some(temp),
+                ..Default::default()
+            };
+            detect_changes(&mut state, Displayed::default(), metrics, thresholds, t0()).temp_alert
+        };
+        assert_eq!(poll(65.0), None);
+        assert_eq!(poll(72.5), Some((72.5, 70.0)));
+        // still hot: no repeat
+        assert_eq!(poll(74.0), None);
+        // cools down to the threshold, then crosses again
+        assert_eq!(poll(70.0), None);
+        assert_eq!(poll(71.0), Some((71.0, 70.0)));
+    }
+
+    #[test]
+    fn test_temp_calibration_feeds_max_tracking_and_alerts() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/best".into(),
+            json_pointer_boot_best: "/session".into(),
+            json_pointer_temp_c: Some("/temp".into()),
+            temp_scale: Some(0.5),
+            temp_offset_c: Some(40.0),
+            ..Default::default()
+        };
+        let mut state = MonitorState::new();
+        let thresholds = Thresholds {
+            temp_alert_c: Some(70.0),
+            ..Default::default()
+        };
+        let mut poll = |raw: f64| {
+            let json = serde_json::json!({ "best": 1.0, "session": 1.0, "temp": raw });
+            let m = extract_metrics_from_json(&json, &ptrs).unwrap();
+            let (displayed, metrics) = m.detection_inputs();
+            let out = detect_changes(&mut state, displayed, metrics, thresholds, t0());
+            (
+                m.temp_c,
+                m.temp_c_raw,
+                out.new_tool_max_temp_c,
+                out.temp_alert,
+            )
+        };
+        // 56 raw reads as 68 °C: a new max, below the alert
+        assert_eq!(poll(56.0), (Some(68.0), Some(56.0), Some(68.0), None));
+        // 62 raw is 71 °C, over the threshold although the raw value is not
+        assert_eq!(
+            poll(62.0),
+            (Some(71.0), Some(62.0), Some(71.0), Some((71.0, 70.0)))
+        );
+        assert_eq!(state.tool_max_temp_c, Some(71.0));
+
+        // without calibration the reading is used as is and no raw value is kept
+        let m = extract_metrics_from_json(
+            &serde_json::json!({ "best": 1.0, "session": 1.0, "temp": 62.0 }),
+            &JsonPointers {
+                temp_scale: None,
+                temp_offset_c: None,
+                ..ptrs.clone()
+            },
+        )
+        .unwrap();
+        assert_eq!((m.temp_c, m.temp_c_raw), (Some(62.0), None));
+    }
+
+    #[test]
+    fn test_efficiency_degraded_against_rolling_median() {
+        let mut state = MonitorState::new();
+        let thresholds = Thresholds {
+            efficiency_degraded_pct: Some(10.0),
+            efficiency_baseline_window: 3,
+            ..Default::default()
+        };
+        let mut poll = |eff: f64, uptime: u64| {
+            let metrics = Metrics {
+                uptime_secs: Some(uptime),
+                efficiency_j_per_th: Some(eff),
+                ..Default::default()
+            };
+            detect_changes(&mut state, Displayed::default(), metrics, thresholds, t0())
+                .efficiency_degraded
+        };
+        // no verdict until the window is full
+        assert_eq!(poll(20.0, 100), None);
+        assert_eq!(poll(15.0, 110), None);
+        assert_eq!(poll(15.5, 120), None);
+        // median of [20, 15, 15.5] is 15.5; 17 is within 10%, 17.2 is not
+        assert_eq!(poll(17.0, 130), None);
+        assert_eq!(poll(17.2, 140), Some((17.2, 15.5)));
+        // still degraded: no repeat until it recovers
+        assert_eq!(poll(18.0, 150), None);
+        assert_eq!(poll(15.0, 160), None);
+
+        // a reboot starts the baseline over
+        assert_eq!(poll(30.0, 5), None);
+        assert_eq!(state.efficiency_baseline, [30.0]);
+    }
+
+    #[test]
+    fn test_hashrate_stall_is_edge_triggered() {
+        let mut state = MonitorState::new();
+        let thresholds = Thresholds {
+            min_hashrate_ths: Some(0.1),
+            stall_polls: 2,
+            ..Default::default()
+        };
+        let mut poll = |h: Option<f64>| {
+            let metrics = Metrics {
+                hashrate_ths: h,
+                ..Default::default()
+            };
+            let out = detect_changes(&mut state, Displayed::default(), metrics, thresholds, t0());
+            (out.hashrate_stalled, out.hashrate_recovered)
+        };
+        assert_eq!(poll(Some(1.2)), (None, None));
+        assert_eq!(poll(Some(0.0)), (None, None));
+        // a poll without a reading neither counts nor resets
+        assert_eq!(poll(None), (None, None));
+        assert_eq!(poll(Some(0.05)), (Some((0.05, 0.1)), None));
+        assert_eq!(poll(Some(0.0)), (None, None));
+        assert_eq!(poll(Some(1.1)), (None, Some(1.1)));
+        assert_eq!(poll(Some(1.2)), (None, None));
+        assert_eq!(state.hashrate_below_min_polls, 0);
+    }
+
+    #[test]
+    fn test_efficiency_consistency_check() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/bestDiff".into(),
+            json_pointer_boot_best: "/bestSessionDiff".into(),
+            json_pointer_hashrate_ths: Some("/hashRate".into()),
+            json_pointer_efficiency_j_per_th: Some("/efficiency".into()),
+            json_pointer_power_w: Some("/power".into()),
+            hashrate_scale: Some(0.001),
+            ..Default::default()
+        };
+        // 18 W at 1.2 TH/s is 15 J/TH, matching the reported value
+        let consistent = serde_json::json!({
+            "bestDiff": 1, "bestSessionDiff": 1, "hashRate": 1200.0, "power": 18.0, "efficiency": 15.1
+        });
+        let m = extract_metrics_from_json(&consistent, &ptrs).unwrap();
+        assert_eq!(m.power_w, Some(18.0));
+        assert!(efficiency_consistency_warning(&m, 5.0).is_none());
+
+        // power and efficiency pointers swapped: 15.1 W / 1.2 TH/s vs 18 "J/TH"
+        let swapped_ptrs = JsonPointers {
+            json_pointer_efficiency_j_per_th: Some("/power".into()),
+            json_pointer_power_w: Some("/efficiency".into()),
+            ..ptrs
+        };
+        let m = extract_metrics_from_json(&consistent, &swapped_ptrs).unwrap();
+        let warning = efficiency_consistency_warning(&m, 5.0).expect("warning");
+        assert!(warning.contains("differs from power/hashrate"));
+
+        // without a device efficiency there is nothing to compare
+        let m = ExtractedMetrics {
+            power_w: Some(18.0),
+            hashrate_ths: Some(1.2),
+            ..Default::default()
+        };
+        assert!(efficiency_consistency_warning(&m, 5.0).is_none());
+    }
+
+    #[test]
+    fn test_reboot_grace_reports_one_rebooting_and_downtime() {
+        let t0 = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let at = |secs: i64| t0 + chrono::Duration::seconds(secs);
+        let mut grace = RebootGrace::default();
+
+        // no reboot seen yet: failures are ordinary poll errors
+        assert_eq!(grace.on_failure(t0), None);
+
+        grace.start(t0, 60);
+        assert!(grace.active(at(10)));
+        assert_eq!(grace.on_failure(at(10)), Some(true));
+        assert_eq!(grace.on_failure(at(20)), Some(false));
+        assert_eq!(grace.on_failure(at(30)), Some(false));
+        assert_eq!(grace.on_success(at(40)), Some(30.0));
+
+        // the window closes once the device is back; a later failure is a normal poll error
+        assert!(!grace.active(at(45)));
+        assert_eq!(grace.on_failure(at(45)), None);
+        assert_eq!(grace.on_success(at(50)), None);
+
+        // failures after the window expires are not suppressed
+        grace.start(at(100), 5);
+        assert_eq!(grace.on_failure(at(106)), None);
+    }
+
+    #[test]
+    fn test_rolling_hashrate_average_survives_restart() {
+        let mut state = MonitorState::new();
+        assert_eq!(state.rolling_avg_hashrate_ths(), None);
+        for h in [1.0, 2.0, 3.0, 4.0] {
+            state.push_hashrate_sample(h, 3);
+        }
+        // only the last three samples count
+        assert_eq!(state.rolling_avg_hashrate_ths(), Some(3.0));
+
+        let json = serde_json::to_string(&state).unwrap();
+        let mut restored: MonitorState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.rolling_avg_hashrate_ths(), Some(3.0));
+
+        // a smaller window after restart trims the saved samples
+        restored.push_hashrate_sample(6.0, 2);
+        assert_eq!(restored.recent_hashrate_ths.len(), 2);
+        assert_eq!(restored.rolling_avg_hashrate_ths(), Some(5.0));
+    }
+
+    #[test]
+    fn test_reset_clears_selected_bests() {
+        let mut state = MonitorState::new();
+        state.tool_best_hashrate_ths = Some(1.5);
+        state.tool_best_efficiency_j_per_th = Some(15.0);
+        state.tool_global_all_time_best = 9.0;
+        state.last_displayed_all_time = Some(9.0);
+        state.config_hash = Some("abc".to_string());
+
+        assert_eq!(
+            state.reset(true, false, false),
+            vec!["tool_best_hashrate_ths"]
+        );
+        assert_eq!(state.tool_best_hashrate_ths, None);
+        assert_eq!(state.tool_best_efficiency_j_per_th, Some(15.0));
+        assert_eq!(state.tool_global_all_time_best, 9.0);
+
+        assert_eq!(state.reset(false, false, true), vec!["all"]);
+        assert_eq!(state.tool_best_efficiency_j_per_th, None);
+        assert_eq!(state.tool_global_all_time_best, 0.0);
+        assert_eq!(state.last_displayed_all_time, None);
+        assert_eq!(state.config_hash.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn test_reject_ratio_alert_and_reboot_baseline() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/all_time".into(),
+            json_pointer_boot_best: "/boot_best".into(),
+            json_pointer_uptime_secs: Some("/uptime".into()),
+            json_pointer_shares_accepted: Some("/sharesAccepted".into()),
+            json_pointer_shares_rejected: Some("/sharesRejected".into()),
+            ..Default::default()
+        };
+        let thresholds = Thresholds {
+            reject_ratio_alert: Some(0.05),
+            ..Default::default()
+        };
+        let mut state = MonitorState::new();
+        let mut poll = |uptime: u64, accepted: u64, rejected: u64| {
+            let json = serde_json::json!({
+                "all_time": 1, "boot_best": 1, "uptime": uptime,
+                "sharesAccepted": accepted, "sharesRejected": rejected.to_string()
+            });
+            let m = extract_metrics_from_json(&json, &ptrs).unwrap();
+            let metrics = Metrics {
+                uptime_secs: m.uptime_secs,
+                shares_accepted: m.shares_accepted,
+                shares_rejected: m.shares_rejected,
+                ..Default::default()
+            };
+            detect_changes(
+                &mut state,
+                Displayed {
+                    all_time: 1.0,
+                    boot_best: 1.0,
+                },
+                metrics,
+                thresholds,
+                t0(),
+            )
+        };
+        // first poll only sets the baseline, however bad the lifetime ratio is
+        assert_eq!(poll(100, 10, 10).high_reject_ratio, None);
+        // 2 of 100 new shares rejected
+        assert_eq!(poll(200, 108, 12).high_reject_ratio, None);
+        // 10 of 50 rejected
+        assert_eq!(poll(300, 148, 22).high_reject_ratio, Some((0.2, 0.05)));
+        // no new shares: nothing to judge
+        assert_eq!(poll(400, 148, 22).high_reject_ratio, None);
+
+        // the counters restart with the device; the reboot poll re-baselines instead of alerting
+        let out = poll(10, 5, 3);
+        assert!(out.boot_detected);
+        assert_eq!(out.high_reject_ratio, None);
+        assert_eq!(poll(110, 105, 3).high_reject_ratio, None);
+        assert_eq!(
+            (state.last_shares_accepted, state.last_shares_rejected),
+            (Some(105), Some(3))
+        );
+    }
+
+    #[test]
+    fn test_metric_availability_transitions() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/all_time".into(),
+            json_pointer_boot_best: "/boot_best".into(),
+            json_pointer_temp_c: Some("/temp".into()),
+            json_pointer_hashrate_ths: Some("/hashRate".into()),
+            ..Default::default()
+        };
+        let mut state = MonitorState::new();
+        let mut poll = |json: serde_json::Value| {
+            let changes = update_metric_availability(&mut state, &json, &ptrs);
+            // the poll still extracts whatever is present
+            let m =
+                extract_metrics_from_json(&json, &without_missing_optional(&json, &ptrs)).unwrap();
+            (changes, m.temp_c)
+        };
+
+        // hashrate appears right away; temp is still warming up (missing, then null)
+        let (changes, temp) =
+            poll(serde_json::json!({ "all_time": 1, "boot_best": 1, "hashRate": 1.1 }));
+        assert_eq!(changes, vec![("json_pointer_hashrate_ths", true)]);
+        assert_eq!(temp, None);
+        let (changes, _) = poll(
+            serde_json::json!({ "all_time": 1, "boot_best": 1, "hashRate": 1.1, "temp": null }),
+        );
+        assert!(changes.is_empty());
+
+        // temp shows up mid-run
+        let (changes, temp) = poll(
+            serde_json::json!({ "all_time": 1, "boot_best": 1, "hashRate": 1.1, "temp": 52.0 }),
+        );
+        assert_eq!(changes, vec![("json_pointer_temp_c", true)]);
+        assert_eq!(temp, Some(52.0));
+
+        // and later vanishes again, once
+        let json = serde_json::json!({ "all_time": 1, "boot_best": 1, "hashRate": 1.1 });
+        assert_eq!(poll(json.clone()).0, vec![("json_pointer_temp_c", false)]);
+        assert!(poll(json).0.is_empty());
+        assert_eq!(
+            state.metric_availability.get("json_pointer_temp_c"),
+            Some(&false)
+        );
+    }
+
+    #[test]
+    fn test_power_from_voltage_and_current_feeds_efficiency() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/bestDiff".into(),
+            json_pointer_boot_best: "/bestSessionDiff".into(),
+            json_pointer_hashrate_ths: Some("/hashRate".into()),
+            json_pointer_voltage_v: Some("/voltage".into()),
+            json_pointer_current_a: Some("/current".into()),
+            ..Default::default()
+        };
+        // 12 V * 1.5 A = 18 W at 1.2 TH/s is 15 J/TH
+        let json = serde_json::json!({
+            "bestDiff": 1, "bestSessionDiff": 1, "hashRate": 1.2, "voltage": 12.0, "current": "1.5"
+        });
+        let m = extract_metrics_from_json(&json, &ptrs).unwrap();
+        assert_eq!(m.power_w, Some(18.0));
+        assert!((m.efficiency_j_per_th.unwrap() - 15.0).abs() < 1e-9);
+
+        // a reported power wins over V * I
+        let with_power = JsonPointers {
+            json_pointer_power_w: Some("/power".into()),
+            ..ptrs.clone()
+        };
+        let mut json_power = json.clone();
+        json_power["power"] = serde_json::json!(20.0);
+        let m = extract_metrics_from_json(&json_power, &with_power).unwrap();
+        assert_eq!(m.power_w, Some(20.0));
+    }
+
+    #[test]
+    fn test_power_scale_converts_milliwatts() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/bestDiff".into(),
+            json_pointer_boot_best: "/bestSessionDiff".into(),
+            json_pointer_hashrate_ths: Some("/hashRate".into()),
+            json_pointer_power_w: Some("/power".into()),
+            power_scale: Some(0.001),
+            ..Default::default()
+        };
+        // 18000 mW at 1.5 TH/s is 12 J/TH
+        let json = serde_json::json!({
+            "bestDiff": 1, "bestSessionDiff": 1, "hashRate": 1.5, "power": 18000
+        });
+        let m = extract_metrics_from_json(&json, &ptrs).unwrap();
+        assert!((m.power_w.unwrap() - 18.0).abs() < 1e-9);
+        assert!((m.efficiency_j_per_th.unwrap() - 12.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_negative_current_gives_no_power() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/bestDiff".into(),
+            json_pointer_boot_best: "/bestSessionDiff".into(),
+            json_pointer_hashrate_ths: Some("/hashRate".into()),
+            json_pointer_voltage_v: Some("/voltage".into()),
+            json_pointer_current_a: Some("/current".into()),
+            ..Default::default()
+        };
+        let json = serde_json::json!({
+            "bestDiff": 1, "bestSessionDiff": 1, "hashRate": 1.2, "voltage": 12.0, "current": -1.5
+        });
+        let m = extract_metrics_from_json(&json, &ptrs).unwrap();
+        assert_eq!(m.power_w, None);
+        assert_eq!(m.efficiency_j_per_th, None);
+        assert_eq!(super::power_from_voltage_current(f64::NAN, 1.0), None);
+    }
+
+    #[test]
+    fn test_parse_number_with_binary_unit() {
+        let dot = DecimalSeparator::Dot;
+        assert_eq!(super::parse_number_with_unit("1Ki", dot).unwrap(), 1024.0);
+        assert_eq!(
+            super::parse_number_with_unit("2Mi", dot).unwrap(),
+            2.0 * 1024.0 * 1024.0
+        );
+        assert_eq!(
+            super::parse_number_with_unit("1.5Gi", dot).unwrap(),
+            1.5 * 2f64.powi(30)
+        );
+        assert_eq!(
+            super::parse_number_with_unit("1Ti", dot).unwrap(),
+            2f64.powi(40)
+        );
+        // the decimal suffixes keep their meaning
+        assert_eq!(super::parse_number_with_unit("1G", dot).unwrap(), 1e9);
+        assert_eq!(super::parse_number_with_unit("1K", dot).unwrap(), 1e3);
+        // "i" alone is not a unit
+        assert!(super::parse_number_with_unit("1i", dot).is_err());
+        assert!(super::parse_number_with_unit("1Xi", dot).is_err());
+    }
+
+    #[test]
+    fn test_jsonpath_selectors() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "$.bestDiff".into(),
+            json_pointer_boot_best: "$.workers[?@.name=='main'].bestSessionDiff".into(),
+            json_pointer_hashrate_ths: Some("$.workers[?@.name=='main'].hashRate".into()),
+            // matches every worker: the first one in document order is used
+            json_pointer_temp_c: Some("$.workers[*].temp".into()),
+            selector_kind: Some(SelectorKind::JsonPath),
+            ..Default::default()
+        };
+        let json = serde_json::json!({
+            "bestDiff": "2.5M",
+            "workers": [
+                { "name": "aux", "bestSessionDiff": 7, "hashRate": 0.4, "temp": 48.0 },
+                { "name": "main", "bestSessionDiff": 9, "hashRate": 1.2, "temp": 61.0 }
+            ]
+        });
+        let m = extract_metrics_from_json(&json, &ptrs).unwrap();
+        assert_eq!(m.displayed_all_time, 2.5e6);
+        assert_eq!(m.displayed_boot_best, 9.0);
+        assert_eq!(m.hashrate_ths, Some(1.2));
+        assert_eq!(m.temp_c, Some(48.0));
+
+        // a path that matches nothing fails like a missing pointer
+        let missing = JsonPointers {
+            json_pointer_hashrate_ths: Some("$.workers[?@.name=='gone'].hashRate".into()),
+            ..ptrs
+        };
+        let err = extract_metrics_from_json(&json, &missing).unwrap_err();
+        assert!(format!("{}", err).contains("not found"), "{}", err);
+    }
+
+    #[test]
+    fn test_boot_flapping_enters_and_clears() {
+        let t0 = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let at = |secs: i64| t0 + chrono::Duration::seconds(secs);
+        let mut flap = BootFlapping::default();
+        // two reboots in ten minutes are within the limit
+        assert_eq!(flap.record(at(0), true, 600, 2), None);
+        assert_eq!(flap.record(at(60), false, 600, 2), None);
+        assert_eq!(flap.record(at(120), true, 600, 2), None);
+        assert!(!flap.active());
+
+        // the third one starts flapping, reported once
+        assert_eq!(
+            flap.record(at(180), true, 600, 2),
+            Some(FlapChange::Started { boots: 3 })
+        );
+        assert!(flap.active());
+        assert_eq!(flap.record(at(240), true, 600, 2), None);
+        assert!(flap.active());
+
+        // still flapping while any reboot is inside the window
+        assert_eq!(flap.record(at(700), false, 600, 2), None);
+        assert!(flap.active());
+        // the last reboot (at 240) has aged out
+        assert_eq!(
+            flap.record(at(840), false, 600, 2),
+            Some(FlapChange::Cleared)
+        );
+        assert!(!flap.active());
+        assert_eq!(flap.record(at(900), false, 600, 2), None);
+    }
+
+    #[test]
+    fn test_histogram_bucket_counts() {
+        let mut state = MonitorState::new();
+        let edges = [1.0, 1.5, 2.0];
+        // a throttling device: one cluster near 1.2 TH/s and another near 1.9
+        for v in [0.9, 1.2, 1.2, 1.4, 1.9, 2.5, 1.0, f64::NAN] {
+            state.observe_histogram("hashrate_ths", &edges, v);
+        }
+        let hist = &state.histograms["hashrate_ths"];
+        assert_eq!(hist.counts, vec![2, 3, 1, 1]);
+        assert_eq!(hist.cumulative(), vec![2, 5, 6, 7]);
+        assert_eq!(hist.count(), 7);
+        assert!((hist.sum - 10.1).abs() < 1e-9);
+        assert_eq!(
+            hist.render_ascii(6),
+            "<= 1   |####  | 2\n<= 1.5 |######| 3\n<= 2   |##    | 1\n> 2    |##    | 1\n"
+        );
+
+        // the buckets survive a save/load round trip
+        let restored: MonitorState =
+            serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+        assert_eq!(restored.histograms["hashrate_ths"], *hist);
+
+        // new edges start over instead of mixing incompatible buckets
+        state.observe_histogram("hashrate_ths", &[1.0, 2.0], 1.2);
+        assert_eq!(state.histograms["hashrate_ths"].counts, vec![0, 1, 0]);
+    }
+
+    #[test]
+    fn test_sparkline_known_inputs() {
+        assert_eq!(sparkline(&[]), "");
+        assert_eq!(
+            sparkline(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]),
+            "▁▂▃▄▅▆▇█"
+        );
+        assert_eq!(sparkline(&[1.2, 1.0, 1.1]), "█▁▅");
+        // a flat window sits in the middle; non-finite samples are skipped
+        assert_eq!(sparkline(&[1.5, f64::NAN, 1.5]), "▄▄");
+    }
+
+    #[test]
+    fn test_validate_pointers_sample() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/bestDiff".into(),
+            json_pointer_boot_best: "/bestSessionDiff".into(),
+            json_pointer_hashrate_ths: Some("/hashRate".into()),
+            hashrate_scale: Some(0.001),
+            ..Default::default()
+        };
+        let sample =
+            serde_json::json!({ "bestDiff": "1.2G", "bestSessionDiff": 5, "hashRate": 1200.0 });
+        let lines = validate_pointers_sample(&sample, &ptrs, TrackedMetrics::default()).unwrap();
+        assert!(lines.contains(&"json_pointer_hashrate_ths (/hashRate) = 1200.0".to_string()));
+        assert!(lines.contains(&"hashrate (scaled): 1.2000 TH/s".to_string()));
+
+        // a typo names the pointer that failed
+        let typo = JsonPointers {
+            json_pointer_hashrate_ths: Some("/hashrate".into()),
+            ..ptrs
+        };
+        let msg = format!(
+            "{:#}",
+            validate_pointers_sample(&sample, &typo, TrackedMetrics::default()).unwrap_err()
+        );
+        assert!(msg.contains("/hashrate"), "{}", msg);
+    }
+
+    #[test]
+    fn test_disabled_metrics_are_not_extracted_or_tracked() {
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/all_time".into(),
+            json_pointer_boot_best: "/boot_best".into(),
+            json_pointer_hashrate_ths: Some("/hashRate".into()),
+            json_pointer_power_w: Some("/power".into()),
+            json_pointer_temp_c: Some("/missing_temp".into()),
+            ..Default::default()
+        };
+        let json =
+            serde_json::json!({ "all_time": 5, "boot_best": 5, "hashRate": 1.2, "power": 18.0 });
+        // with every metric on, the stale temp pointer fails the poll
+        assert!(extract_metrics_from_json(&json, &ptrs).is_err());
+
+        let tracked = TrackedMetrics::from_config(Some(&MetricsConfig {
+            track_efficiency: Some(false),
+            track_temp: Some(false),
+            ..Default::default()
+        }));
+        let m = extract_tracked_metrics(&json, &ptrs, tracked).unwrap();
+        assert_eq!(m.hashrate_ths, Some(1.2));
+        // no power-based fallback either
+        assert_eq!((m.efficiency_j_per_th, m.temp_c), (None, None));
+        assert_eq!(m.power_w, Some(18.0));
+
+        let mut state = MonitorState::new();
+        let out = detect_changes(
+            &mut state,
+            Displayed::default(),
+            Metrics {
+                hashrate_ths: m.hashrate_ths,
+                efficiency_j_per_th: m.efficiency_j_per_th,
+                temp_c: m.temp_c,
+                ..Default::default()
+            },
+            Thresholds::default(),
+            t0(),
+        );
+        assert!(out.new_tool_best_hashrate_ths.is_some());
+        assert!(out.new_tool_best_efficiency_j_per_th.is_none());
+        assert!(out.new_tool_max_temp_c.is_none());
+    }
+
+    #[test]
+    fn test_detect_changes_stamps_bests_with_given_time() {
+        let mut state = MonitorState::new();
+        let t0 = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let out = detect_changes(
+            &mut state,
+            Displayed {
+                all_time: 10.0,
+                boot_best: 4.0,
+            },
+            Metrics {
+                uptime_secs: Some(600),
+                hashrate_ths: Some(1.2),
+                ..Default::default()
+            },
+            Thresholds::from_config(None),
+            t0,
+        );
+        assert_eq!(out.new_tool_all_time_best, Some(10.0));
+        assert_eq!(state.tool_global_all_time_best_at, Some(t0));
+        assert_eq!(state.tool_best_hashrate_ths_at, Some(t0));
+    }
+
+    #[test]
+    fn test_repeated_errors_collapse_into_runs() {
+        let mut runs = RepeatedErrors::default();
+        let t0 = Utc::now();
+        assert_eq!(runs.on_error(t0, "timed out"), (true, None));
+        assert_eq!(runs.on_error(t0, "timed out"), (false, None));
+        assert_eq!(runs.on_error(t0, "timed out"), (false, None));
+        // a different error is logged and closes the previous run
+        assert_eq!(
+            runs.on_error(t0, "connection refused"),
+            (true, Some(("timed out".to_string(), t0, 3)))
+        );
+        // a single failure is not summarized
+        assert_eq!(runs.on_success(), None);
+        runs.on_error(t0, "timed out");
+        runs.on_error(t0, "timed out");
+        assert_eq!(runs.on_success(), Some(("timed out".to_string(), t0, 2)));
+    }
+
+    #[test]
+    fn test_circuit_breaker_opens_once_and_closes_on_success() {
+        let mut circuit = CircuitBreaker::default();
+        let t0 = Utc::now();
+        // a success before the threshold closes nothing
+        assert!(!circuit.on_failure(t0, 3));
+        assert_eq!(circuit.on_success(), None);
+
+        assert!(!circuit.on_failure(t0, 3));
+        assert!(!circuit.on_failure(t0, 3));
+        let t1 = t0 + chrono::Duration::seconds(20);
+        assert!(circuit.on_failure(t1, 3));
+        assert!(circuit.is_open());
+        // further failures keep it open without opening it again
+        assert!(!circuit.on_failure(t1 + chrono::Duration::seconds(300), 3));
+        assert_eq!(circuit.on_success(), Some((t1, 4)));
+        assert!(!circuit.is_open());
+        assert_eq!(circuit.failures, 0);
+    }
 }
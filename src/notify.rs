@@ -0,0 +1,925 @@
+use crate::config::{
+    DiscordConfig, NotificationsConfig, NotifyMode, TelegramConfig, WebhookConfig,
+};
+use crate::metrics::{DailyDigest, LastNotified};
+use crate::outcome::with_severity;
+use crate::persist::{append_event_jsonl, EventLog};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Client;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::task::JoinHandle;
+
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+//one notification channel; every configured channel gets the events it subscribes to
+pub trait Notifier: Send + Sync {
+    //written as "target" on notify_error events
+    fn name(&self) -> &'static str;
+    //event names this channel forwards
+    fn events(&self) -> &[String];
+    fn mode(&self) -> NotifyMode;
+    fn render(&self, device: Option<&str>, event: &Value) -> String {
+        format_message(device, event)
+    }
+    fn send<'a>(&'a self, client: &'a Client, content: &'a str) -> SendFuture<'a>;
+
+    fn subscribed(&self, event: &Value) -> bool {
+        event
+            .get("event")
+            .and_then(Value::as_str)
+            .is_some_and(|name| self.events().iter().any(|e| e == name))
+    }
+}
+
+impl Notifier for DiscordConfig {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    fn events(&self) -> &[String] {
+        &self.events
+    }
+
+    fn mode(&self) -> NotifyMode {
+        self.mode.unwrap_or_default()
+    }
+
+    fn send<'a>(&'a self, client: &'a Client, content: &'a str) -> SendFuture<'a> {
+        Box::pin(send_discord(client, self, content))
+    }
+}
+
+impl Notifier for TelegramConfig {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    fn events(&self) -> &[String] {
+        &self.events
+    }
+
+    fn mode(&self) -> NotifyMode {
+        self.mode.unwrap_or_default()
+    }
+
+    //phones show the message on its own, so the time it happened goes along
+    fn render(&self, device: Option<&str>, event: &Value) -> String {
+        let line = format_message(device, event);
+        match event.get("ts").and_then(Value::as_str) {
+            Some(ts) => format!("{}\n{}", line, ts),
+            None => line,
+        }
+    }
+
+    fn send<'a>(&'a self, client: &'a Client, content: &'a str) -> SendFuture<'a> {
+        Box::pin(send_telegram(client, self, content))
+    }
+}
+
+impl Notifier for WebhookConfig {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn events(&self) -> &[String] {
+        &self.events
+    }
+
+    fn mode(&self) -> NotifyMode {
+        self.mode.unwrap_or_default()
+    }
+
+    fn render(&self, device: Option<&str>, event: &Value) -> String {
+        let template = self
+            .payload_template
+            .as_deref()
+            .unwrap_or(DEFAULT_PAYLOAD_TEMPLATE);
+        render_payload(template, device, event)
+    }
+
+    fn send<'a>(&'a self, client: &'a Client, content: &'a str) -> SendFuture<'a> {
+        Box::pin(send_webhook(client, self, content))
+    }
+}
+
+//records that move down when they improve; every other new_* record moves up
+const LOWER_IS_BETTER: [&str; 3] = [
+    "new_tool_best_efficiency_j_per_th",
+    "new_tool_min_power_w",
+    "new_tool_worst_hashrate_ths",
+];
+
+//records that start over with each boot, so a reboot forgets what was announced for them
+const BOOT_RECORDS: [&str; 2] = ["new_device_boot_best", "new_session_best_difficulty"];
+
+//with notifications.dedupe: the events still worth announcing. a record event (new_*) whose value is
+//no better than the last one announced for it is dropped, e.g. one detected again after a restart
+//from a state file older than the announcement; the rest are remembered in `last`. other events
+//always pass
+pub fn unannounced(
+    last: &mut BTreeMap<String, LastNotified>,
+    emitted: &[Value],
+    now: DateTime<Utc>,
+) -> Vec<Value> {
+    if emitted
+        .iter()
+        .any(|e| e.get("event").and_then(Value::as_str) == Some("boot_detected"))
+    {
+        last.retain(|name, _| !BOOT_RECORDS.contains(&name.as_str()));
+    }
+    let mut fresh = Vec::new();
+    for event in emitted {
+        let name = event.get("event").and_then(Value::as_str).unwrap_or("");
+        let value = event.get("value").and_then(Value::as_f64);
+        let (true, Some(value)) = (name.starts_with("new_"), value) else {
+            fresh.push(event.clone());
+            continue;
+        };
+        let better = |prev: f64| {
+            if LOWER_IS_BETTER.contains(&name) {
+                value < prev
+            } else {
+                value > prev
+            }
+        };
+        if last.get(name).is_some_and(|prev| !better(prev.value)) {
+            continue;
+        }
+        last.insert(name.to_string(), LastNotified { value, ts: now });
+        fresh.push(event.clone());
+    }
+    fresh
+}
+
+//every channel set up under notifications, in a fixed order
+pub fn configured(cfg: Option<&NotificationsConfig>) -> Vec<Arc<dyn Notifier>> {
+    let mut out: Vec<Arc<dyn Notifier>> = Vec::new();
+    if let Some(d) = cfg.and_then(|c| c.discord.clone()) {
+        out.push(Arc::new(d));
+    }
+    if let Some(t) = cfg.and_then(|c| c.telegram.clone()) {
+        out.push(Arc::new(t));
+    }
+    if let Some(w) = cfg.and_then(|c| c.webhook.clone()) {
+        out.push(Arc::new(w));
+    }
+    out
+}
+
+//one synthetic "test" event through every configured channel, subscribed to it or not; each
+//channel's name comes back with how the send went
+pub async fn send_test(
+    client: &Client,
+    cfg: Option<&NotificationsConfig>,
+    device: Option<&str>,
+    now: DateTime<Utc>,
+) -> Vec<(&'static str, Result<()>)> {
+    let event = serde_json::json!({ "ts": now, "event": "test" });
+    let mut results = Vec::new();
+    for notifier in configured(cfg) {
+        let content = notifier.render(device, &event);
+        results.push((notifier.name(), notifier.send(client, &content).await));
+    }
+    results
+}
+
+//the finished day's summary for every channel in digest mode, sent once when the clock has passed
+//UTC midnight; nothing is sent (and nothing comes back) on any other poll
+pub async fn post_daily_digest(
+    client: &Client,
+    cfg: Option<&NotificationsConfig>,
+    device: Option<&str>,
+    digest: &mut DailyDigest,
+    now: DateTime<Utc>,
+) -> Vec<(&'static str, Result<()>)> {
+    let Some(day) = digest.roll(now) else {
+        return Vec::new();
+    };
+    let event = serde_json::json!({
+        "ts": now,
+        "event": "daily_digest",
+        "day": day.day,
+        "best_hashrate_ths": day.best_hashrate_ths,
+        "best_efficiency_j_per_th": day.best_efficiency_j_per_th,
+        "reboots": day.reboots,
+        "errors": day.errors,
+        "alerts": day.alerts
+    });
+    let mut results = Vec::new();
+    for notifier in configured(cfg)
+        .into_iter()
+        .filter(|n| n.mode() == NotifyMode::Digest)
+    {
+        let content = notifier.render(device, &event);
+        results.push((notifier.name(), notifier.send(client, &content).await));
+    }
+    results
+}
+
+//"Daily digest 2024-01-01: best hashrate 1.2, best efficiency 17.5, 1 reboot(s), 0 error(s),
+//alerts: temp_alert x2"
+fn format_digest(prefix: &str, event: &Value) -> String {
+    let number = |key: &str| {
+        event
+            .get(key)
+            .filter(|v| !v.is_null())
+            .map_or("-".to_string(), |v| v.to_string())
+    };
+    let alerts = event
+        .get("alerts")
+        .and_then(Value::as_object)
+        .filter(|a| !a.is_empty())
+        .map_or("none".to_string(), |a| {
+            a.iter()
+                .map(|(name, n)| format!("{} x{}", name, n))
+                .collect::<Vec<_>>()
+                .join(", ")
+        });
+    format!(
+        "{}Daily digest {}: best hashrate {}, best efficiency {}, {} reboot(s), {} error(s), alerts: {}",
+        prefix,
+        event.get("day").and_then(Value::as_str).unwrap_or("-"),
+        number("best_hashrate_ths"),
+        number("best_efficiency_j_per_th"),
+        number("reboots"),
+        number("errors"),
+        alerts
+    )
+}
+
+//body posted by notifications.webhook when no payload_template is set
+pub const DEFAULT_PAYLOAD_TEMPLATE: &str = r#"{"event": "{event}", "value": {value}, "device": "{device}", "ts": "{ts}", "message": "{message}"}"#;
+
+//fill the {event}, {value}, {device}, {ts} and {message} placeholders of a webhook payload template
+//in one pass, so a device name containing a placeholder is not filled in again. the text ones are
+//escaped for use inside a json string; {value} is the event's value as json (null when it has none)
+//and {message} the line discord and telegram would post
+pub fn render_payload(template: &str, device: Option<&str>, event: &Value) -> String {
+    fn escaped(text: &str) -> String {
+        let quoted = Value::from(text).to_string();
+        quoted[1..quoted.len() - 1].to_string()
+    }
+    let field = |key: &str| event.get(key).and_then(Value::as_str).unwrap_or_default();
+    let fills = [
+        ("{event}", escaped(field("event"))),
+        (
+            "{value}",
+            event.get("value").unwrap_or(&Value::Null).to_string(),
+        ),
+        ("{device}", escaped(device.unwrap_or_default())),
+        ("{ts}", escaped(field("ts"))),
+        ("{message}", escaped(&format_message(device, event))),
+    ];
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match fills.iter().find(|(p, _)| rest.starts_with(p)) {
+            Some((p, fill)) => {
+                out.push_str(fill);
+                rest = &rest[p.len()..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+//one short line per event; human-formatted values are preferred when human_values added them
+pub fn format_message(device: Option<&str>, event: &Value) -> String {
+    let name = event
+        .get("event")
+        .and_then(Value::as_str)
+        .unwrap_or("event");
+    let prefix = device.map(|d| format!("[{}] ", d)).unwrap_or_default();
+    if name == "daily_digest" {
+        return format_digest(&prefix, event);
+    }
+    let value = [
+        "hashrate_human",
+        "efficiency_human",
+        "difficulty_human",
+        "display",
+    ]
+    .iter()
+    .find_map(|k| event.get(*k).and_then(Value::as_str).map(str::to_string))
+    .or_else(|| {
+        event
+            .get("value")
+            .filter(|v| !v.is_null())
+            .map(|v| v.to_string())
+    });
+    let label = match name {
+        "boot_detected" => "Device rebooted",
+        "new_device_all_time_best" => "New device all-time best",
+        "new_device_boot_best" => "New best since boot",
+        "new_tool_all_time_best" => "New all-time best seen by the monitor",
+        "new_tool_best_hashrate_ths" => "New best hashrate",
+        "new_tool_best_efficiency_j_per_th" => "New best efficiency",
+        "new_best_difficulty" => "New best difficulty",
+        "new_tool_max_temp_c" => "New max chip temperature",
+        "new_tool_min_power_w" => "New lowest power draw",
+        "new_tool_max_power_w" => "New highest power draw",
+        "temp_alert" => "Chip temperature above alert threshold",
+        "efficiency_degraded" => "Efficiency degraded from recent median",
+        "hashrate_stalled" => "Hashrate stalled",
+        "hashrate_recovered" => "Hashrate recovered",
+        "test" => "Test notification from bitaxe_monitor",
+        other => other,
+    };
+    match value {
+        Some(v) => format!("{}{}: {}", prefix, label, v),
+        None => format!("{}{}", prefix, label),
+    }
+}
+
+pub async fn send_discord(client: &Client, cfg: &DiscordConfig, content: &str) -> Result<()> {
+    client
+        .post(&cfg.webhook_url)
+        .json(&serde_json::json!({ "content": content }))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+//the rendered template is posted as is
+pub async fn send_webhook(client: &Client, cfg: &WebhookConfig, body: &str) -> Result<()> {
+    client
+        .post(&cfg.url)
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.to_string())
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+//bot api sendMessage as plain text
+pub async fn send_telegram(client: &Client, cfg: &TelegramConfig, text: &str) -> Result<()> {
+    let base = cfg
+        .api_url
+        .as_deref()
+        .unwrap_or("https://api.telegram.org")
+        .trim_end_matches('/');
+    client
+        .post(format!("{}/bot{}/sendMessage", base, cfg.bot_token))
+        .json(&serde_json::json!({ "chat_id": cfg.chat_id, "text": text }))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+//a message waiting for the background sender; the target is captured per message so a config
+//reload applies to messages queued afterwards
+#[derive(Clone)]
+pub struct QueuedMessage {
+    pub notifier: Arc<dyn Notifier>,
+    pub notified_event: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlushReport {
+    pub delivered: usize,
+    // failed sends plus messages still queued when the flush timed out
+    pub dropped: usize,
+}
+
+//sends notifications from a dedicated task so a slow webhook never holds up a poll; failures are
+//written as notify_error events
+pub struct NotifyQueue {
+    tx: UnboundedSender<QueuedMessage>,
+    handle: JoinHandle<()>,
+    queued: Arc<AtomicUsize>,
+    delivered: Arc<AtomicUsize>,
+}
+
+impl NotifyQueue {
+    pub fn spawn(client: Client, events: Arc<EventLog>) -> Self {
+        let (tx, mut rx) = unbounded_channel::<QueuedMessage>();
+        let delivered = Arc::new(AtomicUsize::new(0));
+        let sent = delivered.clone();
+        let handle = tokio::spawn(async move {
+            while let Some(msg) = rx.recv().await {
+                match msg.notifier.send(&client, &msg.content).await {
+                    Ok(()) => {
+                        sent.fetch_add(1, Ordering::SeqCst);
+                    }
+                    Err(err) => {
+                        let _ = append_event_jsonl(
+                            &events,
+                            with_severity(serde_json::json!({
+                                "ts": Utc::now(),
+                                "event": "notify_error",
+                                "target": msg.notifier.name(),
+                                "notified_event": msg.notified_event,
+                                "error": err.to_string()
+                            })),
+                        );
+                    }
+                }
+            }
+        });
+        Self {
+            tx,
+            handle,
+            queued: Arc::new(AtomicUsize::new(0)),
+            delivered,
+        }
+    }
+
+    pub fn push(&self, msg: QueuedMessage) {
+        if self.tx.send(msg).is_ok() {
+            self.queued.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    //stop accepting messages and deliver what is queued, giving up after timeout
+    pub async fn shutdown(self, timeout: Duration) -> FlushReport {
+        let Self {
+            tx,
+            mut handle,
+            queued,
+            delivered,
+        } = self;
+        drop(tx);
+        if tokio::time::timeout(timeout, &mut handle).await.is_err() {
+            handle.abort();
+        }
+        let delivered = delivered.load(Ordering::SeqCst);
+        FlushReport {
+            delivered,
+            dropped: queued.load(Ordering::SeqCst).saturating_sub(delivered),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+
+    #[test]
+    fn test_subscription_and_message_format() {
+        let cfg = DiscordConfig {
+            webhook_url: "https://discord.invalid/api/webhooks/1/x".to_string(),
+            events: vec![
+                "new_device_all_time_best".to_string(),
+                "boot_detected".to_string(),
+            ],
+            mode: None,
+        };
+        let best = serde_json::json!({ "event": "new_device_all_time_best", "value": 4.5e9 });
+        let boot = serde_json::json!({ "event": "boot_detected", "state": {} });
+        let other = serde_json::json!({ "event": "new_device_boot_best", "value": 1.0 });
+        assert!(cfg.subscribed(&best) && cfg.subscribed(&boot));
+        assert!(!cfg.subscribed(&other));
+
+        assert_eq!(
+            format_message(Some("garage"), &best),
+            "[garage] New device all-time best: 4500000000.0"
+        );
+        assert_eq!(format_message(None, &boot), "Device rebooted");
+        let human = serde_json::json!({
+            "event": "new_tool_best_hashrate_ths",
+            "value": 1.234,
+            "hashrate_human": "1.23 TH/s"
+        });
+        assert_eq!(format_message(None, &human), "New best hashrate: 1.23 TH/s");
+    }
+
+    #[tokio::test]
+    async fn test_queued_notifications_delivered_on_shutdown() {
+        use axum::{routing::post, Router};
+
+        // a slow webhook that counts the posts it receives
+        let received = Arc::new(AtomicUsize::new(0));
+        let counter = received.clone();
+        let app = Router::new().route(
+            "/hook",
+            post(move || {
+                let counter = counter.clone();
+                async move {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let storage = crate::config::StorageConfig {
+            events_path: dir
+                .path()
+                .join("events.jsonl")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+        let events = Arc::new(EventLog::new(&storage, false, Arc::new(SystemClock)));
+        let queue = NotifyQueue::spawn(Client::new(), events.clone());
+        let discord: Arc<dyn Notifier> = Arc::new(DiscordConfig {
+            webhook_url: format!("http://{}/hook", addr),
+            events: vec!["boot_detected".to_string()],
+            mode: None,
+        });
+        for _ in 0..3 {
+            queue.push(QueuedMessage {
+                notifier: discord.clone(),
+                notified_event: "boot_detected".to_string(),
+                content: "Device rebooted".to_string(),
+            });
+        }
+        // nothing has gone out yet; shutdown waits for all three
+        assert_eq!(received.load(Ordering::SeqCst), 0);
+        let report = queue.shutdown(Duration::from_secs(5)).await;
+        assert_eq!(
+            report,
+            FlushReport {
+                delivered: 3,
+                dropped: 0
+            }
+        );
+        assert_eq!(received.load(Ordering::SeqCst), 3);
+
+        // a flush that runs out of time reports what was left behind
+        let queue = NotifyQueue::spawn(Client::new(), events);
+        for _ in 0..3 {
+            queue.push(QueuedMessage {
+                notifier: discord.clone(),
+                notified_event: "boot_detected".to_string(),
+                content: "Device rebooted".to_string(),
+            });
+        }
+        let report = queue.shutdown(Duration::from_millis(75)).await;
+        assert_eq!(report.delivered + report.dropped, 3);
+        assert!(report.dropped >= 1);
+    }
+
+    #[tokio::test]
+    async fn test_telegram_send_message() {
+        use axum::{extract::Path, routing::post, Json, Router};
+        use tokio::sync::mpsc;
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<(String, Value)>();
+        let app = Router::new().route(
+            "/{bot}/sendMessage",
+            post(move |Path(bot): Path<String>, Json(body): Json<Value>| {
+                let tx = tx.clone();
+                async move {
+                    let _ = tx.send((bot, body));
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let telegram = TelegramConfig {
+            bot_token: "123:abc".to_string(),
+            chat_id: "-1001".to_string(),
+            events: vec!["temp_alert".to_string()],
+            api_url: Some(format!("http://{}/", addr)),
+            mode: None,
+        };
+        let event = serde_json::json!({
+            "ts": "2024-01-02T03:04:05Z", "event": "temp_alert", "value": 71.5, "threshold": 70.0
+        });
+        assert!(telegram.subscribed(&event));
+        let text = telegram.render(Some("garage"), &event);
+        assert_eq!(
+            text,
+            "[garage] Chip temperature above alert threshold: 71.5\n2024-01-02T03:04:05Z"
+        );
+        telegram.send(&Client::new(), &text).await.unwrap();
+        let (bot, body) = rx.recv().await.unwrap();
+        assert_eq!(bot, "bot123:abc");
+        assert_eq!(body["chat_id"], "-1001");
+        assert_eq!(body["text"], text);
+
+        // every configured channel is returned
+        let notifications = NotificationsConfig {
+            discord: None,
+            telegram: Some(telegram),
+            webhook: None,
+            queued: None,
+            flush_timeout_secs: None,
+            dedupe: None,
+        };
+        let names: Vec<_> = configured(Some(&notifications))
+            .iter()
+            .map(|n| n.name())
+            .collect();
+        assert_eq!(names, ["telegram"]);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_carries_no_device_credentials() {
+        use crate::config::HttpConfig;
+        use crate::fetch::build_sink_client;
+        use axum::{http::HeaderMap, routing::post, Router};
+        use tokio::sync::mpsc;
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<HeaderMap>();
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap| {
+                let tx = tx.clone();
+                async move {
+                    let _ = tx.send(headers);
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        // a device that needs a bearer token and an api key header
+        let http: HttpConfig = serde_json::from_value(serde_json::json!({
+            "endpoint_url": "http://miner.local/api/system/info",
+            "headers": { "X-Api-Key": "device-key" },
+            "auth_token_env": "BITAXE_MONITOR_TEST_WEBHOOK_TOKEN"
+        }))
+        .unwrap();
+        let discord = DiscordConfig {
+            webhook_url: format!("http://{}/hook", addr),
+            events: vec!["boot_detected".to_string()],
+            mode: None,
+        };
+        let client = build_sink_client(&http).unwrap();
+        send_discord(&client, &discord, "rebooted").await.unwrap();
+
+        let headers = rx.recv().await.unwrap();
+        assert!(headers.get("authorization").is_none(), "{:?}", headers);
+        assert!(headers.get("x-api-key").is_none(), "{:?}", headers);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_posts_rendered_template() {
+        use axum::{routing::post, Json, Router};
+        use tokio::sync::mpsc;
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<Value>();
+        let app = Router::new().route(
+            "/hook",
+            post(move |Json(body): Json<Value>| {
+                let tx = tx.clone();
+                async move {
+                    let _ = tx.send(body);
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        // a slack-style body; {value} goes in unquoted
+        let webhook = WebhookConfig {
+            url: format!("http://{}/hook", addr),
+            events: vec!["temp_alert".to_string()],
+            payload_template: Some(
+                r#"{"text": "{device}: {event} at {ts}", "value": {value}, "tags": {"src": "bitaxe"}}"#
+                    .to_string(),
+            ),
+            mode: None,
+        };
+        let event = serde_json::json!({
+            "ts": "2024-01-02T03:04:05Z", "event": "temp_alert", "value": 71.5, "threshold": 70.0
+        });
+        assert!(webhook.subscribed(&event));
+        let body = webhook.render(Some("rig \"1\""), &event);
+        webhook.send(&Client::new(), &body).await.unwrap();
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            serde_json::json!({
+                "text": "rig \"1\": temp_alert at 2024-01-02T03:04:05Z",
+                "value": 71.5,
+                "tags": { "src": "bitaxe" }
+            })
+        );
+
+        // the default body, for an event without a value and a monitor without device_name
+        let plain = WebhookConfig {
+            payload_template: None,
+            ..webhook
+        };
+        let boot = serde_json::json!({ "ts": "2024-01-02T03:04:05Z", "event": "boot_detected" });
+        let body: Value = serde_json::from_str(&plain.render(None, &boot)).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "event": "boot_detected", "value": null, "device": "", "ts": "2024-01-02T03:04:05Z",
+                "message": "Device rebooted"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_test_reports_each_channel() {
+        use axum::{routing::post, Json, Router};
+        use tokio::sync::mpsc;
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<Value>();
+        let app = Router::new().route(
+            "/hook",
+            post(move |Json(body): Json<Value>| {
+                let tx = tx.clone();
+                async move {
+                    let _ = tx.send(body);
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        // neither channel lists "test"; both are tried anyway, and the dead one is reported
+        let notifications = NotificationsConfig {
+            discord: Some(DiscordConfig {
+                webhook_url: "http://127.0.0.1:9/none".to_string(),
+                events: vec!["boot_detected".to_string()],
+                mode: None,
+            }),
+            telegram: None,
+            webhook: Some(WebhookConfig {
+                url: format!("http://{}/hook", addr),
+                events: vec!["boot_detected".to_string()],
+                payload_template: None,
+                mode: None,
+            }),
+            queued: None,
+            flush_timeout_secs: None,
+            dedupe: None,
+        };
+        let now = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let results = send_test(&Client::new(), Some(&notifications), Some("garage"), now).await;
+        let names: Vec<_> = results.iter().map(|(n, r)| (*n, r.is_ok())).collect();
+        assert_eq!(names, [("discord", false), ("webhook", true)]);
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            serde_json::json!({
+                "event": "test", "value": null, "device": "garage", "ts": "2023-11-14T22:13:20Z",
+                "message": "[garage] Test notification from bitaxe_monitor"
+            })
+        );
+        assert!(send_test(&Client::new(), None, None, now).await.is_empty());
+    }
+
+    #[test]
+    fn test_dedupe_survives_restart() {
+        use crate::metrics::MonitorState;
+        use crate::persist::{load_state, save_state};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json").to_string_lossy().to_string();
+        let now = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let best = serde_json::json!({ "event": "new_device_all_time_best", "value": 4.5e9 });
+        let boot_best = serde_json::json!({ "event": "new_device_boot_best", "value": 2.0 });
+        let mut state = MonitorState::new();
+        let sent = unannounced(&mut state.last_notified, &[best.clone(), boot_best], now);
+        assert_eq!(sent.len(), 2);
+        save_state(&path, &state).unwrap();
+
+        // restarted; the same best is detected again and recorded, but not announced twice
+        let mut state = load_state(&path).unwrap();
+        assert_eq!(state.last_notified["new_device_all_time_best"].value, 4.5e9);
+        assert!(unannounced(&mut state.last_notified, &[best], now).is_empty());
+        let better = serde_json::json!({ "event": "new_device_all_time_best", "value": 5e9 });
+        assert_eq!(
+            unannounced(&mut state.last_notified, std::slice::from_ref(&better), now),
+            [better]
+        );
+
+        // efficiency records improve downwards
+        let eff = |v: f64| serde_json::json!({ "event": "new_tool_best_efficiency_j_per_th", "value": v });
+        assert_eq!(
+            unannounced(&mut state.last_notified, &[eff(17.0)], now).len(),
+            1
+        );
+        assert!(unannounced(&mut state.last_notified, &[eff(17.5)], now).is_empty());
+        assert_eq!(
+            unannounced(&mut state.last_notified, &[eff(16.9)], now).len(),
+            1
+        );
+
+        // boot bests start over with a reboot, and events that are not records always pass
+        let boot = serde_json::json!({ "event": "boot_detected" });
+        let low_boot_best = serde_json::json!({ "event": "new_device_boot_best", "value": 1.0 });
+        assert!(unannounced(
+            &mut state.last_notified,
+            std::slice::from_ref(&low_boot_best),
+            now
+        )
+        .is_empty());
+        assert_eq!(
+            unannounced(
+                &mut state.last_notified,
+                &[boot.clone(), low_boot_best],
+                now
+            )
+            .len(),
+            2
+        );
+        assert_eq!(unannounced(&mut state.last_notified, &[boot], now).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_daily_digest_posted_once_after_midnight() {
+        use crate::clock::{Clock, FixedClock};
+        use axum::{routing::post, Json, Router};
+        use tokio::sync::mpsc;
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<Value>();
+        let app = Router::new().route(
+            "/hook",
+            post(move |Json(body): Json<Value>| {
+                let tx = tx.clone();
+                async move {
+                    let _ = tx.send(body);
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        // the realtime discord channel is unreachable; it must not be tried for the digest
+        let notifications = NotificationsConfig {
+            discord: Some(DiscordConfig {
+                webhook_url: "http://127.0.0.1:9/none".to_string(),
+                events: vec!["temp_alert".to_string()],
+                mode: None,
+            }),
+            telegram: None,
+            webhook: Some(WebhookConfig {
+                url: format!("http://{}/hook", addr),
+                events: Vec::new(),
+                payload_template: Some(
+                    r#"{"event": "{event}", "message": "{message}"}"#.to_string(),
+                ),
+                mode: Some(NotifyMode::Digest),
+            }),
+            queued: None,
+            flush_timeout_secs: None,
+            dedupe: None,
+        };
+        let clock = FixedClock::new("2024-03-09T23:58:00Z".parse().unwrap());
+        let client = Client::new();
+        let mut digest = DailyDigest::default();
+        let mut posted = Vec::new();
+        for minute in 0..8 {
+            let sent = post_daily_digest(
+                &client,
+                Some(&notifications),
+                Some("rig"),
+                &mut digest,
+                clock.now(),
+            )
+            .await;
+            posted.extend(sent.into_iter().map(|(name, r)| (name, r.is_ok())));
+            if minute < 2 {
+                digest.observe(
+                    Some(1.2 - minute as f64 / 10.0),
+                    Some(17.5 + minute as f64),
+                    &[
+                        serde_json::json!({ "event": "temp_alert", "severity": "warn" }),
+                        serde_json::json!({ "event": "boot_detected" }),
+                    ],
+                );
+                digest.errors += 1;
+            }
+            clock.advance(chrono::Duration::minutes(1));
+        }
+        assert_eq!(posted, [("webhook", true)]);
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            serde_json::json!({
+                "event": "daily_digest",
+                "message": "[rig] Daily digest 2024-03-09: best hashrate 1.2, best efficiency 17.5, \
+                    2 reboot(s), 2 error(s), alerts: temp_alert x2"
+            })
+        );
+        assert!(rx.try_recv().is_err());
+        // the new day started from scratch
+        assert_eq!(digest.day, "2024-03-10".parse().ok());
+        assert_eq!(digest.reboots, 0);
+    }
+}
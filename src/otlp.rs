@@ -0,0 +1,225 @@
+use crate::config::{EventSeverity, OtlpConfig};
+use crate::history::event_time;
+use crate::persist::{event_severity, EventSink};
+use anyhow::{Context, Result};
+use opentelemetry::logs::{AnyValue, LogRecord, Logger, LoggerProvider, Severity};
+use opentelemetry_otlp::{WithExportConfig, WithHttpConfig};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogBatch, LogExporter, SdkLogger, SdkLoggerProvider};
+use opentelemetry_sdk::Resource;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+//one event mapped to log record fields; kept apart from the sdk record so the mapping is testable
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFields {
+    pub body: String,
+    pub severity: Severity,
+    pub severity_text: &'static str,
+    pub timestamp: Option<SystemTime>,
+    pub attributes: Vec<(String, AnyValue)>,
+}
+
+//event name becomes the body and its severity the record severity; every other field (metric values,
+//errors) becomes an attribute
+pub fn map_event(event: &Value) -> LogFields {
+    let name = event
+        .get("event")
+        .and_then(Value::as_str)
+        .unwrap_or("unknown")
+        .to_string();
+    let (severity, severity_text) = match event_severity(event) {
+        EventSeverity::Error => (Severity::Error, "ERROR"),
+        EventSeverity::Warn => (Severity::Warn, "WARN"),
+        EventSeverity::Info => (Severity::Info, "INFO"),
+    };
+    let timestamp = event.get("ts").and_then(event_time).map(SystemTime::from);
+
+    let mut attributes = Vec::new();
+    if let Some(obj) = event.as_object() {
+        for (key, v) in obj {
+            if key == "event" || key == "ts" || key == "severity" {
+                continue;
+            }
+            let value = match v {
+                Value::Null => continue,
+                Value::Bool(b) => AnyValue::Boolean(*b),
+                Value::Number(n) => match n.as_i64() {
+                    Some(i) => AnyValue::Int(i),
+                    None => AnyValue::Double(n.as_f64().unwrap_or(f64::NAN)),
+                },
+                Value::String(s) => AnyValue::String(s.clone().into()),
+                // nested objects (e.g. the state on boot_detected) stay readable as json text
+                other => AnyValue::String(other.to_string().into()),
+            };
+            attributes.push((key.clone(), value));
+        }
+    }
+
+    LogFields {
+        body: name,
+        severity,
+        severity_text,
+        timestamp,
+        attributes,
+    }
+}
+
+//wraps the otlp exporter so failed batch exports can be reported as otlp_error events
+#[derive(Debug)]
+struct ObservedExporter<E> {
+    inner: E,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl<E: LogExporter> LogExporter for ObservedExporter<E> {
+    async fn export(&self, batch: LogBatch<'_>) -> OTelSdkResult {
+        let result = self.inner.export(batch).await;
+        if let Err(err) = &result {
+            *self.last_error.lock().unwrap_or_else(|e| e.into_inner()) = Some(err.to_string());
+        }
+        result
+    }
+
+    fn shutdown_with_timeout(&self, timeout: std::time::Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+//forwards every event written to the events log as an otlp log record, whichever backend stores
+//it; records are batched by the sdk
+pub struct OtlpForwarder {
+    provider: SdkLoggerProvider,
+    logger: SdkLogger,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl OtlpForwarder {
+    //installed with EventLog::forward_to, so only events written after startup are forwarded
+    pub fn new(cfg: &OtlpConfig) -> Result<Self> {
+        let mut builder = opentelemetry_otlp::LogExporter::builder()
+            .with_http()
+            .with_endpoint(cfg.endpoint.clone());
+        if let Some(headers) = &cfg.headers {
+            builder = builder.with_headers(headers.clone());
+        }
+        let last_error = Arc::new(Mutex::new(None));
+        let exporter = ObservedExporter {
+            inner: builder
+                .build()
+                .context("failed to build otlp log exporter")?,
+            last_error: last_error.clone(),
+        };
+        let provider = SdkLoggerProvider::builder()
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(
+                        cfg.service_name
+                            .clone()
+                            .unwrap_or_else(|| "bitaxe_monitor".to_string()),
+                    )
+                    .build(),
+            )
+            .with_batch_exporter(exporter)
+            .build();
+        let logger = provider.logger("bitaxe_monitor");
+        Ok(Self {
+            provider,
+            logger,
+            last_error,
+        })
+    }
+
+    //the most recent batch export failure since the last call, if any
+    pub fn take_error(&self) -> Option<String> {
+        self.last_error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+    }
+
+    //flush the batch (including service_stop) before exit
+    pub fn shutdown(&self) -> Result<()> {
+        self.provider
+            .shutdown()
+            .map_err(|e| anyhow::anyhow!("otlp shutdown failed: {}", e))?;
+        match self.take_error() {
+            Some(err) => anyhow::bail!(err),
+            None => Ok(()),
+        }
+    }
+}
+
+impl EventSink for OtlpForwarder {
+    fn append(&self, event: &Value) -> Result<()> {
+        let fields = map_event(event);
+        let mut record = self.logger.create_log_record();
+        record.set_body(AnyValue::String(fields.body.into()));
+        record.set_severity_number(fields.severity);
+        record.set_severity_text(fields.severity_text);
+        if let Some(ts) = fields.timestamp {
+            record.set_timestamp(ts);
+        }
+        record.add_attributes(fields.attributes);
+        self.logger.emit(record);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_event_to_log_record_fields() {
+        let event = serde_json::json!({
+            "ts": "2024-01-02T03:04:05Z",
+            "event": "new_tool_best_hashrate_ths",
+            "value": 1.25,
+            "display": "1.25T",
+            "count": 3
+        });
+        let fields = map_event(&event);
+        assert_eq!(fields.body, "new_tool_best_hashrate_ths");
+        assert_eq!(fields.severity, Severity::Info);
+        assert_eq!(
+            fields.timestamp,
+            Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_704_164_645))
+        );
+        assert!(fields
+            .attributes
+            .contains(&("value".to_string(), AnyValue::Double(1.25))));
+        assert!(fields
+            .attributes
+            .contains(&("count".to_string(), AnyValue::Int(3))));
+        assert!(fields
+            .attributes
+            .contains(&("display".to_string(), AnyValue::String("1.25T".into()))));
+        assert!(!fields
+            .attributes
+            .iter()
+            .any(|(k, _)| k == "event" || k == "ts"));
+
+        let err = map_event(&serde_json::json!({ "event": "poll_error", "error": "timeout" }));
+        assert_eq!(
+            (err.severity, err.severity_text),
+            (Severity::Error, "ERROR")
+        );
+        let epoch = map_event(&serde_json::json!({ "ts": 1_704_164_645_000u64, "event": "x" }));
+        assert_eq!(epoch.timestamp, fields.timestamp);
+        let epoch = map_event(&serde_json::json!({ "ts": 1_704_164_645u64, "event": "x" }));
+        assert_eq!(epoch.timestamp, fields.timestamp);
+
+        let warn = map_event(&serde_json::json!({ "event": "stale_sample", "age_secs": 90 }));
+        assert_eq!(warn.severity, Severity::Warn);
+        let escalated = map_event(
+            &serde_json::json!({ "event": "boot_flapping", "boots": 4, "severity": "error" }),
+        );
+        assert_eq!(escalated.severity, Severity::Error);
+    }
+}
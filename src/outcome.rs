@@ -0,0 +1,313 @@
+use crate::clock::Clock;
+use crate::metrics::{self, DetectionOutcome, FlapChange, MonitorState};
+use crate::persist::{append_event_jsonl, event_severity, EventLog};
+use anyhow::Result;
+use serde_json::Value;
+
+//stamp an event with its severity when it is built; this is the only place the field is added, so
+//the events log, notifications and otlp all see the same value and other files (e.g. the wide
+//records) never get one. an explicit severity already on the event is kept
+pub fn with_severity(mut event: Value) -> Value {
+    let severity = event_severity(&event);
+    if let Some(obj) = event.as_object_mut() {
+        obj.insert("severity".to_string(), serde_json::json!(severity));
+    }
+    event
+}
+
+//temperature events show the reading as the sensor gave it when calibration changed it
+fn with_raw_temp(mut event: Value, raw: Option<f64>) -> Value {
+    if let (Some(obj), Some(raw)) = (event.as_object_mut(), raw) {
+        obj.insert("raw_value".to_string(), serde_json::json!(raw));
+    }
+    event
+}
+
+//turn one poll's detection outcome into events, each stamped with clock.now()
+pub fn handle_detection_outcome(
+    events: &EventLog,
+    state: &MonitorState,
+    outcome: DetectionOutcome,
+    human_values: bool,
+    escalate_flapping: bool,
+    clock: &dyn Clock,
+) -> Result<Vec<Value>> {
+    //write structured events based on detected changes so the events log shows reboots and new records in order
+    let now = clock.now();
+    //human-formatted copies are added next to the raw numbers, never instead of them
+    //written events are also returned so they can be passed on to notifications
+    let mut emitted = Vec::new();
+    let mut emit = |mut event: serde_json::Value| -> Result<()> {
+        if human_values {
+            metrics::add_human_values(&mut event);
+        }
+        let event = with_severity(event);
+        append_event_jsonl(events, &event)?;
+        emitted.push(event);
+        Ok(())
+    };
+
+    //a restart loop is reported once on entry and once when it settles; the reboots in between
+    //are not logged one by one
+    match outcome.boot_flapping {
+        Some(FlapChange::Started { boots }) => {
+            let mut event = serde_json::json!({
+                "ts": now,
+                "event": "boot_flapping",
+                "boots": boots
+            });
+            if escalate_flapping {
+                event["severity"] = "error".into();
+            }
+            emit(event)?;
+        }
+        Some(FlapChange::Cleared) => emit(serde_json::json!({
+            "ts": now,
+            "event": "boot_flapping_cleared"
+        }))?,
+        None => {}
+    }
+
+    //record a boot event when a fresh start is observed so timelines show when the device restarted
+    if outcome.boot_detected && !state.boot_flapping.active() {
+        emit(serde_json::json!({
+            "ts": now,
+            "event": "boot_detected",
+            "state": state
+        }))?;
+    }
+
+    //record a session best when the current boot produces a new top value so each run keeps its own high-water mark
+    if let Some(v) = outcome.new_device_boot_best {
+        emit(serde_json::json!({
+            "ts": now,
+            "event": "new_device_boot_best",
+            "value": v
+        }))?;
+    }
+
+    //record a lifetime best for this device when a new all-time high appears so progress across many runs is captured
+    if let Some(v) = outcome.new_device_all_time_best {
+        emit(serde_json::json!({
+            "ts": now,
+            "event": "new_device_all_time_best",
+            "value": v
+        }))?;
+    }
+
+    //record the best value this tool has ever seen so the monitor can celebrate its own highest reading
+    if let Some(v) = outcome.new_tool_all_time_best {
+        emit(serde_json::json!({
+            "ts": now,
+            "event": "new_tool_all_time_best",
+            "value": v
+        }))?;
+    }
+
+    // record new best hashrate (TH/s) when present
+    if let Some(v) = outcome.new_tool_best_hashrate_ths {
+        let mut event = serde_json::json!({
+            "ts": now,
+            "event": "new_tool_best_hashrate_ths",
+            "value": v
+        });
+        //the voltage/frequency that produced the record, when those pointers are configured
+        if let Some(mv) = state.last_voltage_mv {
+            event["voltage_mv"] = serde_json::json!(mv);
+        }
+        if let Some(mhz) = state.last_frequency_mhz {
+            event["frequency_mhz"] = serde_json::json!(mhz);
+        }
+        emit(event)?;
+    }
+
+    // record new best efficiency (lowest J/TH) when present
+    if let Some(v) = outcome.new_tool_best_efficiency_j_per_th {
+        emit(serde_json::json!({
+            "ts": now,
+            "event": "new_tool_best_efficiency_j_per_th",
+            "value": v
+        }))?;
+    }
+
+    // record a new best difficulty with the device's original formatting alongside the number
+    if let Some(v) = outcome.new_best_difficulty {
+        emit(serde_json::json!({
+            "ts": now,
+            "event": "new_best_difficulty",
+            "value": v,
+            "display": state.tool_best_difficulty_display
+        }))?;
+    }
+
+    // same for the best share since the last reboot
+    if let Some(v) = outcome.new_session_best_difficulty {
+        emit(serde_json::json!({
+            "ts": now,
+            "event": "new_session_best_difficulty",
+            "value": v,
+            "display": state.session_best_difficulty_display
+        }))?;
+    }
+
+    // record a new highest chip temperature; a calibrated reading also carries the raw one
+    if let Some(v) = outcome.new_tool_max_temp_c {
+        emit(with_raw_temp(
+            serde_json::json!({
+                "ts": now,
+                "event": "new_tool_max_temp_c",
+                "value": v
+            }),
+            outcome.temp_c_raw,
+        ))?;
+    }
+
+    // record the power draw range
+    if let Some(v) = outcome.new_tool_min_power_w {
+        emit(serde_json::json!({
+            "ts": now,
+            "event": "new_tool_min_power_w",
+            "value": v
+        }))?;
+    }
+    if let Some(v) = outcome.new_tool_max_power_w {
+        emit(serde_json::json!({
+            "ts": now,
+            "event": "new_tool_max_power_w",
+            "value": v
+        }))?;
+    }
+
+    // warn when the chip first runs hotter than thresholds.temp_alert_c
+    if let Some((v, threshold)) = outcome.temp_alert {
+        emit(with_raw_temp(
+            serde_json::json!({
+                "ts": now,
+                "event": "temp_alert",
+                "value": v,
+                "threshold": threshold
+            }),
+            outcome.temp_c_raw,
+        ))?;
+    }
+
+    // warn when the device keeps answering but hashes (next to) nothing, and when it picks back up
+    if let Some((v, threshold)) = outcome.hashrate_stalled {
+        emit(serde_json::json!({
+            "ts": now,
+            "event": "hashrate_stalled",
+            "value": v,
+            "threshold": threshold
+        }))?;
+    }
+    if let Some(v) = outcome.hashrate_recovered {
+        emit(serde_json::json!({
+            "ts": now,
+            "event": "hashrate_recovered",
+            "value": v
+        }))?;
+    }
+
+    // warn when efficiency slips well behind its recent median (e.g. thermal throttling)
+    if let Some((v, baseline)) = outcome.efficiency_degraded {
+        emit(serde_json::json!({
+            "ts": now,
+            "event": "efficiency_degraded",
+            "value": v,
+            "baseline_median": baseline
+        }))?;
+    }
+
+    // warn when too many of the shares submitted since the last poll were rejected
+    if let Some((v, threshold)) = outcome.high_reject_ratio {
+        emit(serde_json::json!({
+            "ts": now,
+            "event": "high_reject_ratio",
+            "value": v,
+            "threshold": threshold
+        }))?;
+    }
+
+    // record new worst hashrate (lowest steady-state TH/s) for degradation tracking
+    if let Some(v) = outcome.new_tool_worst_hashrate_ths {
+        emit(serde_json::json!({
+            "ts": now,
+            "event": "new_tool_worst_hashrate_ths",
+            "value": v
+        }))?;
+    }
+
+    // record new worst efficiency (highest steady-state J/TH)
+    if let Some(v) = outcome.new_tool_worst_efficiency_j_per_th {
+        emit(serde_json::json!({
+            "ts": now,
+            "event": "new_tool_worst_efficiency_j_per_th",
+            "value": v
+        }))?;
+    }
+
+    Ok(emitted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{FixedClock, SystemClock};
+    use chrono::DateTime;
+
+    #[test]
+    fn test_events_stamped_from_clock() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let storage = crate::config::StorageConfig {
+            events_path: dir
+                .path()
+                .join("events.jsonl")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+        let events = EventLog::new(&storage, false, std::sync::Arc::new(SystemClock));
+        let t0 = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let clock = FixedClock::new(t0);
+        let outcome = DetectionOutcome {
+            boot_detected: true,
+            new_tool_all_time_best: Some(12.0),
+            ..Default::default()
+        };
+        let emitted =
+            handle_detection_outcome(&events, &MonitorState::new(), outcome, false, false, &clock)
+                .unwrap();
+        let names: Vec<&str> = emitted
+            .iter()
+            .map(|e| e["event"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["boot_detected", "new_tool_all_time_best"]);
+        assert!(emitted.iter().all(|e| e["ts"] == "2023-11-14T22:13:20Z"));
+
+        clock.advance(chrono::Duration::seconds(90));
+        let outcome = DetectionOutcome {
+            new_tool_max_temp_c: Some(61.0),
+            temp_c_raw: Some(64.0),
+            ..Default::default()
+        };
+        let emitted =
+            handle_detection_outcome(&events, &MonitorState::new(), outcome, false, false, &clock)
+                .unwrap();
+        assert_eq!(emitted[0]["ts"], "2023-11-14T22:14:50Z");
+        // a calibrated temperature keeps the sensor's own reading next to it
+        assert_eq!(
+            (
+                emitted[0]["value"].as_f64(),
+                emitted[0]["raw_value"].as_f64()
+            ),
+            (Some(61.0), Some(64.0))
+        );
+        assert_eq!(
+            std::fs::read_to_string(events.path())
+                .unwrap()
+                .lines()
+                .count(),
+            3
+        );
+    }
+}
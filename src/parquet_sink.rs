@@ -0,0 +1,234 @@
+use crate::metrics::ExtractedMetrics;
+use anyhow::{Context, Result};
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, TimestampMillisecondArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::{self, File};
+use std::path::Path;
+use std::sync::Arc;
+
+//one poll worth of values in the columnar layout
+#[derive(Debug, Clone)]
+pub struct ParquetSample {
+    pub ts: DateTime<Utc>,
+    pub displayed_all_time: f64,
+    pub displayed_boot_best: f64,
+    pub uptime_secs: Option<u64>,
+    pub hashrate_ths: Option<f64>,
+    pub efficiency_j_per_th: Option<f64>,
+}
+
+impl ParquetSample {
+    pub fn from_metrics(ts: DateTime<Utc>, m: &ExtractedMetrics) -> Self {
+        Self {
+            ts,
+            displayed_all_time: m.displayed_all_time,
+            displayed_boot_best: m.displayed_boot_best,
+            uptime_secs: m.uptime_secs,
+            hashrate_ths: m.hashrate_ths,
+            efficiency_j_per_th: m.efficiency_j_per_th,
+        }
+    }
+}
+
+//buffers samples and writes them as parquet row groups so long histories stay cheap to analyze
+pub struct ParquetSink {
+    writer: ArrowWriter<File>,
+    schema: SchemaRef,
+    pending: Vec<ParquetSample>,
+    batch_size: usize,
+    path: String,
+}
+
+impl ParquetSink {
+    //parquet files cannot be appended to, so an existing file gets a per-session sibling instead
+    pub fn create(path: &str, batch_size: usize, started: DateTime<Utc>) -> Result<Self> {
+        let path = session_path(path, started);
+        if let Some(parent) = Path::new(&path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let file = File::create(&path)
+            .with_context(|| format!("failed to create parquet file {}", path))?;
+        let schema = sample_schema();
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let writer = ArrowWriter::try_new(file, schema.clone(), Some(props))?;
+        Ok(Self {
+            writer,
+            schema,
+            pending: Vec::with_capacity(batch_size),
+            batch_size: batch_size.max(1),
+            path,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn push(&mut self, sample: ParquetSample) -> Result<()> {
+        self.pending.push(sample);
+        if self.pending.len() >= self.batch_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    //write buffered samples as one row group
+    pub fn flush(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let batch = to_record_batch(&self.schema, &self.pending)?;
+        self.writer.write(&batch)?;
+        self.writer.flush()?;
+        self.pending.clear();
+        Ok(())
+    }
+
+    //flush any partial batch and write the parquet footer; the file is unreadable without it
+    pub fn close(mut self) -> Result<()> {
+        self.flush()?;
+        self.writer.close()?;
+        Ok(())
+    }
+}
+
+fn sample_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new(
+            "ts",
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+            false,
+        ),
+        Field::new("displayed_all_time", DataType::Float64, false),
+        Field::new("displayed_boot_best", DataType::Float64, false),
+        Field::new("uptime_secs", DataType::UInt64, true),
+        Field::new("hashrate_ths", DataType::Float64, true),
+        Field::new("efficiency_j_per_th", DataType::Float64, true),
+    ]))
+}
+
+fn to_record_batch(schema: &SchemaRef, samples: &[ParquetSample]) -> Result<RecordBatch> {
+    let ts: Vec<i64> = samples.iter().map(|s| s.ts.timestamp_millis()).collect();
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(TimestampMillisecondArray::from(ts).with_timezone("UTC")),
+        Arc::new(Float64Array::from(
+            samples
+                .iter()
+                .map(|s| s.displayed_all_time)
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(Float64Array::from(
+            samples
+                .iter()
+                .map(|s| s.displayed_boot_best)
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(UInt64Array::from(
+            samples.iter().map(|s| s.uptime_secs).collect::<Vec<_>>(),
+        )),
+        Arc::new(Float64Array::from(
+            samples.iter().map(|s| s.hashrate_ths).collect::<Vec<_>>(),
+        )),
+        Arc::new(Float64Array::from(
+            samples
+                .iter()
+                .map(|s| s.efficiency_j_per_th)
+                .collect::<Vec<_>>(),
+        )),
+    ];
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+//samples.parquet -> samples-20240601T120000Z.parquet when the configured file already exists
+fn session_path(path: &str, started: DateTime<Utc>) -> String {
+    if !Path::new(path).exists() {
+        return path.to_string();
+    }
+    let stamp = started.format("%Y%m%dT%H%M%SZ");
+    match path.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !ext.contains('/') && !ext.contains('\\') => {
+            format!("{}-{}.{}", stem, stamp, ext)
+        }
+        _ => format!("{}-{}", path, stamp),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn sample(secs: i64, hashrate: Option<f64>) -> ParquetSample {
+        ParquetSample {
+            ts: DateTime::from_timestamp(secs, 0).unwrap(),
+            displayed_all_time: 10.0,
+            displayed_boot_best: 5.0,
+            uptime_secs: Some(secs as u64),
+            hashrate_ths: hashrate,
+            efficiency_j_per_th: Some(16.0),
+        }
+    }
+
+    #[test]
+    fn test_write_batches_and_read_back() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("samples.parquet");
+        let path_str = path.to_string_lossy().to_string();
+
+        // batch size 2 with 3 samples leaves a partial batch that close() must flush
+        let started = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut sink = ParquetSink::create(&path_str, 2, started).expect("create");
+        sink.push(sample(1_700_000_000, Some(1.1))).expect("push");
+        sink.push(sample(1_700_000_010, None)).expect("push");
+        sink.push(sample(1_700_000_020, Some(1.3))).expect("push");
+        sink.close().expect("close");
+
+        let file = File::open(&path).expect("open");
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file).expect("reader");
+        assert_eq!(builder.schema().as_ref(), sample_schema().as_ref());
+        assert_eq!(builder.metadata().num_row_groups(), 2);
+
+        let batches: Vec<RecordBatch> = builder
+            .build()
+            .expect("build")
+            .collect::<std::result::Result<_, _>>()
+            .expect("batches");
+        let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+        assert_eq!(rows, 3);
+
+        let hashrate = batches[0]
+            .column_by_name("hashrate_ths")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(hashrate.value(0), 1.1);
+        assert!(hashrate.is_null(1));
+    }
+
+    #[test]
+    fn test_existing_file_gets_session_suffix() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("samples.parquet");
+        let path_str = path.to_string_lossy().to_string();
+        let started = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+
+        assert_eq!(session_path(&path_str, started), path_str);
+        fs::write(&path, b"previous run").unwrap();
+        let next = session_path(&path_str, started);
+        assert!(
+            next.ends_with("samples-20231114T221320Z.parquet"),
+            "{}",
+            next
+        );
+    }
+}
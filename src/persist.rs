@@ -1,19 +1,411 @@
+use crate::clock::Clock;
+#[cfg(feature = "sqlite")]
+use crate::config::StorageBackend;
+use crate::config::{EventSeverity, StorageConfig, TimestampFormat};
 use crate::metrics::MonitorState;
+use crate::outcome::with_severity;
+#[cfg(feature = "sqlite")]
+use crate::sqlite::{SqliteStore, DEFAULT_DB_PATH};
 use anyhow::Result;
-use serde::Serialize;
+use chrono::{DateTime, Days, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fs::{self, File, OpenOptions};
 use std::io::{BufReader, Write};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
-//add one JSON object per line to a file so event history stays simple to read and process later
-pub fn append_event_jsonl(path: &str, value: impl Serialize) -> Result<()> {
-    //create parent folder when path includes directories
-    if let Some(parent) = Path::new(path).parent() {
-        if !parent.as_os_str().is_empty() {
-            fs::create_dir_all(parent)?;
+//where events go instead of the jsonl file: the batched writer task or the sqlite events table
+pub trait EventSink: Send + Sync {
+    fn append(&self, event: &serde_json::Value) -> Result<()>;
+}
+
+//git sha of this build, set by build.rs ("unknown" when it could not be determined)
+pub const BUILD_SHA: &str = env!("BITAXE_BUILD_SHA");
+
+//the events log and how its lines are written; built once from the storage config at startup and
+//passed to everything that writes an event
+pub struct EventLog {
+    path: String,
+    //batched writer or sqlite table standing in for the file; removed again at shutdown
+    sink: Mutex<Option<Arc<dyn EventSink>>>,
+    //also handed every written event, whatever the backend (e.g. the otlp forwarder)
+    forwards: Mutex<Vec<Arc<dyn EventSink>>>,
+    //adds a "build" field to every event (tag_events_with_build)
+    build_tag: bool,
+    //events are dropped while the monitor is still probing (see ReadinessGate)
+    held: AtomicBool,
+    min_severity: EventSeverity,
+    timestamp_format: TimestampFormat,
+    rotation: Option<EventRotation>,
+    //stamps rotated archives, so tests can rotate at a chosen time
+    clock: Arc<dyn Clock>,
+}
+
+impl EventLog {
+    pub fn new(storage: &StorageConfig, build_tag: bool, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            path: storage.events_path.clone(),
+            sink: Mutex::new(None),
+            forwards: Mutex::new(Vec::new()),
+            build_tag,
+            held: AtomicBool::new(false),
+            min_severity: storage.min_event_severity.unwrap_or_default(),
+            timestamp_format: storage.timestamp_format.unwrap_or_default(),
+            rotation: EventRotation::from_storage(storage),
+            clock,
         }
     }
 
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn rotation(&self) -> Option<EventRotation> {
+        self.rotation
+    }
+
+    pub fn install_sink(&self, sink: Arc<dyn EventSink>) {
+        *self.sink.lock().unwrap_or_else(|e| e.into_inner()) = Some(sink);
+    }
+
+    //later appends go straight to the file again
+    pub fn remove_sink(&self) {
+        self.sink.lock().unwrap_or_else(|e| e.into_inner()).take();
+    }
+
+    #[cfg(any(feature = "otlp", test))]
+    pub fn forward_to(&self, sink: Arc<dyn EventSink>) {
+        self.forwards
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(sink);
+    }
+
+    fn sink(&self) -> Option<Arc<dyn EventSink>> {
+        self.sink.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+//with probe_until_ready, nothing is written until the config has worked against the live device once;
+//events appended in the meantime (startup, poll errors, heartbeats) are dropped
+pub struct ReadinessGate {
+    since: DateTime<Utc>,
+    failures: u64,
+}
+
+impl ReadinessGate {
+    pub fn hold(events: &EventLog, now: DateTime<Utc>) -> Self {
+        events.held.store(true, Ordering::SeqCst);
+        Self {
+            since: now,
+            failures: 0,
+        }
+    }
+
+    pub fn failed(&mut self) {
+        self.failures += 1;
+    }
+
+    //open the gate, then write the deferred startup event and monitor_ready
+    pub fn ready(
+        self,
+        events: &EventLog,
+        now: DateTime<Utc>,
+        start_event: serde_json::Value,
+    ) -> Result<()> {
+        events.held.store(false, Ordering::SeqCst);
+        append_event_jsonl(events, start_event)?;
+        append_event_jsonl(
+            events,
+            with_severity(serde_json::json!({
+                "ts": now,
+                "event": "monitor_ready",
+                "probe_failures": self.failures,
+                "probing_secs": (now - self.since).num_seconds()
+            })),
+        )
+    }
+}
+
+//an explicit "severity" on the event (e.g. an escalated boot_flapping) wins; otherwise *_error
+//events are errors, conditions worth a look are warnings and everything else is info
+pub fn event_severity(event: &serde_json::Value) -> EventSeverity {
+    if let Some(severity) = event
+        .get("severity")
+        .and_then(|v| EventSeverity::deserialize(v).ok())
+    {
+        return severity;
+    }
+    let name = event
+        .get("event")
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or("");
+    if name.ends_with("_error") || name == "poll_error_repeated" {
+        EventSeverity::Error
+    } else if matches!(
+        name,
+        "stale_sample"
+            | "high_jitter"
+            | "temp_alert"
+            | "high_reject_ratio"
+            | "efficiency_degraded"
+            | "efficiency_drift"
+            | "hashrate_stalled"
+            | "metric_unavailable"
+            | "rebooting"
+            | "circuit_open"
+            | "boot_flapping"
+            | "implausible_uptime"
+            | "implausible_boot_time"
+            | "outlier_rejected"
+            | "config_changed_since_state"
+            | "config_reload_failed"
+            | "new_tool_worst_hashrate_ths"
+            | "new_tool_worst_efficiency_j_per_th"
+    ) {
+        EventSeverity::Warn
+    } else {
+        EventSeverity::Info
+    }
+}
+
+pub fn format_timestamp(ts: DateTime<Utc>, format: TimestampFormat) -> serde_json::Value {
+    match format {
+        TimestampFormat::Rfc3339 => serde_json::to_value(ts).unwrap_or_default(),
+        TimestampFormat::EpochMs => ts.timestamp_millis().into(),
+        TimestampFormat::EpochS => ts.timestamp().into(),
+    }
+}
+
+//events_path is moved aside once it grows past max_bytes, keeping max_files archives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventRotation {
+    pub max_bytes: u64,
+    pub max_files: usize,
+}
+
+impl EventRotation {
+    pub fn from_storage(storage: &StorageConfig) -> Option<Self> {
+        storage.max_event_bytes.map(|max_bytes| Self {
+            max_bytes,
+            max_files: storage.max_event_files.unwrap_or(10),
+        })
+    }
+}
+
+//timestamp in archive names, e.g. "events-20240102T030405.123Z.jsonl.gz"
+const ARCHIVE_STAMP: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+//"logs/events.jsonl" -> ("logs", "events-"); archives are "<stem>-<timestamp>.jsonl.gz" next to it
+fn archive_prefix(path: &str) -> (std::path::PathBuf, String) {
+    let p = Path::new(path);
+    let dir = p
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .to_path_buf();
+    let stem = p
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "events".to_string());
+    (dir, format!("{}-", stem))
+}
+
+//archives of this events file, oldest first (the timestamp in the name sorts chronologically); only
+//names rotation itself produces match, so other "<stem>-*.jsonl" files next to it are left alone
+fn archive_files(path: &str, suffix: &str) -> Vec<std::path::PathBuf> {
+    let (dir, prefix) = archive_prefix(path);
+    let is_archive = |name: &str| {
+        name.strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(suffix))
+            .is_some_and(|stamp| {
+                stamp.len() == 20
+                    && chrono::NaiveDateTime::parse_from_str(stamp, ARCHIVE_STAMP).is_ok()
+            })
+    };
+    let mut out: Vec<_> = fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(is_archive)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    out.sort();
+    out
+}
+
+//compress a renamed-aside log into <name>.gz; the plain copy is only removed once the archive is
+//complete, so an interrupted run leaves it to be picked up again by the next rotation
+fn compress_archive(plain: &Path) -> Result<()> {
+    let gz = format!("{}.gz", plain.display());
+    let tmp = format!("{}.tmp", gz);
+    {
+        let mut input = File::open(plain)?;
+        let mut enc =
+            flate2::write::GzEncoder::new(File::create(&tmp)?, flate2::Compression::default());
+        std::io::copy(&mut input, &mut enc)?;
+        enc.finish()?.sync_all()?;
+    }
+    fs::rename(&tmp, &gz)?;
+    fs::remove_file(plain)?;
+    Ok(())
+}
+
+//called before each append: once the active file is past max_event_bytes it is renamed aside (a
+//single atomic step, so the events are never lost) and the next append starts a fresh file; the
+//renamed copy is then gzipped and the oldest archives beyond max_event_files are deleted
+pub fn rotate_events_if_needed(
+    path: &str,
+    rotation: EventRotation,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let len = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if len < rotation.max_bytes {
+        return Ok(());
+    }
+    let (dir, prefix) = archive_prefix(path);
+    let plain = dir.join(format!("{}{}.jsonl", prefix, now.format(ARCHIVE_STAMP)));
+    fs::rename(path, &plain)?;
+
+    //finish this archive and any left uncompressed by an earlier interrupted rotation
+    for leftover in archive_files(path, ".jsonl") {
+        compress_archive(&leftover)?;
+    }
+    let archives = archive_files(path, ".jsonl.gz");
+    let excess = archives.len().saturating_sub(rotation.max_files);
+    for old in &archives[..excess] {
+        fs::remove_file(old)?;
+    }
+    Ok(())
+}
+
+//save one raw response body as <dir>/<timestamp>.json and delete the oldest snapshots beyond
+//max_snapshots; only files named like a snapshot are counted or removed
+pub fn write_payload_snapshot(
+    dir: &str,
+    body: &str,
+    now: DateTime<Utc>,
+    max_snapshots: usize,
+) -> Result<std::path::PathBuf> {
+    fs::create_dir_all(dir)?;
+    let path = Path::new(dir).join(format!("{}.json", now.format("%Y%m%dT%H%M%S%.3fZ")));
+    fs::write(&path, body)?;
+
+    let mut snapshots: Vec<_> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_suffix("Z.json"))
+                .is_some_and(|stem| stem.len() == 19 && stem.as_bytes()[8] == b'T')
+        })
+        .collect();
+    snapshots.sort();
+    let excess = snapshots.len().saturating_sub(max_snapshots);
+    for old in &snapshots[..excess] {
+        fs::remove_file(old)?;
+    }
+    Ok(path)
+}
+
+//the startup event always carries the build so a restart after an upgrade is visible in the log
+pub fn service_start_event(
+    now: DateTime<Utc>,
+    endpoint_url: &str,
+    poll_interval_secs: u64,
+) -> serde_json::Value {
+    with_severity(serde_json::json!({
+        "ts": now,
+        "event": "service_start",
+        "build": BUILD_SHA,
+        "config": {
+            "endpoint_url": endpoint_url,
+            "poll_interval_secs": poll_interval_secs
+        }
+    }))
+}
+
+//liveness marker for quiet periods; values come from the latest successful poll (null before one).
+//with self_metrics the monitor's own process_rss_bytes and process_cpu_percent are added
+pub fn heartbeat_event(now: DateTime<Utc>, state: &MonitorState) -> serde_json::Value {
+    let mut event = serde_json::json!({
+        "ts": now,
+        "event": "heartbeat",
+        "hashrate_ths": state.last_hashrate_ths,
+        "efficiency_j_per_th": state.last_efficiency_j_per_th,
+        "uptime_secs": state.last_uptime_secs,
+        "voltage_mv": state.last_voltage_mv,
+        "frequency_mhz": state.last_frequency_mhz
+    });
+    if let Some(rss) = state.process_rss_bytes {
+        event["process_rss_bytes"] = rss.into();
+        event["process_cpu_percent"] = state.process_cpu_percent.into();
+    }
+    with_severity(event)
+}
+
+//add one event to the log, as one JSON object per line, so event history stays simple to read and
+//process later
+pub fn append_event_jsonl(events: &EventLog, value: impl Serialize) -> Result<()> {
+    if events.held.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    let mut value = serde_json::to_value(&value)?;
+    //severity is stamped when the event is built (outcome::with_severity); here it is only read
+    if event_severity(&value) < events.min_severity {
+        return Ok(());
+    }
+    if events.build_tag {
+        if let Some(obj) = value.as_object_mut() {
+            obj.entry("build")
+                .or_insert_with(|| serde_json::Value::from(BUILD_SHA));
+        }
+    }
+    //events are built with chrono timestamps (rfc3339 strings); convert "ts" on the way out
+    if events.timestamp_format != TimestampFormat::Rfc3339 {
+        let parsed = value
+            .get("ts")
+            .and_then(serde_json::Value::as_str)
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok());
+        if let Some(ts) = parsed {
+            value["ts"] = format_timestamp(ts.with_timezone(&Utc), events.timestamp_format);
+        }
+    }
+
+    //forwarding is best effort and never keeps the event from being written
+    let forwards = events
+        .forwards
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    for forward in forwards {
+        let _ = forward.append(&value);
+    }
+
+    //hand the event to the installed sink (batched writer or sqlite) when there is one
+    if let Some(sink) = events.sink() {
+        return sink.append(&value);
+    }
+    if let Some(rotation) = events.rotation {
+        create_parent_dir(&events.path)?;
+        rotate_events_if_needed(&events.path, rotation, events.clock.now())?;
+    }
+    append_jsonl(&events.path, &value)
+}
+
+//add one JSON object per line to a plain file (e.g. the wide records), untouched
+pub fn append_jsonl(path: &str, value: impl Serialize) -> Result<()> {
+    create_parent_dir(path)?;
+
     //append one JSON object per line so large histories are easy to stream/process
     let mut f = OpenOptions::new().create(true).append(true).open(path)?;
     let line = serde_json::to_string(&value)?;
@@ -24,8 +416,22 @@ pub fn append_event_jsonl(path: &str, value: impl Serialize) -> Result<()> {
     Ok(())
 }
 
+//create parent folder when path includes directories
+pub fn create_parent_dir(path: &str) -> Result<()> {
+    if let Some(parent) = Path::new(path).parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    Ok(())
+}
+
 //write state safely using a temp file and a replace step so partial writes do not corrupt the saved state
 pub fn save_state(path: &str, state: &MonitorState) -> Result<()> {
+    replace_file(path, &serde_json::to_vec_pretty(state)?)
+}
+
+fn replace_file(path: &str, bytes: &[u8]) -> Result<()> {
     let tmp = format!("{}.tmp", path);
 
     //create parent folder if missing
@@ -38,7 +444,7 @@ pub fn save_state(path: &str, state: &MonitorState) -> Result<()> {
     //write to a temp file first so a crash never leaves a half-written file
     {
         let mut f = File::create(&tmp)?;
-        serde_json::to_writer_pretty(&mut f, state)?;
+        f.write_all(bytes)?;
         f.flush()?;
         //fsync to persist to disk; reduces risk after power loss
         f.sync_all()?;
@@ -65,11 +471,241 @@ pub fn load_state(path: &str) -> Result<MonitorState> {
     Ok(state)
 }
 
+//pointer file for double-buffered state: which buffer holds the last complete write, and its checksum
+#[derive(Debug, Serialize, Deserialize)]
+struct BufferPointer {
+    current: String,
+    sha256: String,
+}
+
+//"state.json" -> ("state.a.json", "state.b.json"); the pointer lives at "state.json.current"
+fn buffer_paths(path: &str) -> (String, String) {
+    let p = Path::new(path);
+    match (p.file_stem(), p.extension()) {
+        (Some(stem), Some(ext)) => {
+            let name = |tag: &str| {
+                p.with_file_name(format!(
+                    "{}.{}.{}",
+                    stem.to_string_lossy(),
+                    tag,
+                    ext.to_string_lossy()
+                ))
+                .to_string_lossy()
+                .to_string()
+            };
+            (name("a"), name("b"))
+        }
+        _ => (format!("{}.a", path), format!("{}.b", path)),
+    }
+}
+
+fn pointer_path(path: &str) -> String {
+    format!("{}.current", path)
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn read_pointer(path: &str) -> Option<BufferPointer> {
+    let text = fs::read_to_string(pointer_path(path)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+//alternate between two buffers so a torn write can only ever damage the copy that is not current;
+//the pointer is switched (atomically, via replace) only after the new buffer is fully synced
+pub fn save_state_double_buffered(path: &str, state: &MonitorState) -> Result<()> {
+    let (a, b) = buffer_paths(path);
+    //normally write the buffer the pointer does not name; if the named one fails its checksum the
+    //other buffer holds the only good copy, so the bad one is overwritten instead
+    let write_b = match read_pointer(path) {
+        Some(p) => {
+            let named_b = p.current == "b";
+            let named = if named_b { &b } else { &a };
+            let intact = fs::read(named).is_ok_and(|bytes| sha256_hex(&bytes) == p.sha256);
+            named_b != intact
+        }
+        None => false,
+    };
+    let (tag, target) = if write_b { ("b", b) } else { ("a", a) };
+    let bytes = serde_json::to_vec_pretty(state)?;
+    if let Some(parent) = Path::new(&target).parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    {
+        let mut f = File::create(&target)?;
+        f.write_all(&bytes)?;
+        f.sync_all()?;
+    }
+    let pointer = BufferPointer {
+        current: tag.to_string(),
+        sha256: sha256_hex(&bytes),
+    };
+    replace_file(&pointer_path(path), &serde_json::to_vec(&pointer)?)
+}
+
+//load the buffer named by the pointer if its checksum matches; otherwise fall back to the other
+//buffer, and finally to a plain single-file state at path (e.g. right after enabling double_buffer)
+pub fn load_state_double_buffered(path: &str) -> Result<MonitorState> {
+    let (a, b) = buffer_paths(path);
+    let pointer = read_pointer(path);
+    let (first, second) = match &pointer {
+        Some(p) if p.current == "b" => (b, a),
+        _ => (a, b),
+    };
+    if let (Some(p), Ok(bytes)) = (&pointer, fs::read(&first)) {
+        if sha256_hex(&bytes) == p.sha256 {
+            if let Ok(state) = serde_json::from_slice::<MonitorState>(&bytes) {
+                return Ok(state);
+            }
+        }
+    }
+    if let Ok(bytes) = fs::read(&second) {
+        if let Ok(state) = serde_json::from_slice::<MonitorState>(&bytes) {
+            eprintln!(
+                "[bitaxe_monitor] WARN: current state buffer is missing or corrupt; recovered from {}",
+                second
+            );
+            return Ok(state);
+        }
+    }
+    if pointer.is_none() {
+        if let Ok(state) = load_state(&first) {
+            return Ok(state);
+        }
+    }
+    load_state(path)
+}
+
+#[cfg(feature = "sqlite")]
+fn open_db(storage: &StorageConfig) -> Result<SqliteStore> {
+    SqliteStore::open(storage.db_path.as_deref().unwrap_or(DEFAULT_DB_PATH))
+}
+
+//whether a routine state save should happen now: always without an interval, on the first save, or
+//when forced; otherwise once the interval has passed since the last one
+pub fn state_save_due(
+    last_saved: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    interval_secs: Option<u64>,
+    force: bool,
+) -> bool {
+    match (interval_secs, last_saved) {
+        (Some(secs), Some(last)) if !force => {
+            (now - last).num_seconds() >= i64::try_from(secs).unwrap_or(i64::MAX)
+        }
+        _ => true,
+    }
+}
+
+//single file, double buffer or sqlite row, depending on storage.backend / storage.double_buffer
+pub fn save_state_to(path: &str, state: &MonitorState, storage: &StorageConfig) -> Result<()> {
+    #[cfg(feature = "sqlite")]
+    if storage.backend == Some(StorageBackend::Sqlite) {
+        return open_db(storage)?.save_state(path, state);
+    }
+    if storage.double_buffer.unwrap_or(false) {
+        save_state_double_buffered(path, state)
+    } else {
+        save_state(path, state)
+    }
+}
+
+fn load_state_from(path: &str, storage: &StorageConfig) -> Result<MonitorState> {
+    #[cfg(feature = "sqlite")]
+    if storage.backend == Some(StorageBackend::Sqlite) {
+        return open_db(storage)?
+            .load_state(path)?
+            .ok_or_else(|| anyhow::anyhow!("no state saved for {}", path));
+    }
+    if storage.double_buffer.unwrap_or(false) {
+        load_state_double_buffered(path)
+    } else {
+        load_state(path)
+    }
+}
+
+pub fn state_exists(path: &str, storage: &StorageConfig) -> bool {
+    #[cfg(feature = "sqlite")]
+    if storage.backend == Some(StorageBackend::Sqlite) {
+        return open_db(storage).is_ok_and(|db| db.load_state(path).is_ok_and(|s| s.is_some()));
+    }
+    if storage.double_buffer.unwrap_or(false) {
+        let (a, b) = buffer_paths(path);
+        Path::new(&pointer_path(path)).exists() || Path::new(&a).exists() || Path::new(&b).exists()
+    } else {
+        Path::new(path).exists()
+    }
+}
+
+//warn when the loaded state was saved under a different config (e.g. other pointers or scaling),
+//since its bests may not be comparable; states without a recorded hash are accepted silently
+pub fn config_hash_mismatch(state: &MonitorState, current: &str) -> Option<String> {
+    match state.config_hash.as_deref() {
+        Some(saved) if saved != current => Some(format!(
+            "state was saved under a different config (state {}, config {})",
+            saved.get(..12).unwrap_or(saved),
+            current.get(..12).unwrap_or(current)
+        )),
+        _ => None,
+    }
+}
+
+//expand a "{date}" placeholder (UTC, YYYY-MM-DD) so state can roll over into one file per day
+pub fn resolve_state_path(template: &str, now: DateTime<Utc>) -> String {
+    if template.contains("{date}") {
+        template.replace("{date}", &now.format("%Y-%m-%d").to_string())
+    } else {
+        template.to_string()
+    }
+}
+
+//load today's state; for dated paths with no file yet, seed from the previous day's bests
+pub fn load_state_for(
+    template: &str,
+    now: DateTime<Utc>,
+    storage: &StorageConfig,
+) -> Result<MonitorState> {
+    let today = resolve_state_path(template, now);
+    match load_state_from(&today, storage) {
+        Ok(state) => Ok(state),
+        Err(err) => {
+            if !template.contains("{date}") || state_exists(&today, storage) {
+                return Err(err);
+            }
+            let yesterday = now
+                .checked_sub_days(Days::new(1))
+                .map(|d| resolve_state_path(template, d));
+            match yesterday {
+                Some(path) => load_state_from(&path, storage),
+                None => Err(err),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::{FixedClock, SystemClock};
     use std::fs;
 
+    //an events log at <dir>/events.jsonl with the given storage settings, on a clock stopped at
+    //2023-11-14T22:13:20Z
+    fn event_log(dir: &Path, storage: StorageConfig) -> EventLog {
+        let storage = StorageConfig {
+            events_path: dir.join("events.jsonl").to_string_lossy().to_string(),
+            ..storage
+        };
+        let t0 = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        EventLog::new(&storage, false, Arc::new(FixedClock::new(t0)))
+    }
+
     #[test]
     fn test_save_then_load_roundtrip() {
         // temp dir for isolated file IO keeps the workspace clean
@@ -102,4 +738,495 @@ mod tests {
         let tmp_exists = fs::metadata(format!("{}.tmp", state_path_str)).is_ok();
         assert!(!tmp_exists, "temp file should be removed after rename");
     }
+
+    #[test]
+    fn test_dated_state_path_rolls_over_and_seeds() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let template = dir
+            .path()
+            .join("state-{date}.json")
+            .to_string_lossy()
+            .to_string();
+
+        // day one: save bests into the dated file
+        let day1 = DateTime::parse_from_rfc3339("2024-06-01T23:59:50Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let day1_path = resolve_state_path(&template, day1);
+        assert!(day1_path.ends_with("state-2024-06-01.json"));
+        let mut s = MonitorState::new();
+        s.tool_best_hashrate_ths = Some(1.5);
+        s.tool_global_all_time_best = 42.0;
+        save_state(&day1_path, &s).expect("save day1");
+
+        // clock crosses midnight: the path changes and the new day starts from yesterday's bests
+        let day2 = day1 + chrono::Duration::seconds(20);
+        let day2_path = resolve_state_path(&template, day2);
+        assert!(day2_path.ends_with("state-2024-06-02.json"));
+        assert!(fs::metadata(&day2_path).is_err());
+
+        let seeded =
+            load_state_for(&template, day2, &StorageConfig::default()).expect("seed from day1");
+        assert_eq!(seeded.tool_best_hashrate_ths, Some(1.5));
+        save_state(&day2_path, &seeded).expect("save day2");
+        let reloaded =
+            load_state_for(&template, day2, &StorageConfig::default()).expect("load day2");
+        assert!((reloaded.tool_global_all_time_best - 42.0).abs() < 1e-9);
+        assert!(fs::metadata(&day1_path).is_ok());
+    }
+
+    #[test]
+    fn test_undated_state_path_unchanged() {
+        let now = Utc::now();
+        assert_eq!(
+            resolve_state_path("myBitAxeInfo.json", now),
+            "myBitAxeInfo.json"
+        );
+    }
+
+    #[test]
+    fn test_config_hash_mismatch_warns() {
+        let mut s = MonitorState::new();
+        // older state files have no hash and are accepted
+        assert!(config_hash_mismatch(&s, "abc123").is_none());
+        s.config_hash = Some("abc123".to_string());
+        assert!(config_hash_mismatch(&s, "abc123").is_none());
+        let msg = config_hash_mismatch(&s, "def456").expect("mismatch warning");
+        assert!(msg.contains("different config"));
+        assert!(msg.contains("abc123") && msg.contains("def456"));
+
+        // a hand-edited hash that is not plain ascii must not panic when shortened
+        s.config_hash = Some("aééééééééé".to_string());
+        let msg = config_hash_mismatch(&s, "def456").expect("mismatch warning");
+        assert!(msg.contains("aééééééééé"));
+    }
+
+    #[test]
+    fn test_double_buffer_alternates_and_recovers_from_corruption() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("state.json").to_string_lossy().to_string();
+        let (a, b) = buffer_paths(&path);
+        assert!(a.ends_with("state.a.json") && b.ends_with("state.b.json"));
+
+        let mut s = MonitorState::new();
+        s.tool_global_all_time_best = 1.0;
+        save_state_double_buffered(&path, &s).expect("save 1");
+        s.tool_global_all_time_best = 2.0;
+        save_state_double_buffered(&path, &s).expect("save 2");
+        assert_eq!(read_pointer(&path).unwrap().current, "b");
+        let loaded = load_state_double_buffered(&path).expect("load");
+        assert_eq!(loaded.tool_global_all_time_best, 2.0);
+
+        // torn write of the current buffer
+        let current = fs::read(&b).unwrap();
+        fs::write(&b, &current[..current.len() / 2]).unwrap();
+        let recovered = load_state_double_buffered(&path).expect("recover");
+        assert_eq!(recovered.tool_global_all_time_best, 1.0);
+
+        // a well-formed but different file under the current name also fails the checksum
+        s.tool_global_all_time_best = 99.0;
+        fs::write(&b, serde_json::to_vec_pretty(&s).unwrap()).unwrap();
+        let recovered = load_state_double_buffered(&path).expect("recover");
+        assert_eq!(recovered.tool_global_all_time_best, 1.0);
+
+        // the next save overwrites the bad buffer, never the good one
+        s.tool_global_all_time_best = 3.0;
+        save_state_double_buffered(&path, &s).expect("save 3");
+        assert_eq!(read_pointer(&path).unwrap().current, "b");
+        let good: MonitorState = serde_json::from_slice(&fs::read(&a).unwrap()).unwrap();
+        assert_eq!(good.tool_global_all_time_best, 1.0);
+        assert_eq!(
+            load_state_double_buffered(&path)
+                .unwrap()
+                .tool_global_all_time_best,
+            3.0
+        );
+    }
+
+    #[test]
+    fn test_double_buffer_falls_back_to_single_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("state.json").to_string_lossy().to_string();
+        let mut s = MonitorState::new();
+        s.tool_global_all_time_best = 5.0;
+        save_state(&path, &s).expect("save");
+        let storage = StorageConfig {
+            double_buffer: Some(true),
+            ..Default::default()
+        };
+        let loaded = load_state_for(&path, Utc::now(), &storage).expect("load legacy");
+        assert_eq!(loaded.tool_global_all_time_best, 5.0);
+    }
+
+    #[test]
+    fn test_payload_snapshots_keep_newest() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let snap_dir = dir.path().join("snaps").to_string_lossy().to_string();
+        let t0 = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut written = Vec::new();
+        for i in 0..4 {
+            let at = t0 + chrono::Duration::seconds(i);
+            written.push(write_payload_snapshot(&snap_dir, "{\"raw\": 1}", at, 2).unwrap());
+        }
+        assert_eq!(
+            written[0].file_name().unwrap().to_string_lossy(),
+            "20231114T221320.000Z.json"
+        );
+        // unrelated files in the directory are never pruned
+        fs::write(Path::new(&snap_dir).join("notes.json"), "{}").unwrap();
+        write_payload_snapshot(&snap_dir, "{}", t0 + chrono::Duration::seconds(9), 2).unwrap();
+
+        let mut left: Vec<String> = fs::read_dir(&snap_dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            [
+                "20231114T221323.000Z.json",
+                "20231114T221329.000Z.json",
+                "notes.json"
+            ]
+        );
+        assert_eq!(fs::read_to_string(&written[3]).unwrap(), "{\"raw\": 1}");
+    }
+
+    #[test]
+    fn test_event_rotation_gzips_and_prunes_archives() {
+        use std::io::Read;
+        let dir = tempfile::tempdir().expect("tempdir");
+        let events = event_log(
+            dir.path(),
+            StorageConfig {
+                max_event_bytes: Some(10),
+                max_event_files: Some(2),
+                ..Default::default()
+            },
+        );
+        let path = events.path().to_string();
+        let rotation = events.rotation().unwrap();
+        let archives = || archive_files(&path, ".jsonl.gz");
+
+        // under the limit nothing happens
+        rotate_events_if_needed(&path, rotation, Utc::now()).unwrap();
+        append_event_jsonl(&events, serde_json::json!({"event": "first"})).unwrap();
+        assert!(archives().is_empty());
+
+        // the next append moves the full file aside, named for the log's clock, and starts over
+        append_event_jsonl(&events, serde_json::json!({"event": "second"})).unwrap();
+        let gz = archives();
+        assert_eq!(gz.len(), 1);
+        assert!(gz[0]
+            .to_string_lossy()
+            .ends_with("events-20231114T221320.000Z.jsonl.gz"));
+        let mut text = String::new();
+        flate2::read::GzDecoder::new(File::open(&gz[0]).unwrap())
+            .read_to_string(&mut text)
+            .unwrap();
+        assert!(text.contains("first"));
+        let active = fs::read_to_string(&path).unwrap();
+        assert!(active.contains("second") && !active.contains("first"));
+
+        // a copy left uncompressed by an interrupted rotation is finished next time
+        let (d, prefix) = archive_prefix(&path);
+        fs::write(
+            d.join(format!("{}20000101T000000.000Z.jsonl", prefix)),
+            "old\n",
+        )
+        .unwrap();
+        let t = Utc::now() + chrono::Duration::seconds(1);
+        rotate_events_if_needed(&path, rotation, t).unwrap();
+        assert!(archive_files(&path, ".jsonl").is_empty());
+
+        // only the newest max_event_files archives are kept
+        let names: Vec<String> = archives()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.iter().all(|n| !n.starts_with("events-2000")));
+    }
+
+    #[test]
+    fn test_event_rotation_leaves_similar_names_alone() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let events = event_log(
+            dir.path(),
+            StorageConfig {
+                max_event_bytes: Some(10),
+                max_event_files: Some(1),
+                ..Default::default()
+            },
+        );
+        let siblings = [
+            "events-other.jsonl",
+            "events-backup.jsonl.gz",
+            "events-20000101T000000.jsonl.gz",
+        ];
+        for name in siblings {
+            fs::write(dir.path().join(name), "keep\n").unwrap();
+        }
+        for _ in 0..3 {
+            append_event_jsonl(&events, serde_json::json!({"event": "poll_error"})).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert_eq!(archive_files(events.path(), ".jsonl.gz").len(), 1);
+        for name in siblings {
+            assert_eq!(
+                fs::read_to_string(dir.path().join(name)).unwrap(),
+                "keep\n",
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_service_start_event_has_build() {
+        let event = service_start_event(Utc::now(), "http://10.0.0.5/api/system/info", 10);
+        assert_eq!(event["event"], "service_start");
+        let build = event["build"].as_str().expect("build field");
+        assert!(!build.is_empty());
+        assert_eq!(build, BUILD_SHA);
+    }
+
+    #[test]
+    fn test_events_tagged_with_build_when_enabled() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let storage = StorageConfig {
+            events_path: dir
+                .path()
+                .join("events.jsonl")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+        let events = EventLog::new(&storage, true, Arc::new(SystemClock));
+        append_event_jsonl(&events, serde_json::json!({"event": "poll_error"})).unwrap();
+        let line: serde_json::Value =
+            serde_json::from_str(fs::read_to_string(events.path()).unwrap().trim()).unwrap();
+        assert_eq!(line["build"], BUILD_SHA);
+    }
+
+    #[test]
+    fn test_min_event_severity_filters_and_tags() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let events = event_log(
+            dir.path(),
+            StorageConfig {
+                min_event_severity: Some(EventSeverity::Warn),
+                ..Default::default()
+            },
+        );
+        for event in [
+            serde_json::json!({"event": "new_device_boot_best", "value": 1.0}),
+            serde_json::json!({"event": "temp_alert", "value": 75.0}),
+            serde_json::json!({"event": "poll_error", "error": "timed out"}),
+            serde_json::json!({"event": "boot_flapping", "boots": 4, "severity": "error"}),
+        ] {
+            append_event_jsonl(&events, with_severity(event)).unwrap();
+        }
+        // an event built without the tag is still filtered by its severity, but written as is
+        append_event_jsonl(&events, serde_json::json!({"event": "stale_sample"})).unwrap();
+        let written: Vec<serde_json::Value> = fs::read_to_string(events.path())
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        let pairs: Vec<(&str, &str)> = written
+            .iter()
+            .take(3)
+            .map(|v| {
+                (
+                    v["event"].as_str().unwrap(),
+                    v["severity"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("temp_alert", "warn"),
+                ("poll_error", "error"),
+                ("boot_flapping", "error"),
+            ]
+        );
+        assert_eq!(written[3], serde_json::json!({"event": "stale_sample"}));
+
+        // plain jsonl files such as the wide records are never tagged
+        let records = dir.path().join("wide.jsonl").to_string_lossy().to_string();
+        append_jsonl(&records, serde_json::json!({"hashrate_ths": 1.2})).unwrap();
+        assert_eq!(
+            fs::read_to_string(&records).unwrap().trim(),
+            r#"{"hashrate_ths":1.2}"#
+        );
+    }
+
+    #[test]
+    fn test_forwards_see_events_whatever_the_sink() {
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<String>>);
+        impl EventSink for Recorder {
+            fn append(&self, event: &serde_json::Value) -> Result<()> {
+                let name = event["event"].as_str().unwrap_or_default().to_string();
+                self.0.lock().unwrap().push(name);
+                Ok(())
+            }
+        }
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let events = event_log(dir.path(), StorageConfig::default());
+        let forward = Arc::new(Recorder::default());
+        events.forward_to(forward.clone());
+        append_event_jsonl(&events, serde_json::json!({"event": "service_start"})).unwrap();
+
+        // with a sink standing in for the file (e.g. sqlite) the forward still gets every event
+        let sink = Arc::new(Recorder::default());
+        events.install_sink(sink.clone());
+        append_event_jsonl(&events, serde_json::json!({"event": "poll_error"})).unwrap();
+        assert_eq!(*forward.0.lock().unwrap(), ["service_start", "poll_error"]);
+        assert_eq!(*sink.0.lock().unwrap(), ["poll_error"]);
+    }
+
+    #[test]
+    fn test_timestamp_formats() {
+        let now = Utc::now();
+        assert_eq!(
+            format_timestamp(now, TimestampFormat::Rfc3339),
+            serde_json::json!(now)
+        );
+        assert_eq!(
+            format_timestamp(now, TimestampFormat::EpochMs),
+            serde_json::json!(now.timestamp_millis())
+        );
+        assert_eq!(
+            format_timestamp(now, TimestampFormat::EpochS),
+            serde_json::json!(now.timestamp())
+        );
+
+        // the configured format is applied to events written to the log
+        let dir = tempfile::tempdir().expect("tempdir");
+        let events = event_log(
+            dir.path(),
+            StorageConfig {
+                timestamp_format: Some(TimestampFormat::EpochMs),
+                ..Default::default()
+            },
+        );
+        append_event_jsonl(
+            &events,
+            serde_json::json!({"ts": now, "event": "service_stop"}),
+        )
+        .unwrap();
+        let line: serde_json::Value =
+            serde_json::from_str(fs::read_to_string(events.path()).unwrap().trim()).unwrap();
+        assert_eq!(line["ts"], now.timestamp_millis());
+    }
+
+    #[test]
+    fn test_readiness_gate_holds_events_until_ready() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let events = event_log(dir.path(), StorageConfig::default());
+        let path = events.path().to_string();
+        let t0 = Utc::now();
+        let mut gate = ReadinessGate::hold(&events, t0);
+        append_event_jsonl(&events, serde_json::json!({"event": "service_start"})).unwrap();
+        append_event_jsonl(&events, serde_json::json!({"event": "poll_error"})).unwrap();
+        gate.failed();
+        gate.failed();
+        assert!(!Path::new(&path).exists());
+
+        gate.ready(
+            &events,
+            t0 + chrono::Duration::seconds(30),
+            serde_json::json!({"event": "service_start"}),
+        )
+        .unwrap();
+        append_event_jsonl(
+            &events,
+            serde_json::json!({"event": "new_tool_best_hashrate_ths"}),
+        )
+        .unwrap();
+        let events: Vec<serde_json::Value> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        let names: Vec<&str> = events
+            .iter()
+            .map(|e| e["event"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "service_start",
+                "monitor_ready",
+                "new_tool_best_hashrate_ths"
+            ]
+        );
+        assert_eq!(events[1]["probe_failures"], 2);
+        assert_eq!(events[1]["probing_secs"], 30);
+    }
+
+    #[test]
+    fn test_heartbeat_event_reports_latest_values() {
+        let mut state = MonitorState::new();
+        let now = Utc::now();
+        let quiet = heartbeat_event(now, &state);
+        assert_eq!(quiet["event"], "heartbeat");
+        assert!(quiet["hashrate_ths"].is_null());
+
+        state.last_hashrate_ths = Some(1.2);
+        state.last_efficiency_j_per_th = Some(15.5);
+        state.last_uptime_secs = Some(3600);
+        let event = heartbeat_event(now, &state);
+        assert_eq!(event["hashrate_ths"], 1.2);
+        assert_eq!(event["efficiency_j_per_th"], 15.5);
+        assert_eq!(event["uptime_secs"], 3600);
+        assert!(event["voltage_mv"].is_null());
+
+        state.last_voltage_mv = Some(1200.0);
+        state.last_frequency_mhz = Some(575.0);
+        let event = heartbeat_event(now, &state);
+        assert_eq!(event["voltage_mv"], 1200.0);
+        assert_eq!(event["frequency_mhz"], 575.0);
+        assert!(event.get("process_rss_bytes").is_none());
+
+        // with self_metrics on
+        crate::self_metrics::SelfMetrics::new()
+            .unwrap()
+            .sample(&mut state);
+        let event = heartbeat_event(now, &state);
+        assert!(event["process_rss_bytes"].as_u64().unwrap() > 0);
+        assert!(event["process_cpu_percent"].as_f64().is_some());
+    }
+
+    #[test]
+    fn test_state_save_debounced_unless_forced() {
+        let t0 = Utc::now();
+        // without an interval every poll saves
+        assert!(state_save_due(Some(t0), t0, None, false));
+        // the first save always happens
+        assert!(state_save_due(None, t0, Some(60), false));
+        assert!(!state_save_due(
+            Some(t0),
+            t0 + chrono::Duration::seconds(59),
+            Some(60),
+            false
+        ));
+        assert!(state_save_due(
+            Some(t0),
+            t0 + chrono::Duration::seconds(60),
+            Some(60),
+            false
+        ));
+        // a new best is saved right away
+        assert!(state_save_due(
+            Some(t0),
+            t0 + chrono::Duration::seconds(1),
+            Some(60),
+            true
+        ));
+    }
 }
@@ -0,0 +1,183 @@
+use anyhow::{bail, Context, Result};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+//pid file locked for the life of the process; dropping it (graceful shutdown or an error exit)
+//removes the file and releases the lock, while a crash leaves the pid behind to be taken over as
+//stale on the next start
+#[derive(Debug)]
+pub struct PidFile {
+    // kept open so the exclusive lock lasts as long as the process
+    file: File,
+    path: String,
+}
+
+impl PidFile {
+    //refuse to start while another process holds the lock; a stale file is overwritten with a note
+    pub fn acquire(path: &str) -> Result<Self> {
+        if let Some(parent) = Path::new(path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let (mut file, previous) = loop {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(path)
+                .with_context(|| format!("failed to open pid file {}", path))?;
+            let mut previous = String::new();
+            let _ = file.read_to_string(&mut previous);
+
+            //the OS drops the lock when the process exits, crash included, so a held lock always
+            //means a live instance; the pid in the file is only used for messages
+            match file.try_lock() {
+                Ok(()) => {}
+                Err(TryLockError::WouldBlock) => bail!(
+                    "another instance is running (pid {} in {}); stop it first",
+                    previous
+                        .trim()
+                        .parse::<u32>()
+                        .map_or_else(|_| "unknown".to_string(), |p| p.to_string()),
+                    path
+                ),
+                Err(TryLockError::Error(err)) => {
+                    return Err(err).with_context(|| format!("failed to lock pid file {}", path))
+                }
+            }
+            //an instance shutting down removes its file before letting go of the lock; if that
+            //happened between our open and lock, the file we hold is gone, so start over
+            if still_at(&file, path) {
+                break (file, previous);
+            }
+        };
+        let previous_pid = previous.trim().parse::<u32>().ok();
+        match (previous.trim(), previous_pid) {
+            ("", _) => {}
+            (_, Some(pid)) => eprintln!(
+                "[bitaxe_monitor] WARN: replacing stale pid file {} (pid {} is not running)",
+                path, pid
+            ),
+            (_, None) => eprintln!(
+                "[bitaxe_monitor] WARN: replacing unreadable pid file {}",
+                path
+            ),
+        }
+
+        file.set_len(0)
+            .and_then(|_| file.seek(SeekFrom::Start(0)))
+            .and_then(|_| writeln!(file, "{}", std::process::id()))
+            .and_then(|_| file.flush())
+            .with_context(|| format!("failed to write pid file {}", path))?;
+        Ok(Self {
+            file,
+            path: path.to_string(),
+        })
+    }
+}
+
+//whether `path` still names the open file, rather than nothing or a newer file
+#[cfg(unix)]
+fn still_at(file: &File, path: &str) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(open), Ok(named)) => open.dev() == named.dev() && open.ino() == named.ino(),
+        _ => false,
+    }
+}
+
+//windows does not remove a file another process has open, so the path cannot move under us
+#[cfg(not(unix))]
+fn still_at(_file: &File, _path: &str) -> bool {
+    true
+}
+
+//remove the file while still holding the lock, then release it: a starting instance that opened
+//the old file waits on the lock and then sees the file is gone, instead of locking a file that is
+//about to be unlinked
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+        let _ = self.file.unlock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pid_file_written_and_removed() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("run").join("bitaxe.pid");
+        let path_str = path.to_string_lossy().to_string();
+        let pid = PidFile::acquire(&path_str).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap().trim(),
+            std::process::id().to_string()
+        );
+        drop(pid);
+        assert!(!path.exists());
+        // and the next start writes a new one
+        let _pid = PidFile::acquire(&path_str).unwrap();
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_second_instance_rejected() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("bitaxe.pid").to_string_lossy().to_string();
+        let _held = PidFile::acquire(&path).unwrap();
+        let err = PidFile::acquire(&path).unwrap_err();
+        assert!(format!("{}", err).contains("another instance is running"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stale_pid_file_taken_over() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("bitaxe.pid");
+        // a child that has already exited and been reaped leaves a pid nobody owns
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let dead = child.id();
+        child.wait().unwrap();
+        fs::write(&path, format!("{}\n", dead)).unwrap();
+
+        let _pid = PidFile::acquire(&path.to_string_lossy()).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap().trim(),
+            std::process::id().to_string()
+        );
+    }
+
+    #[test]
+    fn test_lock_decides_not_the_pid_in_the_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("bitaxe.pid");
+        // pid 1 is alive on unix, but nothing holds the lock, so this is a leftover file
+        fs::write(&path, "1\n").unwrap();
+        let held = PidFile::acquire(&path.to_string_lossy()).unwrap();
+        let err = PidFile::acquire(&path.to_string_lossy()).unwrap_err();
+        assert!(format!("{}", err).contains(&format!("pid {}", std::process::id())));
+        drop(held);
+        // released on drop, so the next start gets it
+        PidFile::acquire(&path.to_string_lossy()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_removed_file_not_mistaken_for_the_pid_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("bitaxe.pid");
+        let old = File::create(&path).unwrap();
+        assert!(still_at(&old, &path.to_string_lossy()));
+        // what a starting instance sees if the running one shut down between its open and lock
+        fs::remove_file(&path).unwrap();
+        assert!(!still_at(&old, &path.to_string_lossy()));
+        fs::write(&path, "").unwrap();
+        assert!(!still_at(&old, &path.to_string_lossy()));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::config::RemoteWriteConfig;
+use crate::metrics::{ExtractedMetrics, MonitorState};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use prost::Message;
+use reqwest::Client;
+
+//subset of prometheus/prompb types.proto + remote.proto needed to push samples (remote-write 1.0)
+#[derive(Clone, PartialEq, Message)]
+pub struct WriteRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub timeseries: Vec<TimeSeries>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct TimeSeries {
+    #[prost(message, repeated, tag = "1")]
+    pub labels: Vec<Label>,
+    #[prost(message, repeated, tag = "2")]
+    pub samples: Vec<Sample>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Label {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub value: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Sample {
+    #[prost(double, tag = "1")]
+    pub value: f64,
+    // milliseconds since the unix epoch
+    #[prost(int64, tag = "2")]
+    pub timestamp: i64,
+}
+
+//one series per metric that has a value this poll; labels are sorted by name as the protocol requires
+pub fn build_write_request(
+    ts: DateTime<Utc>,
+    device: Option<&str>,
+    m: &ExtractedMetrics,
+    state: &MonitorState,
+) -> WriteRequest {
+    let values = [
+        ("bitaxe_displayed_all_time", Some(m.displayed_all_time)),
+        ("bitaxe_displayed_boot_best", Some(m.displayed_boot_best)),
+        ("bitaxe_uptime_seconds", m.uptime_secs.map(|v| v as f64)),
+        ("bitaxe_hashrate_ths", m.hashrate_ths),
+        ("bitaxe_efficiency_j_per_th", m.efficiency_j_per_th),
+        (
+            "bitaxe_tool_global_all_time_best",
+            Some(state.tool_global_all_time_best),
+        ),
+        ("bitaxe_best_hashrate_ths", state.tool_best_hashrate_ths),
+        (
+            "bitaxe_best_efficiency_j_per_th",
+            state.tool_best_efficiency_j_per_th,
+        ),
+    ];
+    let timestamp = ts.timestamp_millis();
+    let timeseries = values
+        .into_iter()
+        .filter_map(|(name, v)| v.filter(|v| v.is_finite()).map(|v| (name, v)))
+        .map(|(name, value)| {
+            let mut labels = vec![Label {
+                name: "__name__".to_string(),
+                value: name.to_string(),
+            }];
+            if let Some(d) = device {
+                labels.push(Label {
+                    name: "device".to_string(),
+                    value: d.to_string(),
+                });
+            }
+            TimeSeries {
+                labels,
+                samples: vec![Sample { value, timestamp }],
+            }
+        })
+        .collect();
+    WriteRequest { timeseries }
+}
+
+//remote-write bodies are protobuf compressed with snappy's raw (block) format, not the framed one
+pub fn encode_body(req: &WriteRequest) -> Result<Vec<u8>> {
+    snap::raw::Encoder::new()
+        .compress_vec(&req.encode_to_vec())
+        .context("snappy compression failed")
+}
+
+pub async fn push(client: &Client, cfg: &RemoteWriteConfig, body: Vec<u8>) -> Result<()> {
+    let mut req = client
+        .post(&cfg.url)
+        .header("Content-Type", "application/x-protobuf")
+        .header("Content-Encoding", "snappy")
+        .header("X-Prometheus-Remote-Write-Version", "0.1.0")
+        .body(body);
+    if let Some(token) = &cfg.bearer_token {
+        req = req.bearer_auth(token);
+    } else if let Some(user) = &cfg.username {
+        req = req.basic_auth(user, cfg.password.as_deref());
+    }
+    req.send().await?.error_for_status()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_body_is_snappy_compressed_write_request() {
+        let m = ExtractedMetrics {
+            displayed_all_time: 12.0,
+            displayed_boot_best: 9.0,
+            uptime_secs: Some(300),
+            boot_id: None,
+            boot_time: None,
+            voltage_mv: None,
+            frequency_mhz: None,
+            hashrate_ths: Some(1.6),
+            efficiency_j_per_th: None,
+            sample_time: None,
+            best_difficulty: None,
+            best_difficulty_display: None,
+            session_difficulty: None,
+            session_difficulty_display: None,
+            temp_c: None,
+            temp_c_raw: None,
+            power_w: None,
+            shares_accepted: None,
+            shares_rejected: None,
+        };
+        let mut state = MonitorState::new();
+        state.tool_global_all_time_best = 12.0;
+        let ts = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+
+        let body = encode_body(&build_write_request(ts, Some("garage"), &m, &state)).unwrap();
+        let raw = snap::raw::Decoder::new().decompress_vec(&body).unwrap();
+        let decoded = WriteRequest::decode(raw.as_slice()).unwrap();
+
+        // efficiency and the unset bests are skipped rather than sent as zero
+        let names: Vec<&str> = decoded
+            .timeseries
+            .iter()
+            .map(|t| t.labels[0].value.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "bitaxe_displayed_all_time",
+                "bitaxe_displayed_boot_best",
+                "bitaxe_uptime_seconds",
+                "bitaxe_hashrate_ths",
+                "bitaxe_tool_global_all_time_best",
+            ]
+        );
+        let hashrate = &decoded.timeseries[3];
+        assert_eq!(hashrate.labels[0].name, "__name__");
+        assert_eq!(hashrate.labels[1].name, "device");
+        assert_eq!(hashrate.labels[1].value, "garage");
+        assert_eq!(hashrate.samples[0].value, 1.6);
+        assert_eq!(hashrate.samples[0].timestamp, 1_700_000_000_000);
+    }
+}
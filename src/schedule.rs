@@ -0,0 +1,246 @@
+use crate::config::{AdaptiveIntervalConfig, FailureBackoffConfig};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+//computes the sleep before the next poll: grows while the device is quiet, snaps back on any event
+#[derive(Debug, Clone)]
+pub struct AdaptiveInterval {
+    base: Duration,
+    // lowest delay ever returned, see config::min_poll_interval_secs
+    min: Duration,
+    max: Duration,
+    factor: f64,
+    stable_polls: u32,
+    current: Duration,
+    stable_count: u32,
+}
+
+impl AdaptiveInterval {
+    //without adaptive config the interval stays fixed at the base; no delay is ever shorter than min
+    pub fn new(base: Duration, cfg: Option<&AdaptiveIntervalConfig>, min: Duration) -> Self {
+        let base = base.max(min);
+        let (max, factor, stable_polls) = match cfg {
+            Some(c) => (
+                Duration::from_secs(c.max_interval_secs).max(base),
+                c.factor.unwrap_or(2.0),
+                c.stable_polls.unwrap_or(5),
+            ),
+            None => (base, 1.0, u32::MAX),
+        };
+        Self {
+            base,
+            min,
+            max,
+            factor,
+            stable_polls,
+            current: base,
+            stable_count: 0,
+        }
+    }
+
+    pub fn current(&self) -> Duration {
+        self.current
+    }
+
+    //feed the result of one poll and get the delay before the next one
+    pub fn record(&mut self, had_event: bool) -> Duration {
+        if had_event {
+            self.stable_count = 0;
+            self.current = self.base;
+            return self.current;
+        }
+        self.stable_count = self.stable_count.saturating_add(1);
+        if self.stable_count >= self.stable_polls {
+            self.stable_count = 0;
+            self.current = self
+                .current
+                .mul_f64(self.factor)
+                .min(self.max)
+                .max(self.min);
+        }
+        self.current
+    }
+}
+
+//widens the delay after consecutive failed polls (scheduled * factor^failures, capped at max) so an
+//offline device is not hit every few seconds; the first success returns to the normal schedule
+#[derive(Debug, Clone)]
+pub struct FailureBackoff {
+    max: Duration,
+    factor: f64,
+    failures: u32,
+}
+
+impl FailureBackoff {
+    pub fn new(cfg: &FailureBackoffConfig) -> Self {
+        Self {
+            max: Duration::from_secs(cfg.max_interval_secs),
+            factor: cfg.factor.unwrap_or(2.0),
+            failures: 0,
+        }
+    }
+
+    pub fn record(&mut self, ok: bool) {
+        self.failures = if ok {
+            0
+        } else {
+            self.failures.saturating_add(1)
+        };
+    }
+
+    //never shorter than the scheduled delay, even when that is above max
+    pub fn delay(&self, scheduled: Duration) -> Duration {
+        if self.failures == 0 {
+            return scheduled;
+        }
+        let widened = scheduled.as_secs_f64() * self.factor.powi(self.failures.min(64) as i32);
+        Duration::from_secs_f64(widened.min(self.max.as_secs_f64())).max(scheduled)
+    }
+}
+
+//heartbeat_interval_secs on the monitor's clock; the first one is due a period after start since
+//service_start already marks the start
+#[derive(Debug, Clone)]
+pub struct Heartbeat {
+    period: chrono::Duration,
+    next: DateTime<Utc>,
+}
+
+impl Heartbeat {
+    pub fn new(period: Duration, now: DateTime<Utc>) -> Self {
+        let period = chrono::Duration::from_std(period).unwrap_or(chrono::Duration::MAX);
+        Self {
+            period,
+            next: now
+                .checked_add_signed(period)
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+        }
+    }
+
+    //how long to wait before checking again
+    pub fn until_due(&self, now: DateTime<Utc>) -> Duration {
+        (self.next - now).to_std().unwrap_or(Duration::ZERO)
+    }
+
+    //true once the period has passed; after a long stall the next one is a full period away
+    //rather than a burst of the missed ones
+    pub fn due(&mut self, now: DateTime<Utc>) -> bool {
+        if now < self.next {
+            return false;
+        }
+        self.next = now
+            .checked_add_signed(self.period)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adaptive() -> AdaptiveInterval {
+        let cfg = AdaptiveIntervalConfig {
+            max_interval_secs: 60,
+            factor: Some(2.0),
+            stable_polls: Some(3),
+        };
+        AdaptiveInterval::new(Duration::from_secs(10), Some(&cfg), Duration::from_secs(2))
+    }
+
+    #[test]
+    fn test_interval_grows_while_stable_and_caps() {
+        let mut s = adaptive();
+        assert_eq!(s.current(), Duration::from_secs(10));
+        // two quiet polls are not enough yet
+        s.record(false);
+        assert_eq!(s.record(false), Duration::from_secs(10));
+        // third quiet poll doubles
+        assert_eq!(s.record(false), Duration::from_secs(20));
+        for _ in 0..3 {
+            s.record(false);
+        }
+        assert_eq!(s.current(), Duration::from_secs(40));
+        // growth stops at the configured max
+        for _ in 0..6 {
+            s.record(false);
+        }
+        assert_eq!(s.current(), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_interval_resets_on_event() {
+        let mut s = adaptive();
+        for _ in 0..6 {
+            s.record(false);
+        }
+        assert_eq!(s.current(), Duration::from_secs(40));
+        assert_eq!(s.record(true), Duration::from_secs(10));
+        // stability count restarts after an event
+        s.record(false);
+        s.record(false);
+        assert_eq!(s.current(), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_fixed_without_config() {
+        let mut s = AdaptiveInterval::new(Duration::from_secs(10), None, Duration::from_secs(2));
+        for _ in 0..100 {
+            assert_eq!(s.record(false), Duration::from_secs(10));
+        }
+    }
+
+    #[test]
+    fn test_interval_never_below_floor() {
+        // a base under the floor is raised to it, and resets snap back to the floor, not the base
+        let cfg = AdaptiveIntervalConfig {
+            max_interval_secs: 60,
+            factor: Some(2.0),
+            stable_polls: Some(1),
+        };
+        let mut s =
+            AdaptiveInterval::new(Duration::from_secs(1), Some(&cfg), Duration::from_secs(5));
+        assert_eq!(s.current(), Duration::from_secs(5));
+        assert_eq!(s.record(false), Duration::from_secs(10));
+        assert_eq!(s.record(true), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_failure_backoff_doubles_to_cap_and_resets() {
+        let mut b = FailureBackoff::new(&FailureBackoffConfig {
+            max_interval_secs: 300,
+            factor: None,
+        });
+        let base = Duration::from_secs(5);
+        assert_eq!(b.delay(base), base);
+        let mut delays = Vec::new();
+        for _ in 0..8 {
+            b.record(false);
+            delays.push(b.delay(base).as_secs());
+        }
+        assert_eq!(delays, [10, 20, 40, 80, 160, 300, 300, 300]);
+        b.record(true);
+        assert_eq!(b.delay(base), base);
+    }
+
+    #[test]
+    fn test_heartbeat_due_once_per_period_on_the_clock() {
+        use crate::clock::{Clock, FixedClock};
+        let clock = FixedClock::new(DateTime::from_timestamp(1_700_000_000, 0).unwrap());
+        let mut hb = Heartbeat::new(Duration::from_secs(60), clock.now());
+        // nothing at start: service_start covers it
+        assert!(!hb.due(clock.now()));
+        clock.advance(chrono::Duration::seconds(59));
+        assert!(!hb.due(clock.now()));
+        assert_eq!(hb.until_due(clock.now()), Duration::from_secs(1));
+        clock.advance(chrono::Duration::seconds(1));
+        assert!(hb.due(clock.now()));
+        assert!(!hb.due(clock.now()));
+        // a stall of several periods gives one heartbeat, then the normal cadence
+        clock.advance(chrono::Duration::seconds(300));
+        assert_eq!(hb.until_due(clock.now()), Duration::ZERO);
+        assert!(hb.due(clock.now()));
+        assert!(!hb.due(clock.now()));
+        assert_eq!(hb.until_due(clock.now()), Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,56 @@
+use crate::metrics::MonitorState;
+use anyhow::{anyhow, Result};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+//the monitor's own memory and cpu use, for spotting leaks in long runs. only built with
+//self_metrics = true, so a default monitor never scans /proc
+pub struct SelfMetrics {
+    system: System,
+    pid: Pid,
+}
+
+impl SelfMetrics {
+    pub fn new() -> Result<Self> {
+        let pid = sysinfo::get_current_pid().map_err(|e| anyhow!("self_metrics: {}", e))?;
+        let mut sampler = Self {
+            system: System::new(),
+            pid,
+        };
+        //cpu use is measured between two refreshes; this one is the starting point
+        sampler.refresh();
+        Ok(sampler)
+    }
+
+    fn refresh(&mut self) {
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[self.pid]),
+            false,
+            ProcessRefreshKind::nothing().with_memory().with_cpu(),
+        );
+    }
+
+    //resident memory in bytes and cpu use (percent of one core) since the previous sample, copied
+    //into the live state for heartbeat events and the exporter
+    pub fn sample(&mut self, state: &mut MonitorState) {
+        self.refresh();
+        let process = self.system.process(self.pid);
+        state.process_rss_bytes = process.map(|p| p.memory());
+        state.process_cpu_percent = process.map(|p| f64::from(p.cpu_usage()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_reports_own_process() {
+        let mut state = MonitorState::new();
+        let mut sampler = SelfMetrics::new().unwrap();
+        // some work between the refreshes so there is cpu time to measure
+        let _ = (0..2_000_000u64).fold(0u64, |acc, i| acc.wrapping_add(i * i));
+        sampler.sample(&mut state);
+        assert!(state.process_rss_bytes.unwrap() > 0);
+        assert!(state.process_cpu_percent.unwrap() >= 0.0);
+    }
+}
@@ -0,0 +1,85 @@
+use crate::clock::Clock;
+use crate::config::JsonPointers;
+use crate::metrics::{
+    detect_changes, extract_tracked_metrics, DetectionOutcome, MonitorState, Thresholds,
+    TrackedMetrics,
+};
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+//feed recorded device responses (one full json payload per line) through extraction and detection in
+//order, against a fresh in-memory state; nothing is fetched, slept on or written. Returns the line
+//number and outcome of each step; blank lines are skipped. bests are stamped with the clock's time
+pub fn replay(
+    text: &str,
+    pointers: &JsonPointers,
+    tracked: TrackedMetrics,
+    thresholds: Thresholds,
+    clock: &dyn Clock,
+) -> Result<Vec<(usize, DetectionOutcome)>> {
+    let mut state = MonitorState::new();
+    let mut steps = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line_no = idx + 1;
+        let json: Value = serde_json::from_str(line)
+            .with_context(|| format!("line {} is not valid json", line_no))?;
+        let extracted = extract_tracked_metrics(&json, pointers, tracked)
+            .with_context(|| format!("line {}: failed extracting metrics", line_no))?;
+        let (displayed, metrics) = extracted.detection_inputs();
+        steps.push((
+            line_no,
+            detect_changes(&mut state, displayed, metrics, thresholds, clock.now()),
+        ));
+    }
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+
+    #[test]
+    fn test_replay_detects_reboot_from_uptime_drop() {
+        let clock = FixedClock::new(chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap());
+        let ptrs = JsonPointers {
+            json_pointer_all_time: "/best".into(),
+            json_pointer_boot_best: "/session".into(),
+            json_pointer_uptime_secs: Some("/uptime".into()),
+            ..Default::default()
+        };
+        let text = concat!(
+            "{\"best\": 10, \"session\": 4, \"uptime\": 600}\n",
+            "\n",
+            "{\"best\": 12, \"session\": 12, \"uptime\": 1200}\n",
+            "{\"best\": 12, \"session\": 1, \"uptime\": 30}\n",
+        );
+        let steps = replay(
+            text,
+            &ptrs,
+            TrackedMetrics::default(),
+            Thresholds::from_config(None),
+            &clock,
+        )
+        .unwrap();
+        let lines: Vec<usize> = steps.iter().map(|(n, _)| *n).collect();
+        assert_eq!(lines, [1, 3, 4]);
+        assert!(!steps[1].1.boot_detected);
+        assert_eq!(steps[1].1.new_tool_all_time_best, Some(12.0));
+        assert!(steps[2].1.boot_detected);
+
+        // a bad line is named rather than skipped
+        let err = replay(
+            "{\"best\": 1, \"session\": 1, \"uptime\": 5}\nnot json\n",
+            &ptrs,
+            TrackedMetrics::default(),
+            Thresholds::from_config(None),
+            &clock,
+        )
+        .unwrap_err();
+        assert!(format!("{}", err).contains("line 2 is not valid json"));
+    }
+}
@@ -0,0 +1,163 @@
+use crate::metrics::MonitorState;
+use crate::persist::EventSink;
+use anyhow::{Context, Result};
+use rusqlite::{params, types::Value as SqlValue, Connection, OptionalExtension};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub const DEFAULT_DB_PATH: &str = "bitaxe_monitor.db";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS events (
+    id INTEGER PRIMARY KEY,
+    ts TEXT,
+    event TEXT,
+    value,
+    raw_json TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS events_event_ts ON events(event, ts);
+CREATE TABLE IF NOT EXISTS state (
+    path TEXT PRIMARY KEY,
+    json TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+";
+
+//events and state in one sqlite file; state rows are keyed by the resolved state_path so dated
+//paths roll over the same way as with json files
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open(db_path: &str) -> Result<Self> {
+        if let Some(parent) = Path::new(db_path).parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        let conn = Connection::open(db_path)
+            .with_context(|| format!("failed to open sqlite database {}", db_path))?;
+        //events and state may be written from separate connections; wait instead of failing
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn save_state(&self, key: &str, state: &MonitorState) -> Result<()> {
+        let json = serde_json::to_string(state)?;
+        self.conn
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .execute(
+                "INSERT INTO state (path, json, updated_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(path) DO UPDATE SET json = excluded.json, updated_at = excluded.updated_at",
+                params![key, json, chrono::Utc::now().to_rfc3339()],
+            )?;
+        Ok(())
+    }
+
+    pub fn load_state(&self, key: &str) -> Result<Option<MonitorState>> {
+        let json: Option<String> = self
+            .conn
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .query_row("SELECT json FROM state WHERE path = ?1", [key], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        json.map(|j| serde_json::from_str(&j).map_err(Into::into))
+            .transpose()
+    }
+}
+
+//numbers and strings keep their sqlite type so value can be compared and sorted in queries
+fn sql_value(v: Option<&Value>) -> SqlValue {
+    match v {
+        None | Some(Value::Null) => SqlValue::Null,
+        Some(Value::Number(n)) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => n.as_f64().map(SqlValue::Real).unwrap_or(SqlValue::Null),
+        },
+        Some(Value::String(s)) => SqlValue::Text(s.clone()),
+        Some(other) => SqlValue::Text(other.to_string()),
+    }
+}
+
+impl EventSink for SqliteStore {
+    fn append(&self, event: &Value) -> Result<()> {
+        //epoch timestamps (storage.timestamp_format) are stored as their decimal text
+        let text = |key: &str| match event.get(key) {
+            Some(Value::String(s)) => Some(s.clone()),
+            Some(Value::Number(n)) => Some(n.to_string()),
+            _ => None,
+        };
+        self.conn
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .execute(
+                "INSERT INTO events (ts, event, value, raw_json) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    text("ts"),
+                    text("event"),
+                    sql_value(event.get("value")),
+                    event.to_string()
+                ],
+            )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_events_and_state_roundtrip() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let db = dir.path().join("monitor.db").to_string_lossy().to_string();
+        let store = SqliteStore::open(&db).unwrap();
+
+        store
+            .append(&serde_json::json!({"ts": "2024-01-01T00:00:00Z", "event": "service_start"}))
+            .unwrap();
+        store
+            .append(&serde_json::json!({
+                "ts": "2024-01-01T00:00:10Z",
+                "event": "new_tool_best_hashrate_ths",
+                "value": 1.25
+            }))
+            .unwrap();
+        let conn = store.conn.lock().unwrap();
+        let (event, value, raw): (String, f64, String) = conn
+            .query_row(
+                "SELECT event, value, raw_json FROM events WHERE value IS NOT NULL",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(event, "new_tool_best_hashrate_ths");
+        assert_eq!(value, 1.25);
+        assert!(raw.contains("\"value\":1.25"));
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM events", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(count, 2);
+        drop(conn);
+
+        assert!(store.load_state("state.json").unwrap().is_none());
+        let mut s = MonitorState::new();
+        s.tool_global_all_time_best = 7.0;
+        store.save_state("state.json", &s).unwrap();
+        s.tool_global_all_time_best = 8.0;
+        store.save_state("state.json", &s).unwrap();
+        // a second connection (as after a restart) sees the latest row
+        let reopened = SqliteStore::open(&db).unwrap();
+        let loaded = reopened.load_state("state.json").unwrap().unwrap();
+        assert_eq!(loaded.tool_global_all_time_best, 8.0);
+    }
+}
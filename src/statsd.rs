@@ -0,0 +1,123 @@
+use crate::config::StatsdConfig;
+use crate::metrics::ExtractedMetrics;
+use anyhow::{Context, Result};
+use std::fmt::Write;
+use tokio::net::UdpSocket;
+
+//one "<prefix>.<name>:<value>|g" gauge per line for the metrics present this poll; an empty prefix
+//leaves the bare name
+pub fn format_gauges(prefix: &str, m: &ExtractedMetrics) -> String {
+    let mut out = String::new();
+    for (name, value) in [
+        ("hashrate_ths", m.hashrate_ths),
+        ("efficiency_j_per_th", m.efficiency_j_per_th),
+        ("temp_c", m.temp_c),
+        ("power_w", m.power_w),
+    ] {
+        let Some(v) = value.filter(|v| v.is_finite()) else {
+            continue;
+        };
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        if !prefix.is_empty() {
+            let _ = write!(out, "{}.", prefix);
+        }
+        let _ = write!(out, "{}:{}|g", name, v);
+    }
+    out
+}
+
+//udp socket connected to the collector; the address is resolved once at startup
+pub struct StatsdSink {
+    socket: UdpSocket,
+    prefix: String,
+}
+
+impl StatsdSink {
+    //an address that does not resolve fails startup; everything after that is best-effort
+    pub async fn connect(cfg: &StatsdConfig) -> Result<Self> {
+        let target = tokio::net::lookup_host(&cfg.addr)
+            .await
+            .with_context(|| format!("failed to resolve statsd.addr {}", cfg.addr))?
+            .next()
+            .with_context(|| format!("no addresses found for statsd.addr {}", cfg.addr))?;
+        let local = if target.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(local)
+            .await
+            .context("failed to bind statsd socket")?;
+        socket
+            .connect(target)
+            .await
+            .with_context(|| format!("failed to connect statsd socket to {}", target))?;
+        Ok(Self {
+            socket,
+            prefix: cfg.prefix.clone().unwrap_or_else(|| "bitaxe".to_string()),
+        })
+    }
+
+    //all gauges go out in one datagram; udp is fire-and-forget, so a failed send is only logged at
+    //debug level and never fails the poll
+    pub async fn send(&self, m: &ExtractedMetrics) {
+        let payload = format_gauges(&self.prefix, m);
+        if payload.is_empty() {
+            return;
+        }
+        if let Err(err) = self.socket.send(payload.as_bytes()).await {
+            tracing::debug!("statsd send failed: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> ExtractedMetrics {
+        ExtractedMetrics {
+            hashrate_ths: Some(1.65),
+            efficiency_j_per_th: Some(16.5),
+            temp_c: Some(58.0),
+            power_w: None,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_format_gauges() {
+        assert_eq!(
+            format_gauges("bitaxe.garage", &sample()),
+            "bitaxe.garage.hashrate_ths:1.65|g\nbitaxe.garage.efficiency_j_per_th:16.5|g\nbitaxe.garage.temp_c:58|g"
+        );
+        let only_power = ExtractedMetrics {
+            power_w: Some(f64::NAN),
+            ..Default::default()
+        };
+        // nothing finite to report means nothing to send
+        assert_eq!(format_gauges("bitaxe", &only_power), "");
+        let bare = ExtractedMetrics {
+            power_w: Some(14.2),
+            ..Default::default()
+        };
+        assert_eq!(format_gauges("", &bare), "power_w:14.2|g");
+    }
+
+    #[tokio::test]
+    async fn test_sink_sends_one_datagram() {
+        let collector = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let cfg = StatsdConfig {
+            addr: collector.local_addr().unwrap().to_string(),
+            prefix: None,
+        };
+        let sink = StatsdSink::connect(&cfg).await.unwrap();
+        sink.send(&sample()).await;
+        let mut buf = [0u8; 512];
+        let n = collector.recv(&mut buf).await.unwrap();
+        let text = std::str::from_utf8(&buf[..n]).unwrap();
+        assert!(text.starts_with("bitaxe.hashrate_ths:1.65|g\n"), "{}", text);
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+//records queued per client before that client starts missing records
+const CLIENT_BUFFER: usize = 64;
+
+//fan-out of per-poll records; every connected client gets its own bounded channel
+#[derive(Debug, Clone, Default)]
+pub struct StreamHub {
+    clients: Arc<Mutex<Vec<mpsc::Sender<String>>>>,
+}
+
+impl StreamHub {
+    //a full channel means that client is not keeping up: it misses this record, the others do not;
+    //clients that disconnected are removed
+    pub fn publish(&self, record: &Value) {
+        let line = record.to_string();
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        clients.retain(|tx| !matches!(tx.try_send(line.clone()), Err(TrySendError::Closed(_))));
+    }
+
+    fn subscribe(&self) -> mpsc::Receiver<String> {
+        let (tx, rx) = mpsc::channel(CLIENT_BUFFER);
+        self.clients
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(tx);
+        rx
+    }
+}
+
+//bind before returning so a bad socket path fails startup; a stale socket file left by a previous
+//run is removed first
+#[cfg(unix)]
+pub async fn spawn(socket_path: &str, hub: StreamHub) -> Result<()> {
+    use anyhow::Context;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::UnixListener;
+
+    if std::fs::symlink_metadata(socket_path).is_ok() {
+        std::fs::remove_file(socket_path)
+            .with_context(|| format!("failed to remove stale socket {}", socket_path))?;
+    }
+    let listener = UnixListener::bind(socket_path)
+        .with_context(|| format!("failed to bind stream socket {}", socket_path))?;
+    tokio::spawn(async move {
+        loop {
+            let (mut sock, _) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    eprintln!("[bitaxe_monitor] WARN: stream socket accept failed: {err}");
+                    continue;
+                }
+            };
+            let mut rx = hub.subscribe();
+            tokio::spawn(async move {
+                while let Some(line) = rx.recv().await {
+                    //a write error means the client went away; dropping rx unregisters it
+                    if sock.write_all(line.as_bytes()).await.is_err()
+                        || sock.write_all(b"\n").await.is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn spawn(_socket_path: &str, _hub: StreamHub) -> Result<()> {
+    anyhow::bail!("stream_socket_path requires unix domain sockets, which this platform lacks")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    use tokio::net::UnixStream;
+
+    #[tokio::test]
+    async fn test_client_receives_streamed_records() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("bitaxe.sock");
+        let path_str = path.to_string_lossy().to_string();
+        let hub = StreamHub::default();
+        spawn(&path_str, hub.clone()).await.unwrap();
+
+        let sock = UnixStream::connect(&path).await.unwrap();
+        // wait until the accept loop has registered the client
+        for _ in 0..100 {
+            if !hub.clients.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        hub.publish(&serde_json::json!({ "hashrate_ths": 1.1 }));
+        hub.publish(&serde_json::json!({ "hashrate_ths": 1.2 }));
+
+        let mut lines = BufReader::new(sock).lines();
+        let first: Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        let second: Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(first["hashrate_ths"], 1.1);
+        assert_eq!(second["hashrate_ths"], 1.2);
+
+        // once the client is gone, the next publish drops it
+        drop(lines);
+        for _ in 0..100 {
+            hub.publish(&serde_json::json!({ "hashrate_ths": 1.3 }));
+            if hub.clients.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(hub.clients.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_slow_client_misses_records_without_blocking_others() {
+        let hub = StreamHub::default();
+        let mut slow = hub.subscribe();
+        let mut fast = hub.subscribe();
+        for i in 0..CLIENT_BUFFER + 5 {
+            hub.publish(&serde_json::json!({ "n": i }));
+            // the fast client keeps draining
+            assert!(fast.try_recv().is_ok());
+        }
+        let mut received = 0;
+        while slow.try_recv().is_ok() {
+            received += 1;
+        }
+        assert_eq!(received, CLIENT_BUFFER);
+        assert_eq!(hub.clients.lock().unwrap().len(), 2);
+    }
+}
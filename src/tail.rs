@@ -0,0 +1,160 @@
+use crate::config::EventSeverity;
+use crate::persist::event_severity;
+use anyhow::Result;
+use serde_json::Value;
+use std::fs::File;
+use std::io::{IsTerminal, Read, Seek, SeekFrom};
+use std::time::Duration;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+//errors red, warnings yellow, new bests/extremes green, everything else uncolored
+fn color_for(event: &Value) -> Option<&'static str> {
+    let name = event.get("event").and_then(Value::as_str).unwrap_or("");
+    match event_severity(event) {
+        EventSeverity::Error => Some(RED),
+        EventSeverity::Warn => Some(YELLOW),
+        _ if name.starts_with("new_") => Some(GREEN),
+        _ => None,
+    }
+}
+
+//"<ts>  <event>  key=value ..." on one line; lines that are not json objects are passed through
+pub fn format_line(line: &str, color: bool) -> String {
+    let Ok(event) = serde_json::from_str::<Value>(line) else {
+        return line.to_string();
+    };
+    let Some(obj) = event.as_object() else {
+        return line.to_string();
+    };
+    let field = |v: &Value| match v {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let ts = obj.get("ts").map(field).unwrap_or_default();
+    let name = obj.get("event").map(field).unwrap_or_default();
+    let rest: Vec<String> = obj
+        .iter()
+        .filter(|(k, _)| *k != "ts" && *k != "event")
+        .map(|(k, v)| format!("{}={}", k, field(v)))
+        .collect();
+    let head = match color_for(&event).filter(|_| color) {
+        Some(c) => format!("{}{}{}", c, name, RESET),
+        None => name,
+    };
+    if rest.is_empty() {
+        format!("{}  {}", ts, head)
+    } else {
+        format!("{}  {}  {}", ts, head, rest.join(" "))
+    }
+}
+
+//complete lines appended after offset; a trailing partial line is left for the next read and a
+//file that shrank (rotated or truncated) is read again from the start
+pub fn read_new_lines(path: &str, offset: u64) -> Result<(Vec<String>, u64)> {
+    let mut f = match File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+        Err(e) => return Err(e.into()),
+    };
+    let len = f.metadata()?.len();
+    let start = if len < offset { 0 } else { offset };
+    f.seek(SeekFrom::Start(start))?;
+    let mut buf = String::new();
+    f.read_to_string(&mut buf)?;
+    let complete = buf.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let lines = buf[..complete]
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(str::to_string)
+        .collect();
+    Ok((lines, start + complete as u64))
+}
+
+//identity of the file behind the path, so a rotation that replaced it is noticed even when the new
+//file has already grown past the old offset
+#[cfg(unix)]
+fn file_id(path: &str) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|m| m.ino())
+}
+
+#[cfg(not(unix))]
+fn file_id(_path: &str) -> Option<u64> {
+    None
+}
+
+//print events appended after startup until interrupted; only reads the file, so it can run
+//alongside the monitor
+pub async fn follow(path: &str, poll: Duration) -> Result<()> {
+    let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let mut offset = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let mut id = file_id(path);
+    println!("[bitaxe_monitor] following {} (Ctrl+C to stop)", path);
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(poll) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+        let current = file_id(path);
+        if current != id {
+            id = current;
+            offset = 0;
+        }
+        let (lines, next) = read_new_lines(path, offset)?;
+        offset = next;
+        for line in lines {
+            println!("{}", format_line(&line, color));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_line_colors_by_type() {
+        let best =
+            r#"{"ts":"2024-01-02T03:04:05Z","event":"new_tool_best_hashrate_ths","value":1.25}"#;
+        assert_eq!(
+            format_line(best, false),
+            "2024-01-02T03:04:05Z  new_tool_best_hashrate_ths  value=1.25"
+        );
+        assert_eq!(
+            format_line(best, true),
+            "2024-01-02T03:04:05Z  \x1b[32mnew_tool_best_hashrate_ths\x1b[0m  value=1.25"
+        );
+        let err = r#"{"ts":"2024-01-02T03:04:05Z","event":"poll_error","error":"timed out"}"#;
+        assert!(format_line(err, true).contains("\x1b[31mpoll_error\x1b[0m  error=timed out"));
+        let stop = r#"{"ts":"2024-01-02T03:04:05Z","event":"service_stop"}"#;
+        assert_eq!(
+            format_line(stop, true),
+            "2024-01-02T03:04:05Z  service_stop"
+        );
+        assert_eq!(format_line("not json", true), "not json");
+    }
+
+    #[test]
+    fn test_read_new_lines_skips_partial_line() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("events.jsonl");
+        let path_str = path.to_string_lossy().to_string();
+        std::fs::write(&path, "{\"event\":\"a\"}\n{\"event\":\"b\"}\n{\"event\"").unwrap();
+
+        let (lines, offset) = read_new_lines(&path_str, 0).unwrap();
+        assert_eq!(lines, vec!["{\"event\":\"a\"}", "{\"event\":\"b\"}"]);
+
+        // the partial line is picked up once it is completed
+        std::fs::write(
+            &path,
+            "{\"event\":\"a\"}\n{\"event\":\"b\"}\n{\"event\":\"c\"}\n",
+        )
+        .unwrap();
+        let (lines, _) = read_new_lines(&path_str, offset).unwrap();
+        assert_eq!(lines, vec!["{\"event\":\"c\"}"]);
+    }
+}
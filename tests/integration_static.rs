@@ -1,6 +1,7 @@
 use bitaxe_monitor::config::JsonPointers;
 use bitaxe_monitor::metrics::{
-    detect_changes, extract_metrics_from_json, Displayed, Metrics, MonitorState, Thresholds,
+    detect_changes, extract_tracked_metrics, Displayed, Metrics, MonitorState, Thresholds,
+    TrackedMetrics,
 };
 
 #[test]
@@ -23,12 +24,12 @@ fn extractor_and_state_flow_over_static_json() {
         json_pointer_boot_id: Some("/boot_id".into()),
         json_pointer_hashrate_ths: Some("/hashrate".into()),
         json_pointer_efficiency_j_per_th: Some("/efficiency".into()),
-        json_pointer_power_w: None,
-        hashrate_scale: None,
+        ..Default::default()
     };
 
     // extract metrics
-    let m = extract_metrics_from_json(&json, &ptrs).expect("extract metrics");
+    let m =
+        extract_tracked_metrics(&json, &ptrs, TrackedMetrics::default()).expect("extract metrics");
     assert_eq!(m.uptime_secs, Some(300));
     assert_eq!(m.boot_id.as_deref(), Some("B1"));
 
@@ -43,12 +44,15 @@ fn extractor_and_state_flow_over_static_json() {
         boot_id: m.boot_id,
         hashrate_ths: m.hashrate_ths,
         efficiency_j_per_th: m.efficiency_j_per_th,
+        ..Default::default()
     };
     let thresholds = Thresholds {
         epsilon_hashrate_ths: 0.01,
         epsilon_efficiency_j_per_th: 0.01,
+        ..Default::default()
     };
-    let out = detect_changes(&mut state, displayed, metrics, thresholds);
+    let now = chrono::Utc::now();
+    let out = detect_changes(&mut state, displayed, metrics, thresholds, now);
 
     // first run should set initial bests
     assert!(out.new_device_all_time_best.is_some());